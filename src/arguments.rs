@@ -0,0 +1,356 @@
+use rmcp::model::JsonObject;
+
+/// What the server does when a tool call contains arguments that are not part of the tool's schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum UnknownArgumentsPolicy {
+    /// Run the tool and add a warning listing the ignored arguments
+    #[default]
+    Warn,
+    /// Fail the call with `invalid_params`
+    Reject,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct UnknownArgument {
+    pub(crate) name: String,
+    pub(crate) suggestion: Option<String>,
+}
+
+impl std::fmt::Display for UnknownArgument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}`", self.name)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, " (did you mean `{suggestion}`?)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns arguments that don't match any property of the tool's input schema.
+pub(crate) fn find_unknown_arguments(
+    schema: &serde_json::Map<String, serde_json::Value>,
+    arguments: Option<&JsonObject>,
+) -> Vec<UnknownArgument> {
+    let Some(arguments) = arguments else {
+        return Vec::new();
+    };
+    let known = known_arguments(schema);
+
+    let mut unknown: Vec<UnknownArgument> = arguments
+        .keys()
+        .filter(|name| !known.contains(&name.as_str()))
+        .map(|name| UnknownArgument {
+            name: name.clone(),
+            suggestion: closest_match(name, &known).map(str::to_owned),
+        })
+        .collect();
+    unknown.sort_by(|a, b| a.name.cmp(&b.name));
+    unknown
+}
+
+/// Error message for [`UnknownArgumentsPolicy::Reject`].
+pub(crate) fn unknown_arguments_error(
+    tool_name: &str,
+    unknown: &[UnknownArgument],
+    schema: &serde_json::Map<String, serde_json::Value>,
+) -> String {
+    let mut known = known_arguments(schema);
+    known.sort_unstable();
+    format!(
+        "Unknown argument(s) for {tool_name}: {}. Valid arguments: {}",
+        join(unknown),
+        known.join(", ")
+    )
+}
+
+/// Warning text for [`UnknownArgumentsPolicy::Warn`].
+pub(crate) fn unknown_arguments_warning(unknown: &[UnknownArgument]) -> String {
+    format!("Ignored unknown argument(s): {}", join(unknown))
+}
+
+fn join(unknown: &[UnknownArgument]) -> String {
+    unknown
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn known_arguments(schema: &serde_json::Map<String, serde_json::Value>) -> Vec<&str> {
+    match schema.get("properties") {
+        Some(serde_json::Value::Object(properties)) => {
+            properties.keys().map(String::as_str).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Finds the known argument closest to `name`, if it is close enough to be a likely typo.
+fn closest_match<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    known
+        .iter()
+        .map(|candidate| (levenshtein(name, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Coerces argument values that LLMs commonly mangle into the type declared by the tool's schema.
+///
+/// Handles JSON-encoded arrays passed as a string for array arguments, comma-separated lists for
+/// the arguments in [`COMMA_SEPARATED_ARGUMENTS`], `"true"`/`"false"` strings for booleans and
+/// numeric strings for integers.
+/// Returns a description of each coercion that was applied.
+pub(crate) fn coerce_arguments(
+    schema: &serde_json::Map<String, serde_json::Value>,
+    arguments: &mut JsonObject,
+) -> Vec<String> {
+    let Some(serde_json::Value::Object(properties)) = schema.get("properties") else {
+        return Vec::new();
+    };
+
+    let mut coercions = Vec::new();
+    for (name, value) in arguments.iter_mut() {
+        let serde_json::Value::String(text) = value else {
+            continue;
+        };
+        let Some(expected) = properties.get(name).and_then(expected_type) else {
+            continue;
+        };
+
+        let coerced = match expected {
+            "array" => coerce_to_array(text, COMMA_SEPARATED_ARGUMENTS.contains(&name.as_str())),
+            "boolean" => coerce_to_bool(text),
+            "integer" => coerce_to_integer(text),
+            _ => None,
+        };
+
+        if let Some(coerced) = coerced {
+            coercions.push(format!(
+                "Argument `{name}` was coerced from string {} to {expected} {coerced}",
+                serde_json::Value::String(text.clone())
+            ));
+            *value = coerced;
+        }
+    }
+    coercions
+}
+
+/// Returns the non-null type of a schema property, e.g. `"array"` for `["array", "null"]`.
+fn expected_type(property: &serde_json::Value) -> Option<&str> {
+    match property.get("type")? {
+        serde_json::Value::String(ty) => Some(ty.as_str()),
+        serde_json::Value::Array(types) => types
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .find(|ty| *ty != "null"),
+        _ => None,
+    }
+}
+
+/// Array arguments whose items are names that can't contain a comma, like features and packages.
+/// Others, e.g. program arguments, may contain commas, so a string is taken as a single item.
+const COMMA_SEPARATED_ARGUMENTS: &[&str] = &[
+    "features",
+    "package",
+    "exclude",
+    "exclude_features",
+    "include_features",
+    "optional_deps",
+];
+
+fn coerce_to_array(text: &str, comma_separated: bool) -> Option<serde_json::Value> {
+    let trimmed = text.trim();
+    if trimmed.starts_with('[') {
+        return match serde_json::from_str::<serde_json::Value>(trimmed) {
+            Ok(array @ serde_json::Value::Array(_)) => Some(array),
+            _ => None,
+        };
+    }
+
+    if comma_separated && trimmed.contains(',') {
+        let items = trimmed
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| serde_json::Value::String(item.to_owned()))
+            .collect();
+        return Some(serde_json::Value::Array(items));
+    }
+
+    None
+}
+
+fn coerce_to_bool(text: &str) -> Option<serde_json::Value> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" => Some(serde_json::Value::Bool(true)),
+        "false" => Some(serde_json::Value::Bool(false)),
+        _ => None,
+    }
+}
+
+fn coerce_to_integer(text: &str) -> Option<serde_json::Value> {
+    text.trim()
+        .parse::<u64>()
+        .ok()
+        .map(|number| serde_json::Value::Number(number.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> serde_json::Map<String, serde_json::Value> {
+        json!({
+            "type": "object",
+            "properties": {
+                "all_features": { "type": "boolean" },
+                "features": { "type": "array" },
+                "workspace": { "type": "boolean" }
+            }
+        })
+        .as_object()
+        .cloned()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("all_feature", "all_features"), 1);
+        assert_eq!(levenshtein("workspcae", "workspace"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_find_unknown_arguments() {
+        let args = json!({
+            "all_feature": true,
+            "workspace": true,
+            "verbose": true
+        });
+
+        let unknown = find_unknown_arguments(&schema(), args.as_object());
+        assert_eq!(
+            unknown,
+            [
+                UnknownArgument {
+                    name: "all_feature".into(),
+                    suggestion: Some("all_features".into())
+                },
+                UnknownArgument {
+                    name: "verbose".into(),
+                    suggestion: None
+                },
+            ]
+        );
+        assert_eq!(
+            unknown_arguments_warning(&unknown),
+            "Ignored unknown argument(s): `all_feature` (did you mean `all_features`?), `verbose`"
+        );
+        assert_eq!(
+            unknown_arguments_error("cargo-check", &unknown, &schema()),
+            "Unknown argument(s) for cargo-check: `all_feature` (did you mean `all_features`?), `verbose`. Valid arguments: all_features, features, workspace"
+        );
+    }
+
+    #[test]
+    fn test_coerce_arguments() {
+        let schema = json!({
+            "properties": {
+                "features": { "type": ["array", "null"], "items": { "type": "string" } },
+                "package": { "type": ["array", "null"], "items": { "type": "string" } },
+                "exclude": { "type": "array", "items": { "type": "string" } },
+                "args": { "type": ["array", "null"], "items": { "type": "string" } },
+                "test_args": { "type": ["array", "null"], "items": { "type": "string" } },
+                "workspace": { "type": ["boolean", "null"] },
+                "jobs": { "type": ["integer", "null"] },
+                "target": { "type": ["string", "null"] }
+            }
+        });
+        let mut args = json!({
+            "features": "[\"serde\",\"tokio\"]",
+            "package": "a, b,",
+            "exclude": "single",
+            "args": "--filter=a,b",
+            "test_args": "[\"--skip\", \"a,b\"]",
+            "workspace": "True",
+            "jobs": "4",
+            "target": "true"
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+
+        let coercions = coerce_arguments(schema.as_object().unwrap(), &mut args);
+
+        assert_eq!(
+            serde_json::Value::Object(args),
+            json!({
+                "features": ["serde", "tokio"],
+                "package": ["a", "b"],
+                "exclude": "single",
+                "args": "--filter=a,b",
+                "test_args": ["--skip", "a,b"],
+                "workspace": true,
+                "jobs": 4,
+                "target": "true"
+            })
+        );
+        assert_eq!(coercions.len(), 5);
+        assert!(
+            coercions.contains(
+                &r#"Argument `jobs` was coerced from string "4" to integer 4"#.to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn test_coerce_arguments_leaves_invalid_values() {
+        let schema = json!({
+            "properties": {
+                "features": { "type": ["array", "null"] },
+                "workspace": { "type": "boolean" },
+                "jobs": { "type": "integer" }
+            }
+        });
+        let mut args = json!({
+            "features": "[not json",
+            "workspace": "yes please",
+            "jobs": "-1"
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+        let original = args.clone();
+
+        assert!(coerce_arguments(schema.as_object().unwrap(), &mut args).is_empty());
+        assert_eq!(args, original);
+    }
+
+    #[test]
+    fn test_find_unknown_arguments_all_known() {
+        let args = json!({ "features": ["serde"], "workspace": false });
+        assert!(find_unknown_arguments(&schema(), args.as_object()).is_empty());
+        assert!(find_unknown_arguments(&schema(), None).is_empty());
+    }
+}
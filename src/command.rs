@@ -1,3 +1,11 @@
+use std::{
+    io::{Read, Write},
+    ops::ControlFlow,
+    process::{Child, Stdio},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
 use rmcp::{
     ErrorData,
     model::{AnnotateAble, Annotated, Annotations, CallToolResult, RawContent, RawTextContent},
@@ -77,7 +85,21 @@ impl From<AgentRecommendation> for Annotated<RawContent> {
     }
 }
 
-fn text_with_description(text: impl Into<String>, description: impl Into<String>) -> RawContent {
+pub(crate) struct Warning(pub String);
+
+impl From<Warning> for Annotated<RawContent> {
+    fn from(val: Warning) -> Self {
+        let content =
+            text_with_description(format!("WARNING: {}", val.0), "warning about the tool call");
+
+        content.annotate(Annotations::default())
+    }
+}
+
+pub(crate) fn text_with_description(
+    text: impl Into<String>,
+    description: impl Into<String>,
+) -> RawContent {
     RawContent::Text(RawTextContent {
         text: text.into(),
         meta: Some(Meta::new().with_description(description).into()),
@@ -126,6 +148,39 @@ impl Output {
     pub(crate) fn success(&self) -> bool {
         self.exit_status.0.success()
     }
+
+    /// Combines the outputs of commands a tool ran one after another into a single output, which
+    /// failed if any of them failed. Returns `None` if there are no outputs.
+    pub(crate) fn combine(outputs: Vec<Output>) -> Option<Output> {
+        let join = |parts: Vec<String>| {
+            let joined = parts.join("\n");
+            (!joined.is_empty()).then_some(joined)
+        };
+        let tool_name = outputs.first()?.tool_name.clone();
+        let exit_status = outputs
+            .iter()
+            .find(|output| !output.success())
+            .or(outputs.last())?
+            .exit_status
+            .clone();
+
+        let mut cmd_lines = Vec::new();
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        for output in outputs {
+            cmd_lines.push(output.cmd_line.0);
+            stdout.extend(output.stdout.map(|s| s.0));
+            stderr.extend(output.stderr.map(|s| s.0));
+        }
+
+        Some(Output {
+            tool_name,
+            cmd_line: CommandLine(cmd_lines.join("; ")),
+            stdout: join(stdout).map(Stdout),
+            stderr: join(stderr).map(Stderr),
+            exit_status,
+        })
+    }
 }
 
 impl From<Output> for CallToolResult {
@@ -158,48 +213,256 @@ pub(crate) fn execute_command(
 ) -> Result<Output, ErrorData> {
     apply_workspace_root(&mut cmd);
 
-    let cmd_line = format!(
+    let cmd_line = command_line(&cmd);
+    tracing::info!("Executing command for {tool_name}: {cmd_line}");
+    match cmd.output() {
+        Ok(output) => Ok(finish_command(tool_name, cmd_line, output)),
+        Err(e) => Err(spawn_error(&cmd, &cmd_line, e)),
+    }
+}
+
+/// What a watched command printed, or a tick for checks that depend on the time.
+pub(crate) enum Watched<'a> {
+    Stdout(&'a str),
+    Stderr(&'a str),
+    /// The start of a stdout line that has not ended yet, passed on each tick until it ends, e.g.
+    /// libtest's "test name ... " while a test runs on a single thread
+    UnfinishedStdout(&'a str),
+    Tick,
+}
+
+/// Interval of [`Watched::Tick`].
+const WATCH_TICK: Duration = Duration::from_millis(100);
+
+/// Runs the command like [`execute_command`], passing each line it prints to `watch` as soon as
+/// it is printed, and a tick at least every 100ms in between. Once `watch` breaks, the command
+/// and the processes it started are killed, and the output printed until then is returned.
+///
+/// `stdin` is written to the command's standard input, which is otherwise empty.
+pub(crate) fn execute_command_watched(
+    mut cmd: std::process::Command,
+    tool_name: &str,
+    stdin: Option<String>,
+    mut watch: impl FnMut(Watched) -> ControlFlow<()>,
+) -> Result<Output, ErrorData> {
+    apply_workspace_root(&mut cmd);
+
+    let cmd_line = command_line(&cmd);
+    tracing::info!("Executing watched command for {tool_name}: {cmd_line}");
+    cmd.stdin(if stdin.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped());
+    // Its own process group, so killing it also kills e.g. the test binaries cargo runs
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    let mut child = cmd.spawn().map_err(|e| spawn_error(&cmd, &cmd_line, e))?;
+
+    // Written from a thread, as the command may only read its input while its output is read
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        std::thread::spawn(move || {
+            // Dropping the pipe closes the input, the command may not have read all of it
+            let _ = pipe.write_all(input.as_bytes());
+        });
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|stdout| read_chunks(stdout, true, sender.clone())),
+        child
+            .stderr
+            .take()
+            .map(|stderr| read_chunks(stderr, false, sender)),
+    ];
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    // Where the line that has not ended yet starts in `stdout` and `stderr`
+    let mut line_starts = [0, 0];
+    let mut killed = false;
+    let mut last_tick = Instant::now();
+    loop {
+        let mut flow = ControlFlow::Continue(());
+        match receiver.recv_timeout(WATCH_TICK) {
+            Ok((is_stdout, chunk)) => {
+                let (output, line_start) = if is_stdout {
+                    (&mut stdout, &mut line_starts[0])
+                } else {
+                    (&mut stderr, &mut line_starts[1])
+                };
+                output.extend(chunk);
+                while let Some(end) = output[*line_start..].iter().position(|&b| b == b'\n') {
+                    let line = String::from_utf8_lossy(&output[*line_start..*line_start + end]);
+                    let line = line.trim_end_matches('\r');
+                    if flow.is_continue() && !killed {
+                        flow = if is_stdout {
+                            watch(Watched::Stdout(line))
+                        } else {
+                            watch(Watched::Stderr(line))
+                        };
+                    }
+                    *line_start += end + 1;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if flow.is_continue() && !killed && last_tick.elapsed() >= WATCH_TICK {
+            last_tick = Instant::now();
+            if line_starts[0] < stdout.len() {
+                flow = watch(Watched::UnfinishedStdout(&String::from_utf8_lossy(
+                    &stdout[line_starts[0]..],
+                )));
+            }
+            if flow.is_continue() {
+                flow = watch(Watched::Tick);
+            }
+        }
+        if flow.is_break() && !killed {
+            tracing::warn!("Killing watched command for {tool_name}: {cmd_line}");
+            kill_process_group(&mut child);
+            killed = true;
+        }
+    }
+    for reader in readers.into_iter().flatten() {
+        let _ = reader.join();
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+    let output = std::process::Output {
+        status,
+        stdout,
+        stderr,
+    };
+    Ok(finish_command(tool_name, cmd_line, output))
+}
+
+/// Sends what is read from `pipe` as soon as it is available, until the pipe is closed.
+fn read_chunks(
+    mut pipe: impl Read + Send + 'static,
+    is_stdout: bool,
+    sender: mpsc::Sender<(bool, Vec<u8>)>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if sender.send((is_stdout, buffer[..read].to_vec())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    signal_process_group(child.id(), "KILL");
+    let _ = child.kill();
+}
+
+/// Sends a signal, e.g. `TERM`, to the process group of a command started in its own group,
+/// returning whether `kill` succeeded.
+#[cfg(unix)]
+pub(crate) fn signal_process_group(pid: u32, signal: &str) -> bool {
+    std::process::Command::new("kill")
+        .args([&format!("-{signal}"), "--", &format!("-{pid}")])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+pub(crate) fn command_line(cmd: &std::process::Command) -> String {
+    format!(
         "{} {}",
         cmd.get_program().to_string_lossy(),
         cmd.get_args()
             .map(|arg| arg.to_string_lossy())
             .collect::<Vec<_>>()
             .join(" ")
-    );
+    )
+}
 
-    tracing::info!("Executing command for {tool_name}: {cmd_line}");
-    match cmd.output() {
-        Ok(output) => {
-            let output = Output::new(tool_name.to_owned(), cmd_line, output);
-            if output.success() {
-                tracing::info!(
-                    "Command executed successfully for {tool_name}\nstdout=\n{}\n\nstderr=\n{}",
-                    output.stdout.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
-                    output.stderr.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
-                );
-            } else {
-                tracing::warn!(
-                    "Command execution failed for {tool_name} (status: {:?}): stdout='\n{}\n', stderr='\n{}\n'",
-                    output.exit_status.0.code(),
-                    output.stdout.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
-                    output.stderr.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
-                );
+fn finish_command(tool_name: &str, cmd_line: String, output: std::process::Output) -> Output {
+    let output = Output::new(tool_name.to_owned(), cmd_line, output);
+    if output.success() {
+        tracing::info!(
+            "Command executed successfully for {tool_name}\nstdout=\n{}\n\nstderr=\n{}",
+            output.stdout.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
+            output.stderr.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
+        );
+    } else {
+        tracing::warn!(
+            "Command execution failed for {tool_name} (status: {:?}): stdout='\n{}\n', stderr='\n{}\n'",
+            output.exit_status.0.code(),
+            output.stdout.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
+            output.stderr.as_ref().map(|s| s.0.as_str()).unwrap_or(""),
+        );
+    }
+    output
+}
+
+pub(crate) fn spawn_error(
+    cmd: &std::process::Command,
+    cmd_line: &str,
+    e: std::io::Error,
+) -> ErrorData {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        tracing::error!("Command not found: {e}");
+        let program = cmd.get_program().to_string_lossy();
+        ErrorData::internal_error(
+            format!(
+                "The command `{program}` was not found, please ensure it is installed and accessible. You can try running the following command yourself to verify: `{cmd_line}`",
+            ),
+            None,
+        )
+    } else {
+        tracing::error!("Failed to execute command: {e}");
+        ErrorData::internal_error(e.to_string(), None)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_command_watched_kills_on_break() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "echo started; sleep 30; echo finished"]);
+        let start = Instant::now();
+        let mut lines = Vec::new();
+        let output = execute_command_watched(cmd, "test", None, |event| match event {
+            Watched::Stdout(line) => {
+                lines.push(line.to_owned());
+                ControlFlow::Break(())
             }
-            Ok(output)
-        }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            tracing::error!("Command not found: {e}");
-            let program = cmd.get_program().to_string_lossy();
-            Err(ErrorData::internal_error(
-                format!(
-                    "The command `{program}` was not found, please ensure it is installed and accessible. You can try running the following command yourself to verify: `{cmd_line}`",
-                ),
-                None,
-            ))
-        }
-        Err(e) => {
-            tracing::error!("Failed to execute command: {e}");
-            Err(ErrorData::internal_error(e.to_string(), None))
-        }
+            Watched::Stderr(_) | Watched::UnfinishedStdout(_) | Watched::Tick => {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(lines, ["started"]);
+        assert!(!output.success());
+        assert_eq!(output.stdout.unwrap().0, "started");
     }
 }
@@ -0,0 +1,243 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path},
+};
+
+use super::{Applicability, Edit, Suggestion, store};
+use crate::diff::unified_diff;
+
+/// A suggestion that was applied, or would be with `dry_run`.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct AppliedSuggestion {
+    pub(crate) id: String,
+    pub(crate) message: String,
+    pub(crate) applicability: Applicability,
+}
+
+/// A diagnostic whose suggestion couldn't be applied.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct SkippedSuggestion {
+    pub(crate) id: String,
+    pub(crate) reason: String,
+}
+
+#[derive(Debug, Default, serde::Serialize)]
+pub(crate) struct SuggestionsResult {
+    pub(crate) applied: Vec<AppliedSuggestion>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) skipped: Vec<SkippedSuggestion>,
+    /// Unified diff of all changed files
+    pub(crate) diff: String,
+}
+
+/// Applies the suggestions of the diagnostics with the given ids, as reported by the latest
+/// check-family runs.
+///
+/// Every edit is checked against the current file content first. Suggestions whose files changed
+/// since the diagnostic was reported, or that overlap a suggestion accepted before them, are
+/// skipped as a whole. Files are only written if `dry_run` is `false`.
+pub(crate) fn apply_suggestions(
+    root: &Path,
+    ids: &[String],
+    allow_maybe_incorrect: bool,
+    dry_run: bool,
+) -> std::io::Result<SuggestionsResult> {
+    let mut result = SuggestionsResult::default();
+    let mut files: HashMap<String, Option<String>> = HashMap::new();
+    let mut accepted: BTreeMap<String, Vec<Edit>> = BTreeMap::new();
+
+    for id in ids {
+        let suggestion = match choose_suggestion(id, allow_maybe_incorrect) {
+            Ok(suggestion) => suggestion,
+            Err(reason) => {
+                result.skipped.push(SkippedSuggestion {
+                    id: id.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+
+        let checked = suggestion.edits.iter().try_for_each(|edit| {
+            if !is_inside_workspace(&edit.file) {
+                return Err(format!("`{}` is outside the workspace", edit.file));
+            }
+            let content = files
+                .entry(edit.file.clone())
+                .or_insert_with(|| std::fs::read_to_string(root.join(&edit.file)).ok())
+                .as_deref()
+                .ok_or_else(|| format!("`{}` can't be read", edit.file))?;
+            check_edit(content, edit)?;
+
+            let overlaps = accepted.get(&edit.file).is_some_and(|edits| {
+                edits.iter().any(|other| {
+                    other != edit
+                        && edit.byte_start < other.byte_end
+                        && other.byte_start < edit.byte_end
+                })
+            });
+            if overlaps {
+                return Err("overlaps a suggestion applied in the same call".to_owned());
+            }
+            Ok(())
+        });
+        if let Err(reason) = checked {
+            result.skipped.push(SkippedSuggestion {
+                id: id.clone(),
+                reason,
+            });
+            continue;
+        }
+
+        for edit in &suggestion.edits {
+            let edits = accepted.entry(edit.file.clone()).or_default();
+            // Diagnostics reported for several targets can carry the same edit
+            if !edits.contains(edit) {
+                edits.push(edit.clone());
+            }
+        }
+        result.applied.push(AppliedSuggestion {
+            id: id.clone(),
+            message: suggestion.message,
+            applicability: suggestion.applicability,
+        });
+    }
+
+    for (file, mut edits) in accepted {
+        let Some(Some(original)) = files.remove(&file) else {
+            continue;
+        };
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.byte_start));
+        let mut content = original.clone();
+        for edit in &edits {
+            content.replace_range(edit.byte_start..edit.byte_end, &edit.replacement);
+        }
+
+        result
+            .diff
+            .push_str(&unified_diff(&file, &original, &content));
+        if !dry_run {
+            std::fs::write(root.join(&file), content)?;
+        }
+    }
+
+    Ok(result)
+}
+
+fn choose_suggestion(id: &str, allow_maybe_incorrect: bool) -> Result<Suggestion, String> {
+    let diagnostic = store::find_diagnostic(id).ok_or_else(|| {
+        "unknown id, it may be from an older run. Run the check again to get current ids".to_owned()
+    })?;
+
+    let allowed = |applicability| {
+        applicability == Applicability::MachineApplicable
+            || (allow_maybe_incorrect && applicability == Applicability::MaybeIncorrect)
+    };
+    if let Some(suggestion) = diagnostic
+        .suggestions
+        .iter()
+        .find(|suggestion| allowed(suggestion.applicability))
+    {
+        return Ok(suggestion.clone());
+    }
+
+    let has_maybe_incorrect = diagnostic
+        .suggestions
+        .iter()
+        .any(|suggestion| suggestion.applicability == Applicability::MaybeIncorrect);
+    Err(if diagnostic.suggestions.is_empty() {
+        "the diagnostic has no suggestions".to_owned()
+    } else if has_maybe_incorrect {
+        "the suggestions are `MaybeIncorrect`, set `allow_maybe_incorrect` to apply them".to_owned()
+    } else {
+        "the suggestions have placeholders or unspecified applicability and need manual edits"
+            .to_owned()
+    })
+}
+
+/// Only relative paths without `..` are edited, which excludes dependencies in the cargo registry.
+fn is_inside_workspace(file: &str) -> bool {
+    Path::new(file)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+}
+
+/// Checks that the edit still points at the code it was suggested for.
+fn check_edit(content: &str, edit: &Edit) -> Result<(), String> {
+    const STALE: &str = "the file changed since the diagnostic was reported";
+
+    if edit.byte_start > edit.byte_end
+        || edit.byte_end > content.len()
+        || !content.is_char_boundary(edit.byte_start)
+        || !content.is_char_boundary(edit.byte_end)
+    {
+        return Err(STALE.to_owned());
+    }
+
+    let before = &content[..edit.byte_start];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    if line != edit.line || column != edit.column {
+        return Err(STALE.to_owned());
+    }
+
+    if let Some(expected) = &edit.line_text {
+        let actual = content[line_start..].lines().next().unwrap_or("");
+        if actual != expected {
+            return Err(STALE.to_owned());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(byte_start: usize, byte_end: usize, line: usize, column: usize) -> Edit {
+        Edit {
+            file: "src/lib.rs".into(),
+            byte_start,
+            byte_end,
+            line,
+            column,
+            line_text: Some("    let x = 5;".into()),
+            replacement: "_x".into(),
+        }
+    }
+
+    #[test]
+    fn test_check_edit() {
+        let content = "fn a() {\n    let x = 5;\n}\n";
+        assert_eq!(check_edit(content, &edit(17, 18, 2, 9)), Ok(()));
+
+        // A line was added above
+        let moved = "// comment\nfn a() {\n    let x = 5;\n}\n";
+        assert!(check_edit(moved, &edit(17, 18, 2, 9)).is_err());
+
+        // Same offsets, different code
+        let changed = "fn a() {\n    let y = 5;\n}\n";
+        assert!(check_edit(changed, &edit(17, 18, 2, 9)).is_err());
+
+        assert!(check_edit("fn a() {}", &edit(17, 18, 2, 9)).is_err());
+    }
+
+    #[test]
+    fn test_check_edit_counts_columns_in_chars() {
+        let content = "fn a() {\n    let é = 5; let x = 5;\n}\n";
+        let mut edit = edit(29, 30, 2, 20);
+        edit.line_text = Some("    let é = 5; let x = 5;".into());
+        assert_eq!(check_edit(content, &edit), Ok(()));
+    }
+
+    #[test]
+    fn test_is_inside_workspace() {
+        assert!(is_inside_workspace("src/lib.rs"));
+        assert!(is_inside_workspace("./crates/core/src/lib.rs"));
+        assert!(!is_inside_workspace("../other/src/lib.rs"));
+        assert!(!is_inside_workspace(
+            "/home/user/.cargo/registry/src/serde/lib.rs"
+        ));
+    }
+}
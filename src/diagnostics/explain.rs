@@ -0,0 +1,125 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::tools::common::rustc_command;
+
+/// Explanations don't change for a given rustc, so each error code is explained once per server
+/// and toolchain. `None` caches codes that `rustc --explain` doesn't know.
+type ExplanationCache = HashMap<(Option<String>, String), Option<String>>;
+static EXPLANATIONS: OnceLock<Mutex<ExplanationCache>> = OnceLock::new();
+
+/// Total size of the excerpts attached to one report.
+pub(super) const EXPLANATIONS_BUDGET: usize = 3000;
+
+/// Longest erroneous example kept in an excerpt, in lines.
+const MAX_EXAMPLE_LINES: usize = 20;
+
+/// Returns a short excerpt of `rustc --explain <code>` of the toolchain the diagnostics came from,
+/// or `None` if rustc can't explain it.
+pub(super) fn explain_excerpt(code: &str, toolchain: Option<&str>) -> Option<String> {
+    let key = (toolchain.map(str::to_owned), code.to_owned());
+    let cache = EXPLANATIONS.get_or_init(Default::default);
+    if let Some(cached) = cache.lock().ok()?.get(&key) {
+        return cached.clone();
+    }
+
+    let explanation = run_rustc_explain(code, toolchain).map(|text| excerpt(&text));
+    cache.lock().ok()?.insert(key, explanation.clone());
+    explanation
+}
+
+fn run_rustc_explain(code: &str, toolchain: Option<&str>) -> Option<String> {
+    let mut cmd = rustc_command(toolchain).ok()?;
+    cmd.arg("--explain").arg(code);
+    let output = cmd.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Keeps the first paragraph and the first code block, which is the erroneous example.
+fn excerpt(explanation: &str) -> String {
+    let mut lines = explanation
+        .lines()
+        .skip_while(|line| line.trim().is_empty());
+
+    let paragraph: Vec<&str> = lines
+        .by_ref()
+        .take_while(|line| !line.trim().is_empty())
+        .map(str::trim)
+        .collect();
+    let mut excerpt = paragraph.join(" ");
+
+    let mut example: Vec<&str> = Vec::new();
+    if lines.by_ref().any(|line| line.starts_with("```")) {
+        example = lines
+            .by_ref()
+            .take_while(|line| !line.starts_with("```"))
+            .collect();
+    }
+    if !example.is_empty() {
+        if example.len() > MAX_EXAMPLE_LINES {
+            example.truncate(MAX_EXAMPLE_LINES);
+            example.push("// ...");
+        }
+        excerpt.push_str("\n```\n");
+        excerpt.push_str(&example.join("\n"));
+        excerpt.push_str("\n```");
+    }
+    excerpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_excerpt() {
+        let explanation = "Expected type did not match the received type.\n\
+                           \n\
+                           Erroneous code examples:\n\
+                           \n\
+                           ```\n\
+                           fn plus_one(x: i32) -> i32 {\n\
+                           \x20   x + 1\n\
+                           }\n\
+                           \n\
+                           plus_one(\"Not a number\");\n\
+                           ```\n\
+                           \n\
+                           This error occurs when an expression was used in a place where the compiler\n\
+                           expected an expression of a different type.\n\
+                           \n\
+                           ```\n\
+                           let x: i32 = 1;\n\
+                           ```\n";
+
+        assert_eq!(
+            excerpt(explanation),
+            "Expected type did not match the received type.\n\
+             ```\n\
+             fn plus_one(x: i32) -> i32 {\n\
+             \x20   x + 1\n\
+             }\n\
+             \n\
+             plus_one(\"Not a number\");\n\
+             ```"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_joins_wrapped_paragraph_and_limits_example() {
+        let example: Vec<String> = (0..30).map(|i| format!("let x{i} = {i};")).collect();
+        let explanation = format!(
+            "A value was moved\nand then used.\n\n```compile_fail,E0382\n{}\n```\n",
+            example.join("\n")
+        );
+
+        let excerpt = excerpt(&explanation);
+        assert!(excerpt.starts_with("A value was moved and then used.\n```\nlet x0 = 0;\n"));
+        assert!(excerpt.ends_with("let x19 = 19;\n// ...\n```"));
+    }
+}
@@ -0,0 +1,611 @@
+//! Compact compiler diagnostics parsed from cargo's `--message-format=json` output.
+
+mod apply;
+mod explain;
+mod options;
+mod parse;
+mod render;
+mod snippet;
+mod store;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response,
+    command::{Output, Stdout, text_with_description},
+    tools::workspace_root,
+};
+
+pub(crate) use apply::{SuggestionsResult, apply_suggestions};
+pub use options::DiagnosticOptions;
+pub(crate) use options::GroupBy;
+pub(crate) use parse::CargoMessages;
+pub(crate) use render::filter_cargo_status;
+pub(crate) use snippet::snippet as source_snippet;
+pub(crate) use store::is_empty as no_diagnostics_remembered;
+
+/// Severity of a diagnostic, ordered from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Level {
+    Help,
+    Note,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Level::Help => "help",
+            Level::Note => "note",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        })
+    }
+}
+
+/// How confident rustc is that a suggestion can be applied as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum Applicability {
+    MachineApplicable,
+    MaybeIncorrect,
+    HasPlaceholders,
+    Unspecified,
+}
+
+/// Location of a diagnostic in the source, with 1-based lines and columns.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct Span {
+    pub(crate) file: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) end_line: usize,
+    pub(crate) end_column: usize,
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// A single replacement of a suggestion.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct Edit {
+    pub(crate) file: String,
+    pub(crate) byte_start: usize,
+    pub(crate) byte_end: usize,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    /// Source line the edit starts on, used to detect that the file changed since the diagnostic
+    #[serde(skip)]
+    pub(crate) line_text: Option<String>,
+    pub(crate) replacement: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct Suggestion {
+    pub(crate) message: String,
+    pub(crate) applicability: Applicability,
+    pub(crate) edits: Vec<Edit>,
+}
+
+/// Whether a diagnostic was already reported by the previous run of the same command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Status {
+    New,
+    Persisting,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct Diagnostic {
+    /// Stable identifier derived from the file, code, message, source line and column, so the
+    /// same problem keeps its id when code above it moves
+    pub(crate) id: String,
+    pub(crate) level: Level,
+    /// Error code, e.g. `E0308`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) code: Option<String>,
+    /// Lint name, e.g. `unused_variables` or `clippy::needless_return`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) lint: Option<String>,
+    pub(crate) message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) span: Option<Span>,
+    /// Label of the primary span, e.g. "expected `u32`, found `&str`"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) label: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) notes: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) suggestions: Vec<Suggestion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) package: Option<String>,
+    /// Targets the diagnostic was emitted for, e.g. `lib` or `test "integration"`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) targets: Vec<String>,
+    /// Compared with the previous run of the same command, if there was one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) status: Option<Status>,
+    /// Source lines around the primary span, with a caret marker under it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) snippet: Option<String>,
+}
+
+/// Counts of the comparison with the previous run of the same command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct Changes {
+    pub(crate) new: usize,
+    pub(crate) persisting: usize,
+    pub(crate) fixed: usize,
+}
+
+/// Diagnostics of one cargo invocation, returned as `structured_content`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct DiagnosticReport {
+    pub(crate) success: bool,
+    /// Errors and warnings after deduplication, including the filtered and omitted ones
+    pub(crate) errors: usize,
+    pub(crate) warnings: usize,
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    /// Diagnostics left out by the filter options
+    #[serde(skip_serializing_if = "is_zero")]
+    pub(crate) filtered: usize,
+    /// Diagnostics left out because of `max_diagnostics`
+    #[serde(skip_serializing_if = "is_zero")]
+    pub(crate) omitted: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) changes: Option<Changes>,
+    /// Diagnostics of the previous run of the same command that are gone
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) fixed: Vec<Diagnostic>,
+    /// Excerpts of `rustc --explain` for the error codes of a failed build
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) explanations: BTreeMap<String, String>,
+    /// Diagnostics whose snippet didn't fit in the budget
+    #[serde(skip_serializing_if = "is_zero")]
+    pub(crate) snippets_omitted: usize,
+    /// Error codes whose excerpt didn't fit in the budget
+    #[serde(skip)]
+    pub(crate) unexplained: Vec<String>,
+    #[serde(skip)]
+    pub(crate) group_by: Option<GroupBy>,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl DiagnosticReport {
+    pub(crate) fn new(success: bool, diagnostics: Vec<Diagnostic>) -> Self {
+        let diagnostics = deduplicate(diagnostics);
+        let count = |level| diagnostics.iter().filter(|d| d.level == level).count();
+        Self {
+            success,
+            errors: count(Level::Error),
+            warnings: count(Level::Warning),
+            diagnostics,
+            filtered: 0,
+            omitted: 0,
+            changes: None,
+            fixed: Vec::new(),
+            snippets_omitted: 0,
+            explanations: BTreeMap::new(),
+            unexplained: Vec::new(),
+            group_by: None,
+        }
+    }
+
+    /// Filters the diagnostics, puts errors first and keeps at most `max_diagnostics` of them.
+    /// Error codes are explained by the rustc of `toolchain`, the one the command ran with.
+    pub(crate) fn apply_options(
+        &mut self,
+        options: &DiagnosticOptions,
+        toolchain: Option<&str>,
+    ) -> Result<(), ErrorData> {
+        let total = self.diagnostics.len();
+        let mut kept = Vec::with_capacity(total);
+        for diagnostic in std::mem::take(&mut self.diagnostics) {
+            if options.matches(&diagnostic)? {
+                kept.push(diagnostic);
+            }
+        }
+        self.filtered = total - kept.len();
+        if options.delta_only() {
+            kept.retain(|diagnostic| diagnostic.status != Some(Status::Persisting));
+        }
+
+        let mut fixed = Vec::with_capacity(self.fixed.len());
+        for diagnostic in std::mem::take(&mut self.fixed) {
+            if options.matches(&diagnostic)? {
+                fixed.push(diagnostic);
+            }
+        }
+        self.fixed = fixed;
+
+        kept.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.level));
+        let max = options.max_diagnostics();
+        self.omitted = kept.len().saturating_sub(max);
+        kept.truncate(max);
+
+        self.diagnostics = kept;
+        self.group_by = options.group_by()?;
+        if let Some(context_lines) = options.context_lines() {
+            self.attach_snippets(context_lines);
+        }
+        if !self.success && options.explain() {
+            self.attach_explanations(toolchain);
+        }
+        Ok(())
+    }
+
+    /// Labels the diagnostics as new or persisting and collects the fixed ones.
+    ///
+    /// Diagnostics that share a base id, i.e. the same problem on identical lines, are paired by
+    /// the closest line. Persisting diagnostics keep the id of the previous run, so that ids stay
+    /// stable while some of their duplicates are fixed.
+    fn compare_with(&mut self, previous: &[Diagnostic]) {
+        let line = |diagnostic: &Diagnostic| diagnostic.span.as_ref().map_or(0, |span| span.line);
+
+        let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+        for (current_index, current) in self.diagnostics.iter().enumerate() {
+            for (previous_index, old) in previous.iter().enumerate() {
+                if base_id(&current.id) == base_id(&old.id) {
+                    let distance = line(current).abs_diff(line(old));
+                    pairs.push((distance, current_index, previous_index));
+                }
+            }
+        }
+        pairs.sort_unstable();
+
+        let mut matched_current: Vec<Option<usize>> = vec![None; self.diagnostics.len()];
+        let mut matched_previous = vec![false; previous.len()];
+        for (_, current_index, previous_index) in pairs {
+            if matched_current[current_index].is_none() && !matched_previous[previous_index] {
+                matched_current[current_index] = Some(previous_index);
+                matched_previous[previous_index] = true;
+            }
+        }
+
+        let mut used_ids: HashSet<String> = matched_current
+            .iter()
+            .flatten()
+            .map(|&previous_index| previous[previous_index].id.clone())
+            .collect();
+        let mut changes = Changes {
+            new: 0,
+            persisting: 0,
+            fixed: 0,
+        };
+        for (diagnostic, matched) in self.diagnostics.iter_mut().zip(matched_current) {
+            match matched {
+                Some(previous_index) => {
+                    diagnostic.id = previous[previous_index].id.clone();
+                    diagnostic.status = Some(Status::Persisting);
+                    changes.persisting += 1;
+                }
+                None => {
+                    let base = base_id(&diagnostic.id).to_owned();
+                    diagnostic.id = (1..)
+                        .map(|n| {
+                            if n == 1 {
+                                base.clone()
+                            } else {
+                                format!("{base}-{n}")
+                            }
+                        })
+                        .find(|id| !used_ids.contains(id))
+                        .expect("there is always an unused id");
+                    used_ids.insert(diagnostic.id.clone());
+                    diagnostic.status = Some(Status::New);
+                    changes.new += 1;
+                }
+            }
+        }
+
+        self.fixed = previous
+            .iter()
+            .zip(matched_previous)
+            .filter(|(_, matched)| !matched)
+            .map(|(diagnostic, _)| Diagnostic {
+                notes: Vec::new(),
+                suggestions: Vec::new(),
+                ..diagnostic.clone()
+            })
+            .collect();
+        changes.fixed = self.fixed.len();
+        self.changes = Some(changes);
+    }
+
+    /// Attaches source snippets read from the workspace files, as long as they fit in the budget.
+    fn attach_snippets(&mut self, context_lines: usize) {
+        let root = workspace_root();
+        let mut files: HashMap<String, Option<String>> = HashMap::new();
+        let mut budget = snippet::SNIPPETS_BUDGET;
+        for diagnostic in &mut self.diagnostics {
+            let Some(span) = &diagnostic.span else {
+                continue;
+            };
+            let content = files
+                .entry(span.file.clone())
+                .or_insert_with(|| std::fs::read_to_string(root.join(&span.file)).ok());
+            let Some(snippet) = content
+                .as_deref()
+                .and_then(|content| snippet::snippet(content, span, context_lines))
+            else {
+                continue;
+            };
+            if snippet.len() > budget {
+                self.snippets_omitted += 1;
+                continue;
+            }
+            budget -= snippet.len();
+            diagnostic.snippet = Some(snippet);
+        }
+    }
+
+    /// Attaches `rustc --explain` excerpts for the distinct error codes of the reported errors,
+    /// as long as they fit in the budget.
+    fn attach_explanations(&mut self, toolchain: Option<&str>) {
+        let mut codes: Vec<&str> = Vec::new();
+        for diagnostic in &self.diagnostics {
+            if diagnostic.level == Level::Error
+                && let Some(code) = diagnostic.code.as_deref()
+                && !codes.contains(&code)
+            {
+                codes.push(code);
+            }
+        }
+
+        let mut budget = explain::EXPLANATIONS_BUDGET;
+        for code in codes {
+            let Some(excerpt) = explain::explain_excerpt(code, toolchain) else {
+                continue;
+            };
+            if excerpt.len() > budget {
+                self.unexplained.push(code.to_owned());
+                continue;
+            }
+            budget -= excerpt.len();
+            self.explanations.insert(code.to_owned(), excerpt);
+        }
+    }
+
+    /// Takes the JSON messages out of the output of a check-family command run with
+    /// `--message-format=json`.
+    ///
+    /// Lines that are not JSON messages stay in stdout, and cargo's progress lines such as
+    /// "Compiling ..." are dropped from stderr. The diagnostics are remembered so that their
+    /// suggestions can be applied by id later, and compared with the previous run of the same
+    /// command.
+    pub(crate) fn from_output(output: &mut Output) -> Self {
+        let messages = CargoMessages::parse(output.stdout.take().map(|s| s.0).unwrap_or_default());
+        output.stdout =
+            (!messages.other_lines.is_empty()).then(|| Stdout(messages.other_lines.join("\n")));
+        output.stderr = output.stderr.take().and_then(filter_cargo_status);
+
+        let success = messages.build_success.unwrap_or_else(|| output.success());
+        let mut report = Self::new(success, messages.diagnostics);
+        if let Some(previous) = store::previous_run(&output.cmd_line.0) {
+            report.compare_with(&previous);
+        }
+        store::remember(&output.cmd_line.0, &report.diagnostics);
+        report
+    }
+
+    pub(crate) fn has_machine_applicable_suggestions(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| {
+            diagnostic
+                .suggestions
+                .iter()
+                .any(|s| s.applicability == Applicability::MachineApplicable)
+        })
+    }
+
+    /// Builds the tool response with a text summary of the diagnostics and the report as
+    /// `structured_content`.
+    pub(crate) fn into_response(self, output: Output) -> Response {
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(render::render_report(&self), "compiler diagnostics")
+                .annotate(Annotations::default()),
+        );
+        response.set_structured_content(
+            serde_json::to_value(&self).expect("diagnostic report is always serializable"),
+        );
+        response
+    }
+}
+
+/// Id without the number that tells apart the same problem on identical lines.
+fn base_id(id: &str) -> &str {
+    id.split_once('-').map_or(id, |(base, _)| base)
+}
+
+/// Merges diagnostics reported at the same place for several targets, e.g. once for the lib and
+/// once for its tests.
+///
+/// Ids don't include the line, so the same problem on two identical lines gets a numbered id.
+fn deduplicate(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    let mut unique: Vec<Diagnostic> = Vec::with_capacity(diagnostics.len());
+    let mut positions: HashMap<(String, Option<usize>), usize> = HashMap::new();
+    let mut id_counts: HashMap<String, usize> = HashMap::new();
+
+    for mut diagnostic in diagnostics {
+        let key = (
+            diagnostic.id.clone(),
+            diagnostic.span.as_ref().map(|span| span.line),
+        );
+        if let Some(&position) = positions.get(&key) {
+            let existing = &mut unique[position];
+            for target in diagnostic.targets {
+                if !existing.targets.contains(&target) {
+                    existing.targets.push(target);
+                }
+            }
+            continue;
+        }
+
+        let count = id_counts.entry(diagnostic.id.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            diagnostic.id = format!("{}-{count}", diagnostic.id);
+        }
+        positions.insert(key, unique.len());
+        unique.push(diagnostic);
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(id: &str, level: Level, file: &str, line: usize, target: &str) -> Diagnostic {
+        Diagnostic {
+            id: id.into(),
+            level,
+            code: None,
+            lint: Some("unused_variables".into()),
+            message: "unused variable: `x`".into(),
+            span: Some(Span {
+                file: file.into(),
+                line,
+                column: 9,
+                end_line: line,
+                end_column: 10,
+            }),
+            label: None,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+            package: None,
+            targets: vec![target.into()],
+            status: None,
+            snippet: None,
+        }
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let report = DiagnosticReport::new(
+            true,
+            vec![
+                diagnostic("a", Level::Warning, "src/lib.rs", 2, "lib"),
+                diagnostic("a", Level::Warning, "src/lib.rs", 2, "lib test"),
+                diagnostic("a", Level::Warning, "src/lib.rs", 7, "lib"),
+                diagnostic("b", Level::Warning, "src/lib.rs", 2, "lib"),
+            ],
+        );
+
+        assert_eq!(report.warnings, 3);
+        let ids: Vec<_> = report.diagnostics.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["a", "a-2", "b"]);
+        assert_eq!(report.diagnostics[0].targets, ["lib", "lib test"]);
+        assert_eq!(report.diagnostics[1].targets, ["lib"]);
+    }
+
+    #[test]
+    fn test_apply_options() {
+        let mut report = DiagnosticReport::new(
+            false,
+            vec![
+                diagnostic("a", Level::Warning, "src/lib.rs", 1, "lib"),
+                diagnostic("b", Level::Warning, "tests/it.rs", 1, "lib"),
+                diagnostic("c", Level::Error, "src/lib.rs", 3, "lib"),
+                diagnostic("d", Level::Warning, "src/main.rs", 5, "lib"),
+            ],
+        );
+        let options: DiagnosticOptions = serde_json::from_value(serde_json::json!({
+            "path_glob": "src/*",
+            "max_diagnostics": 2,
+            "group_by": "file",
+        }))
+        .unwrap();
+        report.apply_options(&options, None).unwrap();
+
+        let ids: Vec<_> = report.diagnostics.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["c", "a"]);
+        assert_eq!(report.filtered, 1);
+        assert_eq!(report.omitted, 1);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.warnings, 3);
+        assert_eq!(report.group_by, Some(GroupBy::File));
+    }
+
+    #[test]
+    fn test_compare_with_previous_run() {
+        let previous = [
+            diagnostic("a", Level::Warning, "src/lib.rs", 1, "lib"),
+            diagnostic("b", Level::Warning, "src/lib.rs", 2, "lib"),
+        ];
+        let mut report = DiagnosticReport::new(
+            true,
+            vec![
+                // Moved down by an edit above, still the same problem
+                diagnostic("b", Level::Warning, "src/lib.rs", 5, "lib"),
+                diagnostic("c", Level::Warning, "src/lib.rs", 6, "lib"),
+            ],
+        );
+        report.compare_with(&previous);
+
+        assert_eq!(
+            report.changes,
+            Some(Changes {
+                new: 1,
+                persisting: 1,
+                fixed: 1
+            })
+        );
+        assert_eq!(report.diagnostics[0].status, Some(Status::Persisting));
+        assert_eq!(report.diagnostics[1].status, Some(Status::New));
+        assert_eq!(report.fixed[0].id, "a");
+
+        let delta_only: DiagnosticOptions =
+            serde_json::from_value(serde_json::json!({ "delta_only": true })).unwrap();
+        report.apply_options(&delta_only, None).unwrap();
+        let ids: Vec<_> = report.diagnostics.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["c"]);
+        assert_eq!(report.fixed.len(), 1);
+        assert_eq!(report.filtered, 0);
+    }
+
+    #[test]
+    fn test_compare_keeps_ids_of_duplicates() {
+        let previous = [
+            diagnostic("a", Level::Warning, "src/lib.rs", 2, "lib"),
+            diagnostic("a-2", Level::Warning, "src/lib.rs", 7, "lib"),
+        ];
+        // The first one was fixed, so the remaining one is numbered 1 by deduplication
+        let mut report = DiagnosticReport::new(
+            true,
+            vec![diagnostic("a", Level::Warning, "src/lib.rs", 7, "lib")],
+        );
+        report.compare_with(&previous);
+
+        assert_eq!(report.diagnostics[0].id, "a-2");
+        assert_eq!(report.diagnostics[0].status, Some(Status::Persisting));
+        assert_eq!(report.fixed[0].id, "a");
+        assert_eq!(report.fixed[0].span.as_ref().unwrap().line, 2);
+
+        // A new duplicate doesn't reuse the id of a persisting one
+        let mut report = DiagnosticReport::new(
+            true,
+            vec![
+                diagnostic("a", Level::Warning, "src/lib.rs", 2, "lib"),
+                diagnostic("a", Level::Warning, "src/lib.rs", 7, "lib"),
+                diagnostic("a", Level::Warning, "src/lib.rs", 12, "lib"),
+            ],
+        );
+        report.compare_with(&previous);
+        let ids: Vec<_> = report.diagnostics.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["a", "a-2", "a-3"]);
+        assert_eq!(report.diagnostics[2].status, Some(Status::New));
+    }
+}
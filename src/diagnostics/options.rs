@@ -0,0 +1,273 @@
+use rmcp::ErrorData;
+
+use super::{Diagnostic, Level};
+use crate::serde_utils::{deserialize_string, deserialize_string_vec};
+
+const DEFAULT_MAX_DIAGNOSTICS: u32 = 50;
+
+/// Filtering and grouping of diagnostics, shared by the check-family tools.
+#[derive(Debug, Default, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct DiagnosticOptions {
+    /// Group diagnostics in the summary. One of: file, lint.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    group_by: Option<String>,
+
+    /// Only report diagnostics at this level or above. One of: error, warning, note, help.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    min_level: Option<String>,
+
+    /// Only report diagnostics in files matching this glob, e.g. `src/parser/**` or
+    /// `**/tests/*.rs`. `*` doesn't match `/`, `**` does.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    path_glob: Option<String>,
+
+    /// Only report these error codes or lints, e.g. `E0308` or `clippy::*`.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    include_codes: Option<Vec<String>>,
+
+    /// Don't report these error codes or lints, e.g. `dead_code` or `clippy::pedantic::*`.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    exclude_codes: Option<Vec<String>>,
+
+    /// Maximum number of diagnostics to return, errors first. Defaults to 50.
+    #[serde(default)]
+    max_diagnostics: Option<u32>,
+
+    /// Attach a short `rustc --explain` excerpt for each error code when the build fails. Defaults
+    /// to true.
+    #[serde(default)]
+    explain: Option<bool>,
+
+    /// Only return diagnostics that are new since the last run with the same arguments, plus the
+    /// ones that were fixed.
+    #[serde(default)]
+    delta_only: Option<bool>,
+
+    /// Attach this many lines of source code around each primary span, at most 10. Snippets are
+    /// left out once their total size reaches about 6000 bytes.
+    #[serde(default)]
+    context_lines: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GroupBy {
+    File,
+    Lint,
+}
+
+impl DiagnosticOptions {
+    pub(crate) fn validate(&self) -> Result<(), ErrorData> {
+        self.group_by()?;
+        self.min_level()?;
+        Ok(())
+    }
+
+    pub(crate) fn group_by(&self) -> Result<Option<GroupBy>, ErrorData> {
+        self.group_by
+            .as_deref()
+            .map(|group_by| match group_by {
+                "file" => Ok(GroupBy::File),
+                "lint" => Ok(GroupBy::Lint),
+                unknown => Err(ErrorData::invalid_params(
+                    format!("Unknown group_by: {unknown}. Valid options are: file, lint"),
+                    None,
+                )),
+            })
+            .transpose()
+    }
+
+    fn min_level(&self) -> Result<Option<Level>, ErrorData> {
+        self.min_level
+            .as_deref()
+            .map(|level| match level {
+                "error" => Ok(Level::Error),
+                "warning" => Ok(Level::Warning),
+                "note" => Ok(Level::Note),
+                "help" => Ok(Level::Help),
+                unknown => Err(ErrorData::invalid_params(
+                    format!(
+                        "Unknown min_level: {unknown}. Valid options are: error, warning, note, help"
+                    ),
+                    None,
+                )),
+            })
+            .transpose()
+    }
+
+    pub(crate) fn max_diagnostics(&self) -> usize {
+        self.max_diagnostics.unwrap_or(DEFAULT_MAX_DIAGNOSTICS) as usize
+    }
+
+    pub(crate) fn context_lines(&self) -> Option<usize> {
+        self.context_lines.map(|lines| lines as usize)
+    }
+
+    pub(crate) fn delta_only(&self) -> bool {
+        self.delta_only.unwrap_or(false)
+    }
+
+    pub(crate) fn explain(&self) -> bool {
+        self.explain.unwrap_or(true)
+    }
+
+    /// Returns `false` for diagnostics that are filtered out by the options.
+    pub(crate) fn matches(&self, diagnostic: &Diagnostic) -> Result<bool, ErrorData> {
+        if let Some(min_level) = self.min_level()?
+            && diagnostic.level < min_level
+        {
+            return Ok(false);
+        }
+
+        if let Some(pattern) = &self.path_glob {
+            let file = diagnostic.span.as_ref().map(|span| span.file.as_str());
+            if !file.is_some_and(|file| glob_match(pattern, file)) {
+                return Ok(false);
+            }
+        }
+
+        let code = diagnostic.code.as_deref().or(diagnostic.lint.as_deref());
+        let matches_any = |patterns: &[String]| {
+            code.is_some_and(|code| patterns.iter().any(|pattern| glob_match(pattern, code)))
+        };
+        if let Some(include) = &self.include_codes
+            && !include.is_empty()
+            && !matches_any(include)
+        {
+            return Ok(false);
+        }
+        if let Some(exclude) = &self.exclude_codes
+            && matches_any(exclude)
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+/// Matches `text` against a glob where `*` matches anything except `/`, `**` matches anything and
+/// `?` matches a single character.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` also matches no directory at all, so `**/*.rs` matches `main.rs`
+            let rest_without_slash = rest.strip_prefix(&['/']).unwrap_or(rest);
+            glob_match_chars(rest_without_slash, text)
+                || (0..text.len()).any(|skip| glob_match_chars(rest, &text[skip..]))
+                || glob_match_chars(rest, &[])
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&skip| skip == 0 || text[skip - 1] != '/')
+            .any(|skip| glob_match_chars(rest, &text[skip..])),
+        ['?', rest @ ..] => {
+            matches!(text, [c, ..] if *c != '/') && glob_match_chars(rest, &text[1..])
+        }
+        [p, rest @ ..] => matches!(text, [c, ..] if c == p) && glob_match_chars(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("src/main.rs", "src/main.rs"));
+        assert!(glob_match("src/*.rs", "src/main.rs"));
+        assert!(!glob_match("src/*.rs", "src/parser/mod.rs"));
+        assert!(glob_match("src/**", "src/parser/mod.rs"));
+        assert!(glob_match("src/**/*.rs", "src/main.rs"));
+        assert!(glob_match("src/**/*.rs", "src/parser/lexer/mod.rs"));
+        assert!(glob_match("**/tests/*.rs", "crates/core/tests/it.rs"));
+        assert!(glob_match("**/tests/*.rs", "tests/it.rs"));
+        assert!(glob_match("src/?.rs", "src/a.rs"));
+        assert!(!glob_match("src/?.rs", "src/ab.rs"));
+        assert!(glob_match("clippy::*", "clippy::needless_return"));
+        assert!(!glob_match("clippy::*", "unused_variables"));
+        assert!(glob_match("E03*", "E0308"));
+    }
+
+    fn diagnostic(level: Level, file: &str, code: Option<&str>, lint: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            id: "id".into(),
+            level,
+            code: code.map(Into::into),
+            lint: lint.map(Into::into),
+            message: "message".into(),
+            span: Some(super::super::Span {
+                file: file.into(),
+                line: 1,
+                column: 1,
+                end_line: 1,
+                end_column: 2,
+            }),
+            label: None,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+            package: None,
+            targets: Vec::new(),
+            status: None,
+            snippet: None,
+        }
+    }
+
+    fn options(value: serde_json::Value) -> DiagnosticOptions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_matches() {
+        let error = diagnostic(Level::Error, "src/lib.rs", Some("E0308"), None);
+        let unused = diagnostic(Level::Warning, "src/lib.rs", None, Some("unused_variables"));
+        let clippy = diagnostic(
+            Level::Warning,
+            "tests/it.rs",
+            None,
+            Some("clippy::needless_return"),
+        );
+
+        let min_error = options(json!({ "min_level": "error" }));
+        assert!(min_error.matches(&error).unwrap());
+        assert!(!min_error.matches(&unused).unwrap());
+
+        let in_src = options(json!({ "path_glob": "src/**" }));
+        assert!(in_src.matches(&unused).unwrap());
+        assert!(!in_src.matches(&clippy).unwrap());
+
+        let only_clippy = options(json!({ "include_codes": ["clippy::*"] }));
+        assert!(only_clippy.matches(&clippy).unwrap());
+        assert!(!only_clippy.matches(&error).unwrap());
+
+        let no_unused = options(json!({ "exclude_codes": "unused_variables" }));
+        assert!(no_unused.matches(&error).unwrap());
+        assert!(!no_unused.matches(&unused).unwrap());
+
+        assert!(DiagnosticOptions::default().matches(&unused).unwrap());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(
+            options(json!({ "group_by": "file", "min_level": "warning" }))
+                .validate()
+                .is_ok()
+        );
+
+        let err = options(json!({ "group_by": "crate" }))
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Unknown group_by: crate. Valid options are: file, lint"
+        );
+        assert!(options(json!({ "min_level": "fatal" })).validate().is_err());
+    }
+}
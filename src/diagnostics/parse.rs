@@ -0,0 +1,397 @@
+use super::{Applicability, Diagnostic, Edit, Level, Span, Suggestion};
+
+/// Messages printed by cargo with `--message-format=json`.
+#[derive(Debug, Default)]
+pub(crate) struct CargoMessages {
+    pub(crate) diagnostics: Vec<Diagnostic>,
+    /// `success` of the final `build-finished` message
+    pub(crate) build_success: Option<bool>,
+    /// Lines that are not JSON messages, e.g. output of a program run by cargo
+    pub(crate) other_lines: Vec<String>,
+}
+
+impl CargoMessages {
+    pub(crate) fn parse(stdout: impl AsRef<str>) -> Self {
+        let mut messages = Self::default();
+        for line in stdout.as_ref().lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(message) = serde_json::from_str::<RawMessage>(line) else {
+                messages.other_lines.push(line.to_owned());
+                continue;
+            };
+
+            match message.reason.as_str() {
+                "compiler-message" => {
+                    if let Some(diagnostic) = message.diagnostic() {
+                        messages.diagnostics.push(diagnostic);
+                    }
+                }
+                "build-finished" => messages.build_success = message.success,
+                _ => {}
+            }
+        }
+        messages
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RawMessage {
+    reason: String,
+    package_id: Option<String>,
+    target: Option<RawTarget>,
+    message: Option<RawDiagnostic>,
+    success: Option<bool>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawTarget {
+    kind: Vec<String>,
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawDiagnostic {
+    message: String,
+    code: Option<RawCode>,
+    level: String,
+    spans: Vec<RawSpan>,
+    children: Vec<RawDiagnostic>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawCode {
+    code: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<Applicability>,
+    text: Vec<RawSpanText>,
+    expansion: Option<Box<RawExpansion>>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawSpanText {
+    text: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawExpansion {
+    span: RawSpan,
+}
+
+impl RawMessage {
+    fn diagnostic(self) -> Option<Diagnostic> {
+        let raw = self.message?;
+        let level = match raw.level.as_str() {
+            "error" | "error: internal compiler error" => Level::Error,
+            "warning" => Level::Warning,
+            "note" => Level::Note,
+            "help" => Level::Help,
+            // "For more information about this error, try `rustc --explain E0308`."
+            _ => return None,
+        };
+        if raw.spans.is_empty() && is_summary(&raw.message) {
+            return None;
+        }
+
+        let (code, lint) = match raw.code {
+            Some(RawCode { code }) if is_error_code(&code) => (Some(code), None),
+            Some(RawCode { code }) => (None, Some(code)),
+            None => (None, None),
+        };
+
+        let primary = raw.spans.iter().find(|span| span.is_primary).map(|span| {
+            // Point at the macro call instead of the macro definition in another crate
+            let mut span = span;
+            while span.file_name.starts_with('<')
+                && let Some(expansion) = &span.expansion
+            {
+                span = &expansion.span;
+            }
+            span
+        });
+
+        let mut notes = Vec::new();
+        let mut suggestions = Vec::new();
+        for child in &raw.children {
+            let edits: Vec<Edit> = child
+                .spans
+                .iter()
+                .filter_map(|span| {
+                    Some(Edit {
+                        file: span.file_name.clone(),
+                        byte_start: span.byte_start,
+                        byte_end: span.byte_end,
+                        line: span.line_start,
+                        column: span.column_start,
+                        line_text: span.text.first().map(|text| text.text.clone()),
+                        replacement: span.suggested_replacement.clone()?,
+                    })
+                })
+                .collect();
+
+            if !edits.is_empty() {
+                suggestions.push(Suggestion {
+                    message: child.message.clone(),
+                    applicability: child
+                        .spans
+                        .iter()
+                        .find_map(|span| span.suggestion_applicability)
+                        .unwrap_or(Applicability::Unspecified),
+                    edits,
+                });
+            } else if !is_noise_note(&child.message) {
+                notes.push(format!("{}: {}", child.level, child.message));
+            }
+        }
+
+        let id = diagnostic_id(
+            level,
+            code.as_deref().or(lint.as_deref()),
+            &raw.message,
+            primary,
+        );
+
+        Some(Diagnostic {
+            id,
+            level,
+            code,
+            lint,
+            message: raw.message,
+            span: primary.map(|span| Span {
+                file: span.file_name.clone(),
+                line: span.line_start,
+                column: span.column_start,
+                end_line: span.line_end,
+                end_column: span.column_end,
+            }),
+            label: primary.and_then(|span| span.label.clone()),
+            notes,
+            suggestions,
+            package: self.package_id.as_deref().map(package_name),
+            targets: self
+                .target
+                .map(|target| target_name(&target))
+                .into_iter()
+                .collect(),
+            status: None,
+            snippet: None,
+        })
+    }
+}
+
+/// Messages like "aborting due to 2 previous errors" that only repeat the counts.
+fn is_summary(message: &str) -> bool {
+    message.starts_with("aborting due to")
+        || (message.contains("warning") && message.ends_with("emitted"))
+}
+
+/// Notes that are the same for every occurrence of a lint.
+fn is_noise_note(message: &str) -> bool {
+    message.ends_with("on by default") || message.starts_with("for further information visit")
+}
+
+fn is_error_code(code: &str) -> bool {
+    code.len() == 5 && code.starts_with('E') && code[1..].bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Extracts the package name from a package id.
+///
+/// Package ids look like `path+file:///work/my-crate#0.1.0`, where the name is the last path
+/// segment, or `registry+https://github.com/rust-lang/crates.io-index#serde@1.0.219`.
+fn package_name(package_id: &str) -> String {
+    let (url, fragment) = package_id.split_once('#').unwrap_or((package_id, ""));
+    match fragment.split_once('@') {
+        Some((name, _version)) => name.to_owned(),
+        None => url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(url)
+            .to_owned(),
+    }
+}
+
+fn target_name(target: &RawTarget) -> String {
+    match target.kind.first().map(String::as_str) {
+        Some("lib" | "rlib" | "cdylib" | "dylib" | "staticlib" | "proc-macro") | None => {
+            "lib".to_owned()
+        }
+        Some(kind) => format!("{kind} \"{}\"", target.name),
+    }
+}
+
+/// Hashes what identifies a problem, leaving out line numbers so that edits elsewhere in the file
+/// don't change the id. The column keeps the same problem twice on one line apart.
+fn diagnostic_id(
+    level: Level,
+    code: Option<&str>,
+    message: &str,
+    primary: Option<&RawSpan>,
+) -> String {
+    let file = primary.map(|span| span.file_name.as_str()).unwrap_or("");
+    let line_text = primary
+        .and_then(|span| span.text.first())
+        .map(|text| text.text.trim())
+        .unwrap_or("");
+    let column = primary.map(|span| span.column_start).unwrap_or(0);
+
+    // FNV-1a, stable across runs and Rust versions unlike `DefaultHasher`
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for part in [
+        file,
+        &level.to_string(),
+        code.unwrap_or(""),
+        message,
+        line_text,
+        &column.to_string(),
+    ] {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:08x}", (hash ^ (hash >> 32)) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIPPY_OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"path+file:///tmp/diag#0.1.0","target":{"kind":["bin"],"name":"diag"},"profile":{},"features":[],"filenames":[],"executable":null,"fresh":false}
+{"reason":"compiler-message","package_id":"path+file:///tmp/diag#0.1.0","manifest_path":"/tmp/diag/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"diag","src_path":"/tmp/diag/src/main.rs","edition":"2024","doc":true,"doctest":false,"test":true},"message":{"rendered":"warning: unused variable: `x`\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"note","message":"`#[warn(unused_variables)]` (part of `#[warn(unused)]`) on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"if this is intentional, prefix it with an underscore","rendered":null,"spans":[{"byte_end":21,"byte_start":20,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"_x","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":10,"highlight_start":9,"text":"    let x = 5;"}]}]}],"level":"warning","message":"unused variable: `x`","spans":[{"byte_end":21,"byte_start":20,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":10,"highlight_start":9,"text":"    let x = 5;"}]}],"code":{"code":"unused_variables","explanation":null}}}
+{"reason":"compiler-message","package_id":"path+file:///tmp/diag#0.1.0","manifest_path":"/tmp/diag/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"diag","src_path":"/tmp/diag/src/main.rs","edition":"2024","doc":true,"doctest":false,"test":true},"message":{"rendered":"warning: unneeded `return` statement\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"for further information visit https://rust-lang.github.io/rust-clippy/rust-1.95.0/index.html#needless_return","rendered":null,"spans":[]},{"children":[],"code":null,"level":"note","message":"`#[warn(clippy::needless_return)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"remove `return`","rendered":null,"spans":[{"byte_end":87,"byte_start":79,"column_end":13,"column_start":5,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":7,"line_start":7,"suggested_replacement":"1","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":13,"highlight_start":5,"text":"    return 1;"}]},{"byte_end":88,"byte_start":87,"column_end":14,"column_start":13,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":7,"line_start":7,"suggested_replacement":"","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":14,"highlight_start":13,"text":"    return 1;"}]}]}],"level":"warning","message":"unneeded `return` statement","spans":[{"byte_end":87,"byte_start":79,"column_end":13,"column_start":5,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":7,"line_start":7,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":13,"highlight_start":5,"text":"    return 1;"}]}],"code":{"code":"clippy::needless_return","explanation":null}}}
+{"reason":"build-finished","success":true}"#;
+
+    const CHECK_ERROR_OUTPUT: &str = r#"{"reason":"compiler-message","package_id":"path+file:///work/app#my-app@0.1.0","target":{"kind":["lib"],"name":"my_app"},"message":{"rendered":"error[E0308]: mismatched types\n","$message_type":"diagnostic","children":[],"level":"error","message":"mismatched types","spans":[{"byte_end":45,"byte_start":41,"column_end":19,"column_start":15,"expansion":null,"file_name":"src/lib.rs","is_primary":true,"label":"expected `u32`, found `&str`","line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":19,"highlight_start":15,"text":"\tlet s: u32 = \"hi\";"}]},{"byte_end":38,"byte_start":35,"column_end":12,"column_start":9,"expansion":null,"file_name":"src/lib.rs","is_primary":false,"label":"expected due to this","line_end":3,"line_start":3,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":12,"highlight_start":9,"text":"\tlet s: u32 = \"hi\";"}]}],"code":{"code":"E0308","explanation":"Expected type did not match the received type."}}}
+{"reason":"compiler-message","package_id":"path+file:///work/app#my-app@0.1.0","target":{"kind":["lib"],"name":"my_app"},"message":{"rendered":"error: aborting due to 1 previous error\n","$message_type":"diagnostic","children":[],"level":"error","message":"aborting due to 1 previous error","spans":[],"code":null}}
+{"reason":"compiler-message","package_id":"path+file:///work/app#my-app@0.1.0","target":{"kind":["lib"],"name":"my_app"},"message":{"rendered":"For more information about this error, try `rustc --explain E0308`.\n","$message_type":"diagnostic","children":[],"level":"failure-note","message":"For more information about this error, try `rustc --explain E0308`.","spans":[],"code":null}}
+{"reason":"build-finished","success":false}"#;
+
+    #[test]
+    fn test_parse_clippy_warnings() {
+        let messages = CargoMessages::parse(CLIPPY_OUTPUT);
+        assert_eq!(messages.build_success, Some(true));
+        assert!(messages.other_lines.is_empty());
+
+        let [unused, needless_return] = &messages.diagnostics[..] else {
+            panic!("expected 2 diagnostics: {:?}", messages.diagnostics);
+        };
+
+        assert_eq!(unused.level, Level::Warning);
+        assert_eq!(unused.code, None);
+        assert_eq!(unused.lint.as_deref(), Some("unused_variables"));
+        assert_eq!(unused.message, "unused variable: `x`");
+        assert_eq!(unused.span.as_ref().unwrap().to_string(), "src/main.rs:2:9");
+        assert_eq!(unused.package.as_deref(), Some("diag"));
+        assert_eq!(unused.targets, ["bin \"diag\""]);
+        assert!(unused.notes.is_empty(), "{:?}", unused.notes);
+        assert_eq!(
+            unused.suggestions,
+            [Suggestion {
+                message: "if this is intentional, prefix it with an underscore".into(),
+                applicability: Applicability::MachineApplicable,
+                edits: vec![Edit {
+                    file: "src/main.rs".into(),
+                    byte_start: 20,
+                    byte_end: 21,
+                    line: 2,
+                    column: 9,
+                    line_text: Some("    let x = 5;".into()),
+                    replacement: "_x".into(),
+                }],
+            }]
+        );
+
+        assert_eq!(
+            needless_return.lint.as_deref(),
+            Some("clippy::needless_return")
+        );
+        assert_eq!(needless_return.suggestions[0].edits.len(), 2);
+        assert_ne!(unused.id, needless_return.id);
+    }
+
+    #[test]
+    fn test_parse_errors_skips_summaries() {
+        let messages = CargoMessages::parse(CHECK_ERROR_OUTPUT);
+        assert_eq!(messages.build_success, Some(false));
+
+        let [mismatched] = &messages.diagnostics[..] else {
+            panic!("expected 1 diagnostic: {:?}", messages.diagnostics);
+        };
+        assert_eq!(mismatched.level, Level::Error);
+        assert_eq!(mismatched.code.as_deref(), Some("E0308"));
+        assert_eq!(mismatched.lint, None);
+        assert_eq!(
+            mismatched.label.as_deref(),
+            Some("expected `u32`, found `&str`")
+        );
+        assert_eq!(mismatched.package.as_deref(), Some("my-app"));
+        assert_eq!(mismatched.targets, ["lib"]);
+    }
+
+    #[test]
+    fn test_diagnostic_id_ignores_line_numbers() {
+        let moved = CHECK_ERROR_OUTPUT
+            .replace(
+                r#""line_end":3,"line_start":3"#,
+                r#""line_end":9,"line_start":9"#,
+            )
+            .replace(
+                r#""byte_end":45,"byte_start":41"#,
+                r#""byte_end":90,"byte_start":86"#,
+            );
+
+        let before = CargoMessages::parse(CHECK_ERROR_OUTPUT);
+        let after = CargoMessages::parse(moved);
+        assert_eq!(before.diagnostics[0].id, after.diagnostics[0].id);
+        assert_ne!(
+            before.diagnostics[0].span.as_ref().unwrap().line,
+            after.diagnostics[0].span.as_ref().unwrap().line
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_id_includes_column() {
+        let shifted = CHECK_ERROR_OUTPUT.replace(
+            r#""column_end":19,"column_start":15"#,
+            r#""column_end":23,"column_start":19"#,
+        );
+
+        let before = CargoMessages::parse(CHECK_ERROR_OUTPUT);
+        let after = CargoMessages::parse(shifted);
+        assert_ne!(before.diagnostics[0].id, after.diagnostics[0].id);
+    }
+
+    #[test]
+    fn test_parse_keeps_non_json_lines() {
+        let messages = CargoMessages::parse(
+            "hello from build script\n\n{\"reason\":\"build-finished\",\"success\":true}",
+        );
+        assert_eq!(messages.other_lines, ["hello from build script"]);
+        assert!(messages.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(package_name("path+file:///tmp/diag#0.1.0"), "diag");
+        assert_eq!(package_name("path+file:///work/app#my-app@0.1.0"), "my-app");
+        assert_eq!(
+            package_name("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.219"),
+            "serde"
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt::Write;
+
+use super::{Diagnostic, DiagnosticReport, GroupBy, Status, Suggestion};
+use crate::{Tool, command::Stderr, tools::rustc::RustcExplainRmcpTool};
+
+/// Cargo status verbs that only report progress.
+const STATUS_VERBS: [&str; 10] = [
+    "Compiling",
+    "Checking",
+    "Finished",
+    "Blocking",
+    "Updating",
+    "Locking",
+    "Adding",
+    "Downloading",
+    "Downloaded",
+    "Fresh",
+];
+
+/// Removes progress lines from cargo's stderr, returning `None` if nothing else is left.
+pub(crate) fn filter_cargo_status(stderr: Stderr) -> Option<Stderr> {
+    let lines: Vec<&str> = stderr
+        .0
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim_start();
+            let verb = trimmed.split_whitespace().next().unwrap_or("");
+            let is_status = STATUS_VERBS.contains(&verb);
+            // "warning: `app` (lib) generated 2 warnings", the counts are in the summary
+            let is_count = trimmed.starts_with("warning: `") && trimmed.contains(" generated ");
+            !is_status && !is_count
+        })
+        .collect();
+
+    let filtered = lines.join("\n");
+    let filtered = filtered.trim();
+    (!filtered.is_empty()).then(|| Stderr(filtered.to_owned()))
+}
+
+pub(super) fn render_report(report: &DiagnosticReport) -> String {
+    let mut output = if report.errors + report.warnings == 0 && report.diagnostics.is_empty() {
+        "No compiler diagnostics".to_owned()
+    } else {
+        format!("{} error(s), {} warning(s)", report.errors, report.warnings)
+    };
+    if report.filtered > 0 {
+        let _ = write!(output, ", {} filtered out", report.filtered);
+    }
+    if let Some(changes) = &report.changes {
+        let _ = write!(
+            output,
+            "; since the last run: {} new, {} still present, {} fixed",
+            changes.new, changes.persisting, changes.fixed
+        );
+    }
+    output.push('\n');
+
+    match report.group_by {
+        None => {
+            for diagnostic in &report.diagnostics {
+                render_diagnostic(&mut output, diagnostic, true);
+            }
+        }
+        Some(group_by) => {
+            for (key, group) in group_diagnostics(&report.diagnostics, group_by) {
+                let _ = writeln!(output, "\n{key}: {} diagnostic(s)", group.len());
+                for diagnostic in group {
+                    render_diagnostic(&mut output, diagnostic, group_by != GroupBy::File);
+                }
+            }
+        }
+    }
+
+    if report.omitted > 0 {
+        let _ = writeln!(
+            output,
+            "\n... {} more omitted, raise `max_diagnostics` or narrow the filters to see them",
+            report.omitted
+        );
+    }
+
+    if report.snippets_omitted > 0 {
+        let _ = writeln!(
+            output,
+            "\nSource snippets left out for {} diagnostic(s) to stay within the size limit",
+            report.snippets_omitted
+        );
+    }
+
+    if !report.fixed.is_empty() {
+        output.push_str("\nFixed since the last run:\n");
+        for diagnostic in &report.fixed {
+            let _ = write!(output, "- {}", diagnostic.level);
+            if let Some(code) = diagnostic.code.as_ref().or(diagnostic.lint.as_ref()) {
+                let _ = write!(output, "[{code}]");
+            }
+            let _ = write!(output, ": {}", diagnostic.message);
+            if let Some(span) = &diagnostic.span {
+                let _ = write!(output, " at {span}");
+            }
+            let _ = writeln!(output, " (id: {})", diagnostic.id);
+        }
+    }
+
+    for (code, excerpt) in &report.explanations {
+        let _ = writeln!(output, "\n{code}: {excerpt}");
+    }
+    if !report.unexplained.is_empty() {
+        let _ = writeln!(
+            output,
+            "\nRun #{} for {}",
+            RustcExplainRmcpTool::NAME,
+            report.unexplained.join(", ")
+        );
+    }
+    output.truncate(output.trim_end().len());
+    output
+}
+
+/// Groups diagnostics by file or lint, keeping the order in which the groups first appear.
+fn group_diagnostics(
+    diagnostics: &[Diagnostic],
+    group_by: GroupBy,
+) -> Vec<(&str, Vec<&Diagnostic>)> {
+    let mut groups: Vec<(&str, Vec<&Diagnostic>)> = Vec::new();
+    for diagnostic in diagnostics {
+        let key = match group_by {
+            GroupBy::File => diagnostic.span.as_ref().map(|span| span.file.as_str()),
+            GroupBy::Lint => diagnostic.code.as_deref().or(diagnostic.lint.as_deref()),
+        }
+        .unwrap_or("(none)");
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(diagnostic),
+            None => groups.push((key, vec![diagnostic])),
+        }
+    }
+    groups
+}
+
+fn render_diagnostic(output: &mut String, diagnostic: &Diagnostic, show_file: bool) {
+    let _ = write!(output, "\n{}", diagnostic.level);
+    if let Some(code) = diagnostic.code.as_ref().or(diagnostic.lint.as_ref()) {
+        let _ = write!(output, "[{code}]");
+    }
+    let _ = write!(output, ": {} (id: {}", diagnostic.message, diagnostic.id);
+    if diagnostic.status == Some(Status::New) {
+        output.push_str(", new");
+    }
+    output.push_str(")\n");
+
+    if let Some(span) = &diagnostic.span {
+        if show_file {
+            let _ = write!(output, "  --> {span}");
+        } else {
+            let _ = write!(output, "  --> {}:{}", span.line, span.column);
+        }
+        if let Some(label) = &diagnostic.label {
+            let _ = write!(output, ": {label}");
+        }
+        output.push('\n');
+    }
+    if let Some(snippet) = &diagnostic.snippet {
+        for line in snippet.lines() {
+            let _ = writeln!(output, "  {line}");
+        }
+    }
+    for note in &diagnostic.notes {
+        let _ = writeln!(output, "  = {note}");
+    }
+    for suggestion in &diagnostic.suggestions {
+        let _ = writeln!(output, "  = help: {}", render_suggestion(suggestion));
+    }
+}
+
+fn render_suggestion(suggestion: &Suggestion) -> String {
+    let mut text = suggestion.message.clone();
+    match &suggestion.edits[..] {
+        [edit] if !edit.replacement.is_empty() && !edit.replacement.contains('\n') => {
+            let _ = write!(text, ": `{}`", edit.replacement);
+        }
+        [_] => {}
+        edits => {
+            let _ = write!(text, " ({} edits)", edits.len());
+        }
+    }
+    let _ = write!(text, " [{:?}]", suggestion.applicability);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::CargoMessages;
+
+    #[test]
+    fn test_filter_cargo_status() {
+        let stderr = Stderr(
+            "Checking diag v0.1.0 (/tmp/diag)\n\
+             warning: `diag` (bin \"diag\") generated 2 warnings (run `cargo clippy --fix --bin \"diag\"` to apply 2 suggestions)\n\
+             \x20   Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.12s"
+                .into(),
+        );
+        assert!(filter_cargo_status(stderr).is_none());
+
+        let stderr = Stderr(
+            "   Compiling diag v0.1.0 (/tmp/diag)\n\
+             error: could not compile `diag` (bin \"diag\") due to 1 previous error"
+                .into(),
+        );
+        assert_eq!(
+            filter_cargo_status(stderr).unwrap().0,
+            "error: could not compile `diag` (bin \"diag\") due to 1 previous error"
+        );
+    }
+
+    #[test]
+    fn test_render_report() {
+        let messages = CargoMessages::parse(
+            r#"{"reason":"compiler-message","package_id":"path+file:///tmp/diag#0.1.0","target":{"kind":["bin"],"name":"diag"},"message":{"children":[{"children":[],"code":null,"level":"help","message":"if this is intentional, prefix it with an underscore","spans":[{"byte_end":21,"byte_start":20,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"_x","suggestion_applicability":"MachineApplicable","text":[{"highlight_end":10,"highlight_start":9,"text":"    let x = 5;"}]}]}],"level":"warning","message":"unused variable: `x`","spans":[{"byte_end":21,"byte_start":20,"column_end":10,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null,"text":[{"highlight_end":10,"highlight_start":9,"text":"    let x = 5;"}]}],"code":{"code":"unused_variables","explanation":null}}}"#,
+        );
+        let report = DiagnosticReport::new(true, messages.diagnostics);
+        let id = &report.diagnostics[0].id;
+
+        assert_eq!(
+            render_report(&report),
+            format!(
+                "0 error(s), 1 warning(s)\n\
+                 \n\
+                 warning[unused_variables]: unused variable: `x` (id: {id})\n\
+                 \x20 --> src/main.rs:2:9\n\
+                 \x20 = help: if this is intentional, prefix it with an underscore: `_x` [MachineApplicable]"
+            )
+        );
+
+        assert_eq!(
+            render_report(&DiagnosticReport::new(true, Vec::new())),
+            "No compiler diagnostics"
+        );
+    }
+
+    #[test]
+    fn test_render_grouped_report() {
+        let diagnostic = |id: &str, file: &str, line: usize, lint: &str| Diagnostic {
+            id: id.into(),
+            level: super::super::Level::Warning,
+            code: None,
+            lint: Some(lint.into()),
+            message: format!("{lint} here"),
+            span: Some(super::super::Span {
+                file: file.into(),
+                line,
+                column: 5,
+                end_line: line,
+                end_column: 6,
+            }),
+            label: None,
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+            package: None,
+            targets: Vec::new(),
+            status: None,
+            snippet: None,
+        };
+        let mut report = DiagnosticReport::new(
+            true,
+            vec![
+                diagnostic("a", "src/lib.rs", 1, "dead_code"),
+                diagnostic("b", "src/main.rs", 2, "dead_code"),
+                diagnostic("c", "src/lib.rs", 3, "unused_mut"),
+            ],
+        );
+        report.group_by = Some(GroupBy::File);
+        report.omitted = 4;
+
+        assert_eq!(
+            render_report(&report),
+            "0 error(s), 3 warning(s)\n\
+             \n\
+             src/lib.rs: 2 diagnostic(s)\n\
+             \n\
+             warning[dead_code]: dead_code here (id: a)\n\
+             \x20 --> 1:5\n\
+             \n\
+             warning[unused_mut]: unused_mut here (id: c)\n\
+             \x20 --> 3:5\n\
+             \n\
+             src/main.rs: 1 diagnostic(s)\n\
+             \n\
+             warning[dead_code]: dead_code here (id: b)\n\
+             \x20 --> 2:5\n\
+             \n\
+             ... 4 more omitted, raise `max_diagnostics` or narrow the filters to see them"
+        );
+
+        report.group_by = Some(GroupBy::Lint);
+        report.omitted = 0;
+        let rendered = render_report(&report);
+        assert!(rendered.contains("\ndead_code: 2 diagnostic(s)\n"));
+        assert!(rendered.contains("  --> src/main.rs:2:5"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use super::Span;
+
+/// Total size of the snippets attached to one report.
+pub(super) const SNIPPETS_BUDGET: usize = 6000;
+
+/// Most context lines on each side of a primary span.
+const MAX_CONTEXT_LINES: usize = 10;
+
+/// Most lines of a multi-line span shown before its context.
+const MAX_SPAN_LINES: usize = 5;
+
+/// Lines are cut at this display width, e.g. in minified or generated code.
+const MAX_LINE_WIDTH: usize = 160;
+
+const TAB_WIDTH: usize = 4;
+
+/// Renders the lines around `span` with line numbers and a caret marker under the primary span,
+/// or returns `None` if the span is outside of `content`.
+pub(crate) fn snippet(content: &str, span: &Span, context_lines: usize) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    if span.line == 0 || span.line > lines.len() {
+        return None;
+    }
+
+    let context_lines = context_lines.min(MAX_CONTEXT_LINES);
+    let span_end = span
+        .end_line
+        .clamp(span.line, span.line + MAX_SPAN_LINES - 1);
+    let first = span.line.saturating_sub(context_lines).max(1);
+    let last = (span_end + context_lines).min(lines.len());
+    let gutter = last.to_string().len();
+
+    let mut snippet = String::new();
+    for number in first..=last {
+        let line = DisplayLine::new(lines[number - 1]);
+        let numbered = format!("{number:>gutter$} | {}", line.text);
+        snippet.push_str(numbered.trim_end());
+        snippet.push('\n');
+
+        if number == span.line {
+            let start = line.column(span.column);
+            let end = if span.end_line == span.line {
+                line.column(span.end_column)
+            } else {
+                line.width()
+            };
+            let _ = writeln!(
+                snippet,
+                "{:gutter$} | {}{}",
+                "",
+                " ".repeat(start),
+                "^".repeat(end.saturating_sub(start).max(1))
+            );
+        }
+    }
+    snippet.truncate(snippet.trim_end().len());
+    Some(snippet)
+}
+
+/// A source line as displayed, with tabs expanded and long lines cut.
+struct DisplayLine {
+    text: String,
+    /// Display column at which each char starts, plus the width of the whole line
+    columns: Vec<usize>,
+}
+
+impl DisplayLine {
+    fn new(line: &str) -> Self {
+        let mut text = String::with_capacity(line.len());
+        let mut columns = Vec::with_capacity(line.len() + 1);
+        let mut width = 0;
+        for c in line.chars() {
+            columns.push(width);
+            if c == '\t' {
+                let spaces = TAB_WIDTH - width % TAB_WIDTH;
+                text.extend(std::iter::repeat_n(' ', spaces));
+                width += spaces;
+            } else if width + char_width(c) <= MAX_LINE_WIDTH {
+                text.push(c);
+                width += char_width(c);
+            } else {
+                text.push('…');
+                width += 1;
+                break;
+            }
+        }
+        columns.push(width);
+        Self { text, columns }
+    }
+
+    fn width(&self) -> usize {
+        self.columns.last().copied().unwrap_or(0)
+    }
+
+    /// Display column of a 1-based char column as reported by rustc.
+    fn column(&self, column: usize) -> usize {
+        let index = column.saturating_sub(1);
+        self.columns
+            .get(index)
+            .copied()
+            .unwrap_or_else(|| self.width())
+    }
+}
+
+/// Approximates the terminal width of a char: 0 for combining marks, 2 for wide East Asian chars
+/// and emoji, 1 otherwise.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(line: usize, column: usize, end_line: usize, end_column: usize) -> Span {
+        Span {
+            file: "src/main.rs".into(),
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+
+    #[test]
+    fn test_snippet() {
+        let content = "fn main() {\n\tlet s: u32 = \"hi\";\n}\n";
+        assert_eq!(
+            snippet(content, &span(2, 15, 2, 19), 1).unwrap(),
+            "1 | fn main() {\n\
+             2 |     let s: u32 = \"hi\";\n\
+             \x20 |                  ^^^^\n\
+             3 | }"
+        );
+        assert_eq!(
+            snippet(content, &span(2, 15, 2, 19), 0).unwrap(),
+            "2 |     let s: u32 = \"hi\";\n\
+             \x20 |                  ^^^^"
+        );
+        assert!(snippet(content, &span(9, 1, 9, 2), 1).is_none());
+    }
+
+    #[test]
+    fn test_snippet_wide_chars_and_gutter() {
+        let content = format!("{}let 名前 = x;\n", "\n".repeat(9));
+        assert_eq!(
+            snippet(&content, &span(10, 10, 10, 11), 0).unwrap(),
+            "10 | let 名前 = x;\n\
+             \x20  |            ^"
+        );
+    }
+
+    #[test]
+    fn test_snippet_trims_empty_lines() {
+        let content = "fn a() {}\n\nfn b() {}\n";
+        assert_eq!(
+            snippet(content, &span(3, 4, 3, 5), 1).unwrap(),
+            "2 |\n\
+             3 | fn b() {}\n\
+             \x20 |    ^"
+        );
+    }
+
+    #[test]
+    fn test_snippet_multi_line_span() {
+        let content = "fn a() {\n    foo(\n        1,\n    );\n}\n";
+        assert_eq!(
+            snippet(content, &span(2, 5, 4, 6), 0).unwrap(),
+            "2 |     foo(\n\
+             \x20 |     ^^^^\n\
+             3 |         1,\n\
+             4 |     );"
+        );
+    }
+}
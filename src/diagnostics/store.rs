@@ -0,0 +1,49 @@
+use std::sync::Mutex;
+
+use super::Diagnostic;
+use crate::tools::workspace_root;
+
+/// Diagnostics of the most recent check-family runs, newest first, keyed by workspace and command
+/// line. The command line covers the target configuration, e.g. `--all-targets` or `--features`.
+static RUNS: Mutex<Vec<(String, Vec<Diagnostic>)>> = Mutex::new(Vec::new());
+
+/// Runs with different arguments (e.g. with and without `all_targets`) kept at the same time.
+const MAX_RUNS: usize = 8;
+
+fn run_key(command: &str) -> String {
+    format!("{}: {command}", workspace_root().display())
+}
+
+/// Returns the diagnostics of the last run of the same command.
+pub(super) fn previous_run(command: &str) -> Option<Vec<Diagnostic>> {
+    let key = run_key(command);
+    let runs = RUNS.lock().ok()?;
+    runs.iter()
+        .find(|(run, _)| *run == key)
+        .map(|(_, diagnostics)| diagnostics.clone())
+}
+
+/// Remembers the diagnostics of a run, replacing the previous run of the same command.
+pub(super) fn remember(command: &str, diagnostics: &[Diagnostic]) {
+    let key = run_key(command);
+    let Ok(mut runs) = RUNS.lock() else {
+        return;
+    };
+    runs.retain(|(run, _)| *run != key);
+    runs.insert(0, (key, diagnostics.to_vec()));
+    runs.truncate(MAX_RUNS);
+}
+
+/// Finds a diagnostic by id, preferring the most recent run that reported it.
+pub(crate) fn find_diagnostic(id: &str) -> Option<Diagnostic> {
+    let runs = RUNS.lock().ok()?;
+    runs.iter()
+        .flat_map(|(_, diagnostics)| diagnostics)
+        .find(|diagnostic| diagnostic.id == id)
+        .cloned()
+}
+
+/// Returns `true` if no check-family run has been remembered yet.
+pub(crate) fn is_empty() -> bool {
+    RUNS.lock().map(|runs| runs.is_empty()).unwrap_or(true)
+}
@@ -0,0 +1,232 @@
+//! Line-based unified diffs, used to show the changes a tool made to source files.
+
+use std::fmt::Write;
+
+/// Lines of unchanged context around each hunk.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Returns a unified diff between `old` and `new`, or an empty string if they have the same lines.
+pub(crate) fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    if ops.iter().all(|op| *op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut output = format!("--- a/{path}\n+++ b/{path}\n");
+    for hunk in hunks(&ops) {
+        render_hunk(
+            &mut output,
+            &ops[hunk.ops.clone()],
+            &hunk,
+            &old_lines,
+            &new_lines,
+        );
+    }
+    output
+}
+
+/// Shortest edit script between two lines sequences, using Myers' algorithm.
+///
+/// Runs in `O((N + M) * D)`, which is fast for the small edits tools make to large files.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    // Furthest x reached on each diagonal k = x - y, indexed by k + max
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + max) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    let mut ops = Vec::with_capacity((n + m) as usize);
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let at = |k: isize| v[(k + max) as usize];
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            ops.push(Op::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    ops.reverse();
+    ops
+}
+
+struct Hunk {
+    ops: std::ops::Range<usize>,
+    old_start: usize,
+    new_start: usize,
+}
+
+/// Splits the edit script into hunks of changes with up to `CONTEXT_LINES` of context, merging
+/// changes that are closer than twice that.
+fn hunks(ops: &[Op]) -> Vec<Hunk> {
+    // Old and new line index before each op
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old, mut new) = (0, 0);
+    for op in ops {
+        positions.push((old, new));
+        match op {
+            Op::Equal => {
+                old += 1;
+                new += 1;
+            }
+            Op::Delete => old += 1,
+            Op::Insert => new += 1,
+        }
+    }
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i] != Op::Equal).collect();
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for change in changes {
+        let start = change.saturating_sub(CONTEXT_LINES);
+        let end = (change + 1 + CONTEXT_LINES).min(ops.len());
+        match hunks.last_mut() {
+            Some(hunk) if start <= hunk.ops.end => hunk.ops.end = end,
+            _ => hunks.push(Hunk {
+                ops: start..end,
+                old_start: positions[start].0,
+                new_start: positions[start].1,
+            }),
+        }
+    }
+    hunks
+}
+
+fn render_hunk(output: &mut String, ops: &[Op], hunk: &Hunk, old: &[&str], new: &[&str]) {
+    let old_count = ops.iter().filter(|op| **op != Op::Insert).count();
+    let new_count = ops.iter().filter(|op| **op != Op::Delete).count();
+    // Empty ranges start at the line before, as in GNU diff
+    let start = |start: usize, count: usize| if count == 0 { start } else { start + 1 };
+    let _ = writeln!(
+        output,
+        "@@ -{},{old_count} +{},{new_count} @@",
+        start(hunk.old_start, old_count),
+        start(hunk.new_start, new_count)
+    );
+
+    let (mut old_index, mut new_index) = (hunk.old_start, hunk.new_start);
+    for op in ops {
+        match op {
+            Op::Equal => {
+                let _ = writeln!(output, " {}", old[old_index]);
+                old_index += 1;
+                new_index += 1;
+            }
+            Op::Delete => {
+                let _ = writeln!(output, "-{}", old[old_index]);
+                old_index += 1;
+            }
+            Op::Insert => {
+                let _ = writeln!(output, "+{}", new[new_index]);
+                new_index += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "fn a() -> u32 {\n    let x = 5;\n    return 1;\n}\n";
+        let new = "fn a() -> u32 {\n    let _x = 5;\n    1\n}\n";
+
+        assert_eq!(
+            unified_diff("src/lib.rs", old, new),
+            "--- a/src/lib.rs\n\
+             +++ b/src/lib.rs\n\
+             @@ -1,4 +1,4 @@\n\
+             \x20fn a() -> u32 {\n\
+             -    let x = 5;\n\
+             -    return 1;\n\
+             +    let _x = 5;\n\
+             +    1\n\
+             \x20}\n"
+        );
+        assert_eq!(unified_diff("src/lib.rs", old, old), "");
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old: Vec<String> = (1..=20).map(|i| format!("line {i}")).collect();
+        let mut new = old.clone();
+        new[1] = "changed 2".into();
+        new.remove(15);
+        new.push("line 21".into());
+
+        assert_eq!(
+            unified_diff("f", &old.join("\n"), &new.join("\n")),
+            "--- a/f\n\
+             +++ b/f\n\
+             @@ -1,5 +1,5 @@\n\
+             \x20line 1\n\
+             -line 2\n\
+             +changed 2\n\
+             \x20line 3\n\
+             \x20line 4\n\
+             \x20line 5\n\
+             @@ -13,8 +13,8 @@\n\
+             \x20line 13\n\
+             \x20line 14\n\
+             \x20line 15\n\
+             -line 16\n\
+             \x20line 17\n\
+             \x20line 18\n\
+             \x20line 19\n\
+             \x20line 20\n\
+             +line 21\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_from_empty() {
+        assert_eq!(
+            unified_diff("new.rs", "", "fn main() {}\n"),
+            "--- a/new.rs\n+++ b/new.rs\n@@ -0,0 +1,1 @@\n+fn main() {}\n"
+        );
+    }
+}
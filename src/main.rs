@@ -1,18 +1,26 @@
+mod arguments;
 mod command;
+mod diagnostics;
+mod diff;
 mod meta;
+mod process;
 mod response;
 mod rmcp_server;
+mod schema;
 mod serde_utils;
+mod testing;
 mod tool;
 mod tools;
 mod version;
 
 use anyhow::Context;
+use arguments::UnknownArgumentsPolicy;
 use clap::Parser;
 use command::execute_command;
 use response::Response;
 use rmcp::ServiceExt;
 use rmcp::service::QuitReason;
+use schema::{SchemaDetail, SchemaDialect};
 use tool::Tool;
 use tracing_appender::rolling;
 use tracing_subscriber::{EnvFilter, fmt};
@@ -44,6 +52,24 @@ struct Args {
     /// Disable experimental recommendations for agent in tool responses
     #[arg(long)]
     no_recommendations: bool,
+
+    /// Default locking mode for cargo tools that resolve dependencies,
+    /// used when a tool call doesn't specify `locking_mode`
+    #[arg(long, value_parser = tools::common::LOCKING_MODES)]
+    locking_mode: Option<String>,
+
+    /// How to handle tool arguments that are not part of the tool's schema
+    #[arg(long, value_enum, default_value_t = UnknownArgumentsPolicy::Warn)]
+    unknown_arguments: UnknownArgumentsPolicy,
+
+    /// JSON Schema dialect for tool input schemas.
+    /// By default, it is chosen from the client name sent at initialization.
+    #[arg(long, value_enum)]
+    schema_dialect: Option<SchemaDialect>,
+
+    /// How much of the argument documentation is included in tool schemas
+    #[arg(long, value_enum, default_value_t = SchemaDetail::Minimal)]
+    schema_detail: SchemaDetail,
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -77,7 +103,15 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("No workspace root specified, using current directory");
     }
 
-    let server = rmcp_server::Server::new(&args.disabled_tools, args.no_recommendations);
+    if let Some(locking_mode) = args.locking_mode {
+        tracing::info!("Default locking mode: {locking_mode}");
+        tools::common::set_default_locking_mode(locking_mode);
+    }
+
+    let server = rmcp_server::Server::new(&args.disabled_tools, args.no_recommendations)
+        .with_unknown_arguments(args.unknown_arguments)
+        .with_schema_dialect(args.schema_dialect)
+        .with_schema_detail(args.schema_detail);
 
     // Handle documentation generation mode
     if let Some(output_file) = args.generate_docs {
@@ -95,7 +129,26 @@ async fn main() -> anyhow::Result<()> {
 
     eprintln!("Rust MCP Server started on stdio");
 
-    let result = service.waiting().await;
+    // Background processes run in their own process group, so they don't get the signal that
+    // stops the server and have to be stopped here. With `panic = "abort"` nothing after a panic
+    // runs either, hence the hook.
+    let default_panic_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_panic_hook(info);
+        process::kill_all();
+    }));
+
+    let result = tokio::select! {
+        result = service.waiting() => result,
+        signal = shutdown_signal() => {
+            tracing::info!("Received {signal}, shutting down");
+            process::stop_all();
+            // Returning would wait for the blocking read of stdin, which only ends once the client
+            // closes it.
+            std::process::exit(0);
+        }
+    };
+    process::stop_all();
 
     match result {
         Ok(QuitReason::Closed) => tracing::info!("Server closed normally"),
@@ -112,3 +165,34 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Waits for a signal asking the server to stop and returns its name.
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let (Ok(mut terminate), Ok(mut hangup)) = (
+            signal(SignalKind::terminate()),
+            signal(SignalKind::hangup()),
+        ) else {
+            tracing::warn!("Failed to install signal handlers");
+            return std::future::pending().await;
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+            _ = hangup.recv() => "SIGHUP",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => "Ctrl-C",
+            Err(error) => {
+                tracing::warn!("Failed to install signal handler: {error}");
+                std::future::pending().await
+            }
+        }
+    }
+}
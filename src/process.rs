@@ -0,0 +1,419 @@
+//! Programs started in the background, e.g. a web service the agent tests, with their output kept
+//! in memory until they are stopped or the session ends.
+
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Read},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex, MutexGuard, TryLockError,
+        atomic::{AtomicU32, Ordering},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use rmcp::ErrorData;
+
+use crate::{
+    command::{command_line, spawn_error},
+    tools::apply_workspace_root,
+};
+
+/// Output kept per stream and process, older output is dropped.
+const LOG_CAPACITY: usize = 1 << 20;
+
+/// Processes kept at once, running or exited but not stopped.
+pub(crate) const MAX_PROCESSES: usize = 10;
+
+/// How often a waiting call checks the process and its output.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Output of a stream, addressed by offsets counted from the start of the process.
+#[derive(Debug, Default)]
+pub(crate) struct Log {
+    /// Offset of the first byte still kept
+    start: usize,
+    data: Vec<u8>,
+}
+
+impl Log {
+    fn push(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+        if self.data.len() > LOG_CAPACITY {
+            let dropped = self.data.len() - LOG_CAPACITY;
+            self.data.drain(..dropped);
+            self.start += dropped;
+        }
+    }
+
+    /// Offset after the last byte.
+    pub(crate) fn end(&self) -> usize {
+        self.start + self.data.len()
+    }
+
+    /// Up to `max_bytes` from `offset`, or the last `max_bytes` without an offset. Returns the
+    /// offsets the text starts and ends at, the start is later than `offset` if that output was
+    /// dropped. The end is counted in bytes of the output, which differs from the text length when
+    /// invalid UTF-8 was replaced. A character cut off at the end is left for the next read.
+    pub(crate) fn read(&self, offset: Option<usize>, max_bytes: usize) -> (usize, usize, String) {
+        let from = match offset {
+            Some(offset) => offset.clamp(self.start, self.end()),
+            None => self.end().saturating_sub(max_bytes).max(self.start),
+        };
+        let begin = from - self.start;
+        let mut end = (begin + max_bytes).min(self.data.len());
+        let complete = complete_utf8_len(&self.data[begin..end]);
+        if complete > 0 {
+            end = begin + complete;
+        }
+        (
+            from,
+            self.start + end,
+            String::from_utf8_lossy(&self.data[begin..end]).into_owned(),
+        )
+    }
+
+    pub(crate) fn contains(&self, text: &str) -> bool {
+        String::from_utf8_lossy(&self.data).contains(text)
+    }
+}
+
+/// Length of `bytes` without a character that is cut off at the end.
+fn complete_utf8_len(bytes: &[u8]) -> usize {
+    let mut checked = 0;
+    loop {
+        match std::str::from_utf8(&bytes[checked..]) {
+            Ok(_) => return bytes.len(),
+            Err(error) => match error.error_len() {
+                Some(invalid) => checked += error.valid_up_to() + invalid,
+                None => return checked + error.valid_up_to(),
+            },
+        }
+    }
+}
+
+/// A program started in the background.
+pub(crate) struct Process {
+    pub(crate) id: u32,
+    pub(crate) command: String,
+    pub(crate) started: Instant,
+    child: Child,
+    exit: Option<ExitStatus>,
+    pub(crate) stdout: Arc<Mutex<Log>>,
+    pub(crate) stderr: Arc<Mutex<Log>>,
+    /// What cargo printed while it built the program, for processes started with `cargo run`
+    pub(crate) build_output: Option<Arc<Mutex<String>>>,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl Process {
+    pub(crate) fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// The exit status once the process has exited.
+    pub(crate) fn exit_status(&mut self) -> Option<ExitStatus> {
+        if self.exit.is_none() {
+            self.exit = self.child.try_wait().ok().flatten();
+        }
+        self.exit
+    }
+
+    /// Sends a signal to the process and the processes it started, e.g. the program `cargo run`
+    /// started. Only `KILL` is supported outside Unix.
+    pub(crate) fn signal(&mut self, signal: &str) -> bool {
+        #[cfg(unix)]
+        {
+            crate::command::signal_process_group(self.pid(), signal)
+        }
+        #[cfg(not(unix))]
+        {
+            signal == "KILL" && self.child.kill().is_ok()
+        }
+    }
+
+    /// Waits for the process to exit, at most `timeout`.
+    fn wait_for_exit(&mut self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        while self.exit_status().is_none() && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Terminates the process, killing it if it is still running after `grace`.
+    pub(crate) fn stop(&mut self, grace: Duration) {
+        if self.exit_status().is_none() {
+            if !grace.is_zero() && self.signal("TERM") {
+                self.wait_for_exit(grace);
+            }
+            if self.exit_status().is_none() {
+                self.signal("KILL");
+                let _ = self.child.kill();
+                self.exit = self.child.wait().ok();
+            }
+        }
+        self.join_readers();
+    }
+
+    /// Waits for the rest of the output once the process exited. A process it started outside
+    /// its process group may keep the pipes open, so this gives up after a second.
+    fn join_readers(&mut self) {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while !self.readers.iter().all(JoinHandle::is_finished) && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        self.readers.clear();
+    }
+}
+
+static PROCESSES: Mutex<BTreeMap<u32, Process>> = Mutex::new(BTreeMap::new());
+
+fn processes() -> MutexGuard<'static, BTreeMap<u32, Process>> {
+    PROCESSES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Starts `cmd` in the background. With `cargo_run`, cargo's build output is kept apart from
+/// what the program prints to stderr.
+pub(crate) fn start(mut cmd: Command, cargo_run: bool) -> Result<u32, ErrorData> {
+    let mut processes = processes();
+    if processes.len() >= MAX_PROCESSES {
+        return Err(ErrorData::invalid_params(
+            format!(
+                "{MAX_PROCESSES} background processes are kept at most. Stop one that is no longer needed first"
+            ),
+            None,
+        ));
+    }
+
+    apply_workspace_root(&mut cmd);
+    let cmd_line = command_line(&cmd);
+    tracing::info!("Starting background process: {cmd_line}");
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // Its own process group, so stopping it also stops the program `cargo run` started
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    let mut child = cmd.spawn().map_err(|e| spawn_error(&cmd, &cmd_line, e))?;
+
+    let stdout = Arc::new(Mutex::new(Log::default()));
+    let stderr = Arc::new(Mutex::new(Log::default()));
+    let build_output = cargo_run.then(|| Arc::new(Mutex::new(String::new())));
+    let mut readers = Vec::new();
+    if let Some(pipe) = child.stdout.take() {
+        readers.push(capture(pipe, stdout.clone(), None));
+    }
+    if let Some(pipe) = child.stderr.take() {
+        readers.push(capture(pipe, stderr.clone(), build_output.clone()));
+    }
+
+    static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    processes.insert(
+        id,
+        Process {
+            id,
+            command: cmd_line,
+            started: Instant::now(),
+            child,
+            exit: None,
+            stdout,
+            stderr,
+            build_output,
+            readers,
+        },
+    );
+    Ok(id)
+}
+
+/// Copies a pipe into `log`. With `build_output`, the lines up to cargo's "Running `...`" line
+/// go there instead.
+fn capture(
+    pipe: impl Read + Send + 'static,
+    log: Arc<Mutex<Log>>,
+    build_output: Option<Arc<Mutex<String>>>,
+) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut pipe = BufReader::new(pipe);
+        if let Some(build_output) = build_output {
+            let mut line = String::new();
+            while pipe.read_line(&mut line).is_ok_and(|read| read > 0) {
+                let running = line.trim_start().starts_with("Running `");
+                build_output
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push_str(&line);
+                line.clear();
+                if running {
+                    break;
+                }
+            }
+        }
+        let mut buffer = [0; 8192];
+        loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => log
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push(&buffer[..read]),
+            }
+        }
+    })
+}
+
+/// Runs `f` with the process, or fails if there is no process with this id.
+pub(crate) fn with_process<T>(id: u32, f: impl FnOnce(&mut Process) -> T) -> Result<T, ErrorData> {
+    let mut processes = processes();
+    let process = processes.get_mut(&id).ok_or_else(|| unknown_process(id))?;
+    Ok(f(process))
+}
+
+/// Waits until `ready` returns true or the process exits, at most `timeout`, without blocking the
+/// other processes in between. Returns whether `ready` returned true.
+pub(crate) fn wait_until(
+    id: u32,
+    timeout: Duration,
+    ready: impl Fn(&Process) -> bool,
+) -> Result<bool, ErrorData> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (is_ready, exited) = with_process(id, |process| {
+            (ready(process), process.exit_status().is_some())
+        })?;
+        if is_ready {
+            return Ok(true);
+        }
+        if exited {
+            // Output printed right before the exit may still be in the pipes
+            return with_process(id, |process| {
+                process.join_readers();
+                ready(process)
+            });
+        }
+        if Instant::now() >= deadline {
+            return Ok(false);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Runs `f` with each process, in the order they were started.
+pub(crate) fn for_each_process(f: impl FnMut(&mut Process)) {
+    processes().values_mut().for_each(f);
+}
+
+/// Stops the process and forgets it.
+pub(crate) fn stop(id: u32, grace: Duration) -> Result<Process, ErrorData> {
+    let mut process = processes().remove(&id).ok_or_else(|| unknown_process(id))?;
+    process.stop(grace);
+    Ok(process)
+}
+
+/// Kills all background processes, when the session ends.
+pub(crate) fn stop_all() {
+    let mut processes = processes();
+    for process in processes.values_mut() {
+        tracing::info!(
+            "Stopping background process {}: {}",
+            process.id,
+            process.command
+        );
+        process.stop(Duration::ZERO);
+    }
+    processes.clear();
+}
+
+/// Kills all processes without waiting for them, for use in the panic hook.
+///
+/// The panicking thread may hold the process list, in which case this gives up after a second
+/// instead of deadlocking.
+pub(crate) fn kill_all() {
+    let deadline = Instant::now() + Duration::from_secs(1);
+    let mut processes = loop {
+        match PROCESSES.try_lock() {
+            Ok(processes) => break processes,
+            Err(TryLockError::Poisoned(poisoned)) => break poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(TryLockError::WouldBlock) => return,
+        }
+    };
+    for process in processes.values_mut() {
+        if process.exit_status().is_none() {
+            process.signal("KILL");
+        }
+    }
+}
+
+fn unknown_process(id: u32) -> ErrorData {
+    ErrorData::invalid_params(
+        format!(
+            "No background process {id}. Get the ids of the running processes with #process-list"
+        ),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_offsets() {
+        let mut log = Log::default();
+        log.push(b"hello\n");
+        log.push(b"world\n");
+        assert_eq!(log.read(Some(6), 100), (6, 12, "world\n".to_owned()));
+        assert_eq!(log.read(None, 6), (6, 12, "world\n".to_owned()));
+        assert_eq!(log.read(Some(0), 3), (0, 3, "hel".to_owned()));
+        assert_eq!(log.read(Some(50), 3), (12, 12, String::new()));
+
+        log.push(&vec![b'x'; LOG_CAPACITY]);
+        assert_eq!(log.end(), 12 + LOG_CAPACITY);
+        // The first 12 bytes were dropped
+        assert_eq!(log.read(Some(0), 2), (12, 14, "xx".to_owned()));
+    }
+
+    #[test]
+    fn test_log_offsets_with_invalid_utf8() {
+        let mut log = Log::default();
+        log.push(b"a\xff\xfeb");
+        // Two invalid bytes become two replacement characters of three bytes each
+        assert_eq!(
+            log.read(Some(0), 100),
+            (0, 4, "a\u{fffd}\u{fffd}b".to_owned())
+        );
+
+        log.push("é!".as_bytes());
+        // The read stops before the cut off `é`, which is read in full next time
+        assert_eq!(
+            log.read(Some(0), 5),
+            (0, 4, "a\u{fffd}\u{fffd}b".to_owned())
+        );
+        assert_eq!(log.read(Some(4), 5), (4, 7, "é!".to_owned()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_start_and_stop() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo ready; sleep 30"]);
+        let id = start(cmd, false).unwrap();
+        let ready = wait_until(id, Duration::from_secs(10), |process| {
+            process.stdout.lock().unwrap().contains("ready")
+        })
+        .unwrap();
+        assert!(ready);
+
+        let started = Instant::now();
+        let mut process = stop(id, Duration::from_secs(5)).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!process.exit_status().unwrap().success());
+        assert!(with_process(id, |_| ()).is_err());
+    }
+}
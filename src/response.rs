@@ -2,10 +2,15 @@ use rmcp::model::Annotated;
 
 use crate::command::{AgentRecommendation, Output};
 
+/// Result of a tool call, usually built from the output of the command the tool ran.
+///
+/// Tools that don't run a command start from `Response::default()` and only add content.
+#[derive(Default)]
 pub(crate) struct Response {
-    output: Output,
+    output: Option<Output>,
     additional_content: Vec<Annotated<rmcp::model::RawContent>>,
     recommendations: Vec<AgentRecommendation>,
+    structured_content: Option<serde_json::Value>,
 }
 
 impl Response {
@@ -18,12 +23,20 @@ impl Response {
             .push(AgentRecommendation(recommendation.into()));
     }
 
+    pub(crate) fn set_structured_content(&mut self, value: serde_json::Value) {
+        self.structured_content = Some(value);
+    }
+
     pub(crate) fn into_rmcp_result(
         self,
         ignore_recommendations: bool,
     ) -> rmcp::model::CallToolResult {
-        let mut result: rmcp::model::CallToolResult = self.output.into();
+        let mut result = match self.output {
+            Some(output) => output.into(),
+            None => rmcp::model::CallToolResult::success(Vec::new()),
+        };
         result.content.extend(self.additional_content);
+        result.structured_content = self.structured_content;
         if !ignore_recommendations {
             result
                 .content
@@ -36,9 +49,10 @@ impl Response {
 impl From<Output> for Response {
     fn from(val: Output) -> Self {
         Response {
-            output: val,
+            output: Some(val),
             additional_content: Vec::new(),
             recommendations: Vec::new(),
+            structured_content: None,
         }
     }
 }
@@ -1,21 +1,39 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
 
 use rmcp::{
     ErrorData,
-    model::{ListToolsResult, PaginatedRequestParam, ServerInfo},
+    model::{
+        InitializeRequestParam, InitializeResult, ListToolsResult, PaginatedRequestParam,
+        ServerInfo,
+    },
     service::RequestContext,
 };
 
 use crate::{
     Tool,
+    arguments::{
+        UnknownArgumentsPolicy, find_unknown_arguments, unknown_arguments_error,
+        unknown_arguments_warning,
+    },
+    command::Warning,
+    schema::{SchemaDetail, SchemaDialect},
     tool::DynTool,
     tools::{
+        apply_suggestions::ApplySuggestionsRmcpTool,
         cargo::{
-            CargoAddRmcpTool, CargoBuildRmcpTool, CargoCheckRmcpTool, CargoCleanRmcpTool,
-            CargoClippyRmcpTool, CargoDocRmcpTool, CargoFmtRmcpTool, CargoGenerateLockfileRmcpTool,
-            CargoInfoRmcpTool, CargoListRmcpTool, CargoMetadataRmcpTool, CargoNewRmcpTool,
-            CargoPackageRmcpTool, CargoRemoveRmcpTool, CargoSearchRmcpTool, CargoTestRmcpTool,
-            CargoUpdateRmcpTool, CargoWorkspaceInfoRmcpTool,
+            CargoAddRmcpTool, CargoBenchRmcpTool, CargoBuildRmcpTool, CargoCheckRmcpTool,
+            CargoCleanRmcpTool, CargoClippyRmcpTool, CargoDocRmcpTool, CargoFmtRmcpTool,
+            CargoGenerateLockfileRmcpTool, CargoInfoRmcpTool, CargoListRmcpTool,
+            CargoMetadataRmcpTool, CargoNewRmcpTool, CargoPackageRmcpTool, CargoRemoveRmcpTool,
+            CargoRunRmcpTool, CargoSearchRmcpTool, CargoTestFlakyRmcpTool, CargoTestListRmcpTool,
+            CargoTestRmcpTool, CargoUpdateRmcpTool, CargoWorkspaceInfoRmcpTool,
+        },
+        process::{
+            ProcessListRmcpTool, ProcessOutputRmcpTool, ProcessSignalRmcpTool,
+            ProcessStartRmcpTool, ProcessStopRmcpTool,
         },
         rustc::RustcExplainRmcpTool,
     },
@@ -30,14 +48,32 @@ use crate::tools::cargo_deny::{
 #[cfg(feature = "cargo-hack")]
 use crate::tools::cargo_hack::{CargoHackInstallRmcpTool, CargoHackRmcpTool};
 
+#[cfg(feature = "cargo-insta")]
+use crate::tools::cargo_insta::{
+    CargoInstaAcceptRmcpTool, CargoInstaInstallRmcpTool, CargoInstaPendingRmcpTool,
+    CargoInstaRejectRmcpTool,
+};
+
+#[cfg(feature = "cargo-llvm-cov")]
+use crate::tools::cargo_llvm_cov::{CargoLlvmCovInstallRmcpTool, CargoLlvmCovRmcpTool};
+
 #[cfg(feature = "cargo-machete")]
 use crate::tools::cargo_machete::{CargoMacheteInstallRmcpTool, CargoMacheteRmcpTool};
 
+#[cfg(feature = "cargo-nextest")]
+use crate::tools::cargo_nextest::{
+    CargoNextestInstallRmcpTool, CargoNextestListRmcpTool, CargoNextestRunRmcpTool,
+};
+
 #[cfg(feature = "rustup")]
 use crate::tools::rustup::{RustupShowRmcpTool, RustupToolchainAddRmcpTool, RustupUpdateRmcpTool};
 
 pub struct Server {
     ignore_recommendations: bool,
+    unknown_arguments: UnknownArgumentsPolicy,
+    schema_dialect: Option<SchemaDialect>,
+    client_schema_dialect: OnceLock<SchemaDialect>,
+    schema_detail: SchemaDetail,
     tools: HashMap<&'static str, Box<dyn DynTool + Send + Sync>>,
 }
 
@@ -65,14 +101,32 @@ impl Server {
         tools.insert(CargoRemoveRmcpTool::NAME, Box::new(CargoRemoveRmcpTool));
         tools.insert(CargoSearchRmcpTool::NAME, Box::new(CargoSearchRmcpTool));
         tools.insert(CargoTestRmcpTool::NAME, Box::new(CargoTestRmcpTool));
+        tools.insert(CargoRunRmcpTool::NAME, Box::new(CargoRunRmcpTool));
+        tools.insert(CargoBenchRmcpTool::NAME, Box::new(CargoBenchRmcpTool));
+        tools.insert(CargoTestListRmcpTool::NAME, Box::new(CargoTestListRmcpTool));
+        tools.insert(
+            CargoTestFlakyRmcpTool::NAME,
+            Box::new(CargoTestFlakyRmcpTool),
+        );
         tools.insert(CargoUpdateRmcpTool::NAME, Box::new(CargoUpdateRmcpTool));
         tools.insert(
             CargoWorkspaceInfoRmcpTool::NAME,
             Box::new(CargoWorkspaceInfoRmcpTool),
         );
 
+        // Background process tools
+        tools.insert(ProcessStartRmcpTool::NAME, Box::new(ProcessStartRmcpTool));
+        tools.insert(ProcessListRmcpTool::NAME, Box::new(ProcessListRmcpTool));
+        tools.insert(ProcessOutputRmcpTool::NAME, Box::new(ProcessOutputRmcpTool));
+        tools.insert(ProcessSignalRmcpTool::NAME, Box::new(ProcessSignalRmcpTool));
+        tools.insert(ProcessStopRmcpTool::NAME, Box::new(ProcessStopRmcpTool));
+
         // Rustc tools
         tools.insert(RustcExplainRmcpTool::NAME, Box::new(RustcExplainRmcpTool));
+        tools.insert(
+            ApplySuggestionsRmcpTool::NAME,
+            Box::new(ApplySuggestionsRmcpTool),
+        );
 
         #[cfg(feature = "cargo-deny")]
         {
@@ -99,6 +153,37 @@ impl Server {
             );
         }
 
+        #[cfg(feature = "cargo-insta")]
+        {
+            // Cargo-insta tools
+            tools.insert(
+                CargoInstaPendingRmcpTool::NAME,
+                Box::new(CargoInstaPendingRmcpTool),
+            );
+            tools.insert(
+                CargoInstaAcceptRmcpTool::NAME,
+                Box::new(CargoInstaAcceptRmcpTool),
+            );
+            tools.insert(
+                CargoInstaRejectRmcpTool::NAME,
+                Box::new(CargoInstaRejectRmcpTool),
+            );
+            tools.insert(
+                CargoInstaInstallRmcpTool::NAME,
+                Box::new(CargoInstaInstallRmcpTool),
+            );
+        }
+
+        #[cfg(feature = "cargo-llvm-cov")]
+        {
+            // Cargo-llvm-cov tools
+            tools.insert(CargoLlvmCovRmcpTool::NAME, Box::new(CargoLlvmCovRmcpTool));
+            tools.insert(
+                CargoLlvmCovInstallRmcpTool::NAME,
+                Box::new(CargoLlvmCovInstallRmcpTool),
+            );
+        }
+
         #[cfg(feature = "cargo-machete")]
         {
             // Cargo-machete tools
@@ -109,6 +194,23 @@ impl Server {
             );
         }
 
+        #[cfg(feature = "cargo-nextest")]
+        {
+            // Cargo-nextest tools
+            tools.insert(
+                CargoNextestRunRmcpTool::NAME,
+                Box::new(CargoNextestRunRmcpTool),
+            );
+            tools.insert(
+                CargoNextestListRmcpTool::NAME,
+                Box::new(CargoNextestListRmcpTool),
+            );
+            tools.insert(
+                CargoNextestInstallRmcpTool::NAME,
+                Box::new(CargoNextestInstallRmcpTool),
+            );
+        }
+
         #[cfg(feature = "rustup")]
         {
             // Rustup tools
@@ -131,10 +233,38 @@ impl Server {
 
         Self {
             ignore_recommendations,
+            unknown_arguments: UnknownArgumentsPolicy::default(),
+            schema_dialect: None,
+            client_schema_dialect: OnceLock::new(),
+            schema_detail: SchemaDetail::default(),
             tools,
         }
     }
 
+    /// Set how tool calls with arguments missing from the tool's schema are handled
+    pub fn with_unknown_arguments(mut self, policy: UnknownArgumentsPolicy) -> Self {
+        self.unknown_arguments = policy;
+        self
+    }
+
+    /// Force a schema dialect instead of detecting it from the client at initialization
+    pub fn with_schema_dialect(mut self, dialect: Option<SchemaDialect>) -> Self {
+        self.schema_dialect = dialect;
+        self
+    }
+
+    /// Set how much of the argument documentation is included in tool schemas
+    pub fn with_schema_detail(mut self, detail: SchemaDetail) -> Self {
+        self.schema_detail = detail;
+        self
+    }
+
+    fn schema_dialect(&self) -> SchemaDialect {
+        self.schema_dialect
+            .or_else(|| self.client_schema_dialect.get().copied())
+            .unwrap_or_default()
+    }
+
     /// Generate markdown documentation for all tools
     pub fn generate_markdown_docs(&self) -> String {
         let mut output = String::new();
@@ -156,7 +286,7 @@ impl Server {
             output.push_str(&format!("- **{}**\n", tool.name()));
             output.push_str(&format!("  - {}\n", tool.description()));
 
-            let schema = tool.json_schema();
+            let schema = SchemaDetail::Full.apply(tool.json_schema());
             if let Some(serde_json::Value::Object(properties)) = schema.get("properties")
                 && !properties.is_empty()
             {
@@ -169,9 +299,13 @@ impl Server {
                 for prop_name in prop_names {
                     let prop = &properties[prop_name];
                     let type_str = self.format_property_type(prop);
+                    let description = prop
+                        .get("description")
+                        .and_then(serde_json::Value::as_str)
+                        .unwrap_or_default();
                     output.push_str(&format!(
-                        "      - <code>{}</code> : {}<br />\n",
-                        prop_name, type_str
+                        "      - <code>{}</code> : {}<br />{}\n",
+                        prop_name, type_str, description
                     ));
                 }
             }
@@ -184,6 +318,13 @@ impl Server {
 
     fn format_property_type(&self, prop: &serde_json::Value) -> String {
         if let Some(type_val) = prop.get("type") {
+            let type_val = match type_val {
+                // Optional arguments are documented by their non-null type
+                serde_json::Value::Array(types) => {
+                    types.iter().find(|ty| *ty != "null").unwrap_or(type_val)
+                }
+                _ => type_val,
+            };
             match type_val.as_str() {
                 Some("array") => {
                     if let Some(items) = prop.get("items")
@@ -204,9 +345,7 @@ impl Server {
 
 impl rmcp::ServerHandler for Server {
     fn get_info(&self) -> ServerInfo {
-        use rmcp::model::{
-            Implementation, InitializeResult, ProtocolVersion, ServerCapabilities, ToolsCapability,
-        };
+        use rmcp::model::{Implementation, ProtocolVersion, ServerCapabilities, ToolsCapability};
 
         InitializeResult {
             protocol_version: ProtocolVersion::LATEST,
@@ -224,15 +363,35 @@ impl rmcp::ServerHandler for Server {
             instructions: None,
         }
     }
+    async fn initialize(
+        &self,
+        request: InitializeRequestParam,
+        context: RequestContext<rmcp::RoleServer>,
+    ) -> Result<InitializeResult, ErrorData> {
+        let dialect = SchemaDialect::for_client(&request.client_info.name);
+        tracing::info!(
+            "Client: {} {}, detected schema dialect: {dialect:?}",
+            request.client_info.name,
+            request.client_info.version
+        );
+        let _ = self.client_schema_dialect.set(dialect);
+
+        if context.peer.peer_info().is_none() {
+            context.peer.set_peer_info(request);
+        }
+        Ok(self.get_info())
+    }
+
     async fn list_tools(
         &self,
         _request: Option<PaginatedRequestParam>,
         _context: RequestContext<rmcp::RoleServer>,
     ) -> Result<ListToolsResult, ErrorData> {
         let mut tools: Vec<rmcp::model::Tool> = Vec::new();
+        let dialect = self.schema_dialect();
 
         for tool in self.tools.values() {
-            let schema = Arc::new(tool.json_schema());
+            let schema = Arc::new(dialect.apply(self.schema_detail.apply(tool.json_schema())));
             tools.push(rmcp::model::Tool {
                 name: tool.name().into(),
                 title: Some(tool.title().into()),
@@ -261,7 +420,26 @@ impl rmcp::ServerHandler for Server {
             ErrorData::invalid_request(format!("Tool '{}' not found", request.name), None)
         })?;
 
-        tool.call_rmcp_tool(request)
-            .map(|r| r.into_rmcp_result(self.ignore_recommendations))
+        let schema = tool.json_schema();
+        let unknown = find_unknown_arguments(&schema, request.arguments.as_ref());
+        if !unknown.is_empty() {
+            tracing::warn!(
+                "Unknown arguments for {}: {}",
+                tool.name(),
+                unknown_arguments_warning(&unknown)
+            );
+            if self.unknown_arguments == UnknownArgumentsPolicy::Reject {
+                return Err(ErrorData::invalid_params(
+                    unknown_arguments_error(tool.name(), &unknown, &schema),
+                    None,
+                ));
+            }
+        }
+
+        let mut response = tool.call_rmcp_tool(request)?;
+        if !unknown.is_empty() {
+            response.add_content(Warning(unknown_arguments_warning(&unknown)).into());
+        }
+        Ok(response.into_rmcp_result(self.ignore_recommendations))
     }
 }
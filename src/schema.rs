@@ -0,0 +1,384 @@
+use serde_json::Value;
+
+/// JSON Schema flavour used for tool input schemas in `tools/list`.
+///
+/// Tools always produce a canonical JSON Schema 2020-12 document, and the dialect only changes how
+/// it is presented to the client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaDialect {
+    /// Optional types collapsed from `["string", "null"]` to `"string"`, which Gemini requires
+    #[default]
+    Gemini,
+    /// OpenAI strict mode: every property required and no additional properties
+    #[value(name = "openai")]
+    OpenAi,
+    /// Unmodified JSON Schema 2020-12, including `$schema` and nullable types
+    Full,
+}
+
+impl SchemaDialect {
+    /// Picks the dialect for a client from the `clientInfo.name` it sent in `initialize`.
+    ///
+    /// Unknown clients get [`SchemaDialect::Gemini`], which is accepted by every client we know of.
+    pub(crate) fn for_client(client_name: &str) -> Self {
+        let name = client_name.to_ascii_lowercase();
+        if name.contains("gemini") {
+            Self::Gemini
+        } else if name.contains("openai") || name.contains("codex") || name.contains("chatgpt") {
+            Self::OpenAi
+        } else if name.contains("claude") || name.contains("visual studio code") {
+            Self::Full
+        } else {
+            Self::Gemini
+        }
+    }
+
+    /// Converts a canonical tool input schema into this dialect.
+    pub(crate) fn apply(
+        self,
+        mut schema: serde_json::Map<String, Value>,
+    ) -> serde_json::Map<String, Value> {
+        match self {
+            Self::Full => {}
+            Self::Gemini => {
+                schema.remove("$schema");
+                if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+                    properties.values_mut().for_each(collapse_null_type);
+                }
+            }
+            Self::OpenAi => {
+                schema.remove("$schema");
+                make_strict(&mut schema);
+            }
+        }
+        schema
+    }
+}
+
+/// How much of the argument documentation is included in tool input schemas.
+///
+/// Descriptions come from the doc comments of the request fields and cost tokens on every
+/// `tools/list`, so they are left out by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaDetail {
+    /// No argument descriptions, only names and types
+    #[default]
+    Minimal,
+    /// The first sentence of each argument description
+    Standard,
+    /// Complete argument descriptions
+    Full,
+}
+
+impl SchemaDetail {
+    /// Strips argument descriptions of a canonical tool input schema down to this level.
+    pub(crate) fn apply(
+        self,
+        mut schema: serde_json::Map<String, Value>,
+    ) -> serde_json::Map<String, Value> {
+        self.rewrite_descriptions(&mut schema);
+        schema
+    }
+
+    fn rewrite_descriptions(self, schema: &mut serde_json::Map<String, Value>) {
+        if let Some(Value::String(description)) = schema.get_mut("description") {
+            match self {
+                Self::Minimal => {
+                    schema.remove("description");
+                }
+                Self::Standard => {
+                    *description = first_sentence(&unwrap_lines(description)).to_owned();
+                }
+                Self::Full => *description = unwrap_lines(description),
+            }
+        }
+
+        if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+            for property in properties.values_mut() {
+                if let Value::Object(property) = property {
+                    self.rewrite_descriptions(property);
+                }
+            }
+        }
+        if let Some(Value::Object(items)) = schema.get_mut("items") {
+            self.rewrite_descriptions(items);
+        }
+    }
+}
+
+/// Joins doc comment lines that were wrapped in the source, keeping paragraph breaks.
+fn unwrap_lines(description: &str) -> String {
+    description
+        .split("\n\n")
+        .map(|paragraph| paragraph.replace('\n', " "))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Returns the first sentence of a description, not counting the dots of `e.g.` and `i.e.`.
+fn first_sentence(description: &str) -> &str {
+    let mut search_from = 0;
+    while let Some(offset) = description[search_from..].find(". ") {
+        let end = search_from + offset + 1;
+        let before = &description[..end];
+        if !before.ends_with("e.g.") && !before.ends_with("i.e.") {
+            return before;
+        }
+        search_from = end;
+    }
+    description
+}
+
+/// Replaces `"type": ["x", "null"]` with `"type": "x"`.
+fn collapse_null_type(property: &mut Value) {
+    if let Value::Object(property) = property
+        && let Some(Value::Array(types)) = property.get("type")
+        && types.len() == 2
+        && types.iter().any(|ty| ty == "null")
+        && let Some(ty) = types.iter().find(|ty| *ty != "null").cloned()
+    {
+        property.insert("type".to_owned(), ty);
+    }
+}
+
+/// Applies the OpenAI strict mode rules to an object schema and every nested object schema.
+///
+/// Strict mode requires every property to be listed in `required`. Optional arguments are already
+/// nullable in the canonical schema, so the model can pass `null` for them. Arguments that have a
+/// non-nullable default become mandatory. `default` and `format` are not supported and are removed.
+///
+/// Nested structs are emitted by schemars as `$ref`s into `$defs`, possibly wrapped in `anyOf` when
+/// optional, so those are made strict too.
+fn make_strict(schema: &mut serde_json::Map<String, Value>) {
+    schema.remove("default");
+    schema.remove("format");
+
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        for property in properties.values_mut() {
+            if let Value::Object(property) = property {
+                make_strict(property);
+            }
+        }
+
+        let required = properties.keys().cloned().map(Value::String).collect();
+        schema.insert("required".to_owned(), Value::Array(required));
+        schema.insert("additionalProperties".to_owned(), Value::Bool(false));
+    }
+
+    if let Some(Value::Object(items)) = schema.get_mut("items") {
+        make_strict(items);
+    }
+
+    if let Some(Value::Object(defs)) = schema.get_mut("$defs") {
+        for def in defs.values_mut() {
+            if let Value::Object(def) = def {
+                make_strict(def);
+            }
+        }
+    }
+
+    for key in ["anyOf", "oneOf", "allOf"] {
+        if let Some(Value::Array(variants)) = schema.get_mut(key) {
+            for variant in variants {
+                if let Value::Object(variant) = variant {
+                    make_strict(variant);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(serde::Deserialize, schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct Example {
+        name: String,
+        #[serde(default)]
+        opt: Option<String>,
+        #[serde(default)]
+        jobs: Option<u32>,
+        #[serde(default)]
+        flag: bool,
+        #[serde(default)]
+        list: Option<Vec<String>>,
+    }
+
+    fn canonical() -> serde_json::Map<String, Value> {
+        crate::tool::json_schema_impl::<Example>()
+    }
+
+    fn property<'a>(schema: &'a serde_json::Map<String, Value>, name: &str) -> &'a Value {
+        &schema["properties"][name]
+    }
+
+    #[test]
+    fn test_gemini_collapses_null_types() {
+        let schema = SchemaDialect::Gemini.apply(canonical());
+
+        assert!(!schema.contains_key("$schema"));
+        assert_eq!(property(&schema, "name")["type"], "string");
+        assert_eq!(property(&schema, "opt")["type"], "string");
+        assert_eq!(property(&schema, "jobs")["type"], "integer");
+        assert_eq!(property(&schema, "flag")["type"], "boolean");
+        assert_eq!(property(&schema, "list")["type"], "array");
+        assert_eq!(property(&schema, "list")["items"]["type"], "string");
+        assert_eq!(schema["required"], json!(["name"]));
+    }
+
+    #[test]
+    fn test_openai_is_strict() {
+        let schema = SchemaDialect::OpenAi.apply(canonical());
+
+        assert!(!schema.contains_key("$schema"));
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(
+            schema["required"],
+            json!(["flag", "jobs", "list", "name", "opt"])
+        );
+        assert_eq!(property(&schema, "name")["type"], "string");
+        assert_eq!(property(&schema, "opt")["type"], json!(["string", "null"]));
+        assert_eq!(
+            property(&schema, "jobs")["type"],
+            json!(["integer", "null"])
+        );
+        assert_eq!(property(&schema, "list")["type"], json!(["array", "null"]));
+        assert_eq!(property(&schema, "flag")["type"], "boolean");
+        assert!(property(&schema, "jobs").get("format").is_none());
+        assert!(property(&schema, "flag").get("default").is_none());
+    }
+
+    #[test]
+    fn test_openai_nested_structs_are_strict() {
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Inner {
+            #[serde(default)]
+            jobs: Option<u32>,
+        }
+
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Outer {
+            inner: Inner,
+            #[serde(default)]
+            maybe: Option<Inner>,
+            #[serde(default)]
+            many: Vec<Inner>,
+        }
+
+        let schema = SchemaDialect::OpenAi.apply(crate::tool::json_schema_impl::<Outer>());
+
+        assert_eq!(property(&schema, "inner")["$ref"], "#/$defs/Inner");
+        let inner = &schema["$defs"]["Inner"];
+        assert_eq!(inner["additionalProperties"], false);
+        assert_eq!(inner["required"], json!(["jobs"]));
+        assert!(inner["properties"]["jobs"].get("format").is_none());
+        assert!(property(&schema, "many").get("default").is_none());
+        assert!(
+            property(&schema, "maybe")["anyOf"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|variant| variant.get("default").is_none())
+        );
+    }
+
+    #[test]
+    fn test_openai_null_arguments_deserialize() {
+        let args = json!({ "name": "x", "opt": null, "jobs": null, "flag": false, "list": null });
+        assert!(serde_json::from_value::<Example>(args).is_ok());
+    }
+
+    #[test]
+    fn test_full_keeps_canonical_schema() {
+        let schema = SchemaDialect::Full.apply(canonical());
+
+        assert_eq!(schema, canonical());
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        assert_eq!(property(&schema, "opt")["type"], json!(["string", "null"]));
+        assert!(!schema.contains_key("additionalProperties"));
+    }
+
+    #[test]
+    fn test_first_sentence() {
+        assert_eq!(first_sentence("One sentence."), "One sentence.");
+        assert_eq!(first_sentence("First. Second."), "First.");
+        assert_eq!(
+            first_sentence("Toolchain, e.g. `nightly` or `1.90`. Must be installed."),
+            "Toolchain, e.g. `nightly` or `1.90`."
+        );
+    }
+
+    #[test]
+    fn test_schema_detail() {
+        /// Example request.
+        #[derive(serde::Deserialize, schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Documented {
+            /// Packages to build, e.g. `serde`.
+            /// Defaults to the current package.
+            #[serde(default)]
+            package: Option<Vec<String>>,
+            #[serde(default)]
+            undocumented: Option<bool>,
+        }
+        let canonical = crate::tool::json_schema_impl::<Documented>();
+
+        let full = SchemaDetail::Full.apply(canonical.clone());
+        assert_eq!(
+            property(&full, "package")["description"],
+            "Packages to build, e.g. `serde`. Defaults to the current package."
+        );
+
+        let standard = SchemaDetail::Standard.apply(canonical.clone());
+        assert_eq!(standard["description"], "Example request.");
+        assert_eq!(
+            property(&standard, "package")["description"],
+            "Packages to build, e.g. `serde`."
+        );
+        assert!(
+            property(&standard, "undocumented")
+                .get("description")
+                .is_none()
+        );
+
+        let minimal = SchemaDetail::Minimal.apply(canonical);
+        assert!(!minimal.contains_key("description"));
+        assert!(property(&minimal, "package").get("description").is_none());
+        assert_eq!(
+            property(&minimal, "package")["type"],
+            json!(["array", "null"])
+        );
+    }
+
+    #[test]
+    fn test_for_client() {
+        assert_eq!(
+            SchemaDialect::for_client("gemini-cli-mcp-client"),
+            SchemaDialect::Gemini
+        );
+        assert_eq!(
+            SchemaDialect::for_client("codex-mcp-client"),
+            SchemaDialect::OpenAi
+        );
+        assert_eq!(
+            SchemaDialect::for_client("claude-code"),
+            SchemaDialect::Full
+        );
+        assert_eq!(
+            SchemaDialect::for_client("Visual Studio Code"),
+            SchemaDialect::Full
+        );
+        assert_eq!(
+            SchemaDialect::for_client("some-new-client"),
+            SchemaDialect::Gemini
+        );
+    }
+}
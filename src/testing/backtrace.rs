@@ -0,0 +1,319 @@
+//! Panic backtraces trimmed to the frames in workspace code.
+
+use std::{collections::HashMap, fmt::Write, path::Path};
+
+use crate::diagnostics::{Span, source_snippet};
+
+/// Most workspace frames kept per backtrace.
+const MAX_FRAMES: usize = 10;
+
+/// Total size of the snippets attached to the backtraces of one report.
+pub(super) const SNIPPETS_BUDGET: usize = 6000;
+
+/// Context lines around the line of a frame.
+const SNIPPET_CONTEXT_LINES: usize = 1;
+
+/// A backtrace as printed with `RUST_BACKTRACE=1`, innermost frame first.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct Backtrace {
+    pub(crate) frames: Vec<Frame>,
+    /// Frames dropped because they are outside the workspace, e.g. in std or dependencies
+    #[serde(skip_serializing_if = "is_zero")]
+    pub(crate) omitted: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct Frame {
+    /// Position in the printed backtrace
+    pub(crate) index: usize,
+    pub(crate) function: String,
+    /// `file:line:col`, relative to the workspace root once trimmed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) snippet: Option<String>,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// Parses the frames following a "stack backtrace:" line, or returns `None` if there is none.
+pub(super) fn parse_backtrace(lines: &[&str]) -> Option<Backtrace> {
+    let start = lines
+        .iter()
+        .position(|line| line.starts_with("stack backtrace:"))?;
+
+    let mut frames: Vec<Frame> = Vec::new();
+    for line in &lines[start + 1..] {
+        let line = line.trim();
+        if let Some(location) = line.strip_prefix("at ") {
+            if let Some(frame) = frames.last_mut() {
+                frame.location = Some(location.to_owned());
+            }
+        } else if let Some((index, function)) = line.split_once(": ")
+            && let Ok(index) = index.parse()
+        {
+            frames.push(Frame {
+                index,
+                function: function.to_owned(),
+                location: None,
+                snippet: None,
+            });
+        } else {
+            break;
+        }
+    }
+    Some(Backtrace { frames, omitted: 0 })
+}
+
+impl Backtrace {
+    /// Keeps the frames with a location in the workspace and makes their paths relative to the
+    /// workspace root.
+    ///
+    /// Test binaries run in their package directory, so relative paths in a backtrace, like
+    /// `./src/lib.rs`, are relative to the package. `panic_location`, which is relative to the
+    /// workspace root, e.g. `app/src/lib.rs:20:9`, tells which package that is.
+    pub(super) fn trim(&mut self, root: &Path, panic_location: Option<&str>) {
+        let package_dir = package_dir(&self.frames, panic_location);
+        let total = self.frames.len();
+        let mut kept: Vec<Frame> = Vec::new();
+        for mut frame in std::mem::take(&mut self.frames) {
+            let Some(location) = frame
+                .location
+                .as_deref()
+                .and_then(|location| workspace_location(location, root, &package_dir))
+            else {
+                continue;
+            };
+            // The closure that wraps a test or spawned task points to the same code again
+            let function = without_closures(&frame.function);
+            let repeated = kept.last().is_some_and(|previous| {
+                without_closures(&previous.function) == function
+                    && file_of(previous.location.as_deref()) == file_of(Some(&location))
+            });
+            if repeated || kept.len() == MAX_FRAMES {
+                continue;
+            }
+            frame.location = Some(location);
+            kept.push(frame);
+        }
+        self.omitted = total - kept.len();
+        self.frames = kept;
+    }
+
+    /// Attaches the source around each frame, as long as it fits in `budget`.
+    pub(super) fn attach_snippets(
+        &mut self,
+        root: &Path,
+        files: &mut HashMap<String, Option<String>>,
+        budget: &mut usize,
+    ) {
+        for frame in &mut self.frames {
+            let Some(span) = frame.location.as_deref().and_then(parse_location) else {
+                continue;
+            };
+            let content = files
+                .entry(span.file.clone())
+                .or_insert_with(|| std::fs::read_to_string(root.join(&span.file)).ok());
+            let Some(snippet) = content
+                .as_deref()
+                .and_then(|content| source_snippet(content, &span, SNIPPET_CONTEXT_LINES))
+            else {
+                continue;
+            };
+            if snippet.len() > *budget {
+                return;
+            }
+            *budget -= snippet.len();
+            frame.snippet = Some(snippet);
+        }
+    }
+
+    /// Renders the kept frames, indented below a failure.
+    pub(super) fn render(&self, output: &mut String) {
+        output.push_str("  backtrace");
+        if self.omitted > 0 {
+            let _ = write!(
+                output,
+                " ({} frame(s) outside the workspace omitted)",
+                self.omitted
+            );
+        }
+        output.push_str(":\n");
+        for frame in &self.frames {
+            let _ = write!(output, "    #{} {}", frame.index, frame.function);
+            if let Some(location) = &frame.location {
+                let _ = write!(output, " at {location}");
+            }
+            output.push('\n');
+            for line in frame.snippet.iter().flat_map(|snippet| snippet.lines()) {
+                let _ = writeln!(output, "      {line}");
+            }
+        }
+    }
+}
+
+/// The function a closure is defined in, e.g. `app::tests::fails` for
+/// `app::tests::fails::{{closure}}` or, on nightly, `app::tests::fails::{closure#0}`.
+fn without_closures(mut function: &str) -> &str {
+    while let Some((outer, last)) = function.rsplit_once("::")
+        && (last == "{{closure}}" || last.starts_with("{closure#"))
+    {
+        function = outer;
+    }
+    function
+}
+
+/// The package directory relative to the workspace root, from the first relative frame path
+/// that the panic location ends with.
+fn package_dir(frames: &[Frame], panic_location: Option<&str>) -> String {
+    let Some(panic_file) = file_of(panic_location) else {
+        return String::new();
+    };
+    frames
+        .iter()
+        .filter_map(|frame| file_of(frame.location.as_deref()))
+        .filter(|file| !Path::new(file).is_absolute())
+        .map(|file| file.trim_start_matches("./"))
+        .find_map(|file| panic_file.strip_suffix(file))
+        .filter(|prefix| prefix.is_empty() || prefix.ends_with('/'))
+        .unwrap_or_default()
+        .to_owned()
+}
+
+/// Rewrites a frame location relative to the workspace root, or returns `None` if the file is
+/// outside the workspace.
+fn workspace_location(location: &str, root: &Path, package_dir: &str) -> Option<String> {
+    let file = file_of(Some(location))?;
+    let position = &location[file.len()..];
+    let path = Path::new(file);
+    let relative = if path.is_absolute() {
+        let relative = path.strip_prefix(root).ok()?;
+        // Dependencies vendored or checked out in the target directory are not workspace code
+        if relative.starts_with("target") {
+            return None;
+        }
+        relative.to_str()?.to_owned()
+    } else {
+        format!("{package_dir}{}", file.trim_start_matches("./"))
+    };
+    Some(format!("{relative}{position}"))
+}
+
+/// The file of a `file:line:col` location.
+fn file_of(location: Option<&str>) -> Option<&str> {
+    let mut parts = location?.rsplitn(3, ':');
+    let _column = parts.next()?;
+    let _line = parts.next()?;
+    parts.next()
+}
+
+fn parse_location(location: &str) -> Option<Span> {
+    let mut parts = location.rsplitn(3, ':');
+    let column: usize = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?.to_owned();
+    Some(Span {
+        file,
+        line,
+        column,
+        end_line: line,
+        end_column: column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "thread 'tests::a_fails' (6151) panicked at a/src/lib.rs:9:20:
+assertion `left == right` failed
+stack backtrace:
+   0: __rustc::rust_begin_unwind
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/std/src/panicking.rs:689:5
+   1: core::panicking::panic_fmt
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/panicking.rs:80:14
+   2: serde_json::de::from_str
+             at /home/me/.cargo/registry/src/index.crates.io-1949cf8c6b5b557f/serde_json-1.0.140/src/de.rs:2699:5
+   3: a::parse::parse
+             at ./src/parse.rs:12:5
+   4: a::tests::a_fails
+             at ./src/lib.rs:9:20
+   5: a::tests::a_fails::{closure#0}
+             at ./src/lib.rs:9:17
+   6: b::helper
+             at /ws/b/src/lib.rs:3:1
+   7: core::ops::function::FnOnce::call_once
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/core/src/ops/function.rs:250:5
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.";
+
+    #[test]
+    fn test_parse_and_trim_backtrace() {
+        let lines: Vec<&str> = OUTPUT.lines().collect();
+        let mut backtrace = parse_backtrace(&lines).unwrap();
+        assert_eq!(backtrace.frames.len(), 8);
+        assert_eq!(backtrace.frames[3].function, "a::parse::parse");
+        assert_eq!(
+            backtrace.frames[3].location.as_deref(),
+            Some("./src/parse.rs:12:5")
+        );
+
+        backtrace.trim(Path::new("/ws"), Some("a/src/lib.rs:9:20"));
+        let frames: Vec<(usize, &str, &str)> = backtrace
+            .frames
+            .iter()
+            .map(|frame| {
+                (
+                    frame.index,
+                    frame.function.as_str(),
+                    frame.location.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            frames,
+            [
+                (3, "a::parse::parse", "a/src/parse.rs:12:5"),
+                (4, "a::tests::a_fails", "a/src/lib.rs:9:20"),
+                (6, "b::helper", "b/src/lib.rs:3:1"),
+            ]
+        );
+        assert_eq!(backtrace.omitted, 5);
+
+        assert_eq!(without_closures("a::f::{{closure}}::{closure#1}"), "a::f");
+        assert!(parse_backtrace(&["thread 'x' panicked at src/lib.rs:1:1:", "boom"]).is_none());
+    }
+
+    #[test]
+    fn test_attach_snippets() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut backtrace = Backtrace {
+            frames: vec![Frame {
+                index: 4,
+                function: "rust_mcp_server::main".into(),
+                location: Some("src/testing/backtrace.rs:3:5".into()),
+                snippet: None,
+            }],
+            omitted: 2,
+        };
+        let mut budget = SNIPPETS_BUDGET;
+        backtrace.attach_snippets(root, &mut HashMap::new(), &mut budget);
+        assert_eq!(
+            backtrace.frames[0].snippet.as_deref(),
+            Some(
+                "2 |\n\
+                 3 | use std::{collections::HashMap, fmt::Write, path::Path};\n\
+                 \x20 |     ^\n\
+                 4 |"
+            )
+        );
+
+        let mut output = String::new();
+        backtrace.render(&mut output);
+        assert!(output.starts_with(
+            "  backtrace (2 frame(s) outside the workspace omitted):\n    \
+             #4 rust_mcp_server::main at src/testing/backtrace.rs:3:5\n      2 |"
+        ));
+    }
+}
@@ -0,0 +1,350 @@
+//! Test results from JUnit XML reports, as written by cargo-nextest.
+
+use super::{TestBinary, TestFailure, parse::failure_from_output};
+
+/// An XML element with the text of its direct content.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn count(&self, name: &str) -> usize {
+        self.attribute(name)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+}
+
+/// Parses a JUnit report into one entry per test suite, which nextest writes per test binary,
+/// e.g. `my-crate` for the unit tests of a library or `my-crate::api` for `tests/api.rs`.
+pub(crate) fn parse_junit(xml: &str) -> Result<Vec<TestBinary>, String> {
+    let root = parse_xml(xml)?;
+    let suites: Vec<&Element> = if root.name == "testsuite" {
+        vec![&root]
+    } else {
+        root.children("testsuite").collect()
+    };
+    Ok(suites.into_iter().map(test_binary).collect())
+}
+
+fn test_binary(suite: &Element) -> TestBinary {
+    let mut binary = TestBinary {
+        target: suite.attribute("name").unwrap_or("unknown").to_owned(),
+        duration_secs: suite.attribute("time").and_then(|time| time.parse().ok()),
+        ..Default::default()
+    };
+
+    for case in suite.children("testcase") {
+        let name = case.attribute("name").unwrap_or_default().to_owned();
+        let problem = case.child("failure").or_else(|| case.child("error"));
+        if let Some(problem) = problem {
+            binary.failed += 1;
+            binary.failures.push(test_failure(name, case, problem));
+        } else if case.child("skipped").is_some() {
+            binary.ignored += 1;
+        } else {
+            if case.child("flakyFailure").is_some() {
+                binary.flaky.push(name);
+            }
+            binary.passed += 1;
+        }
+    }
+
+    // Reports without test cases, e.g. of a binary that failed to list its tests
+    let tests = suite.count("tests");
+    if binary.passed + binary.failed + binary.ignored < tests {
+        binary.failed = binary
+            .failed
+            .max(suite.count("failures") + suite.count("errors"));
+        binary.ignored = binary
+            .ignored
+            .max(suite.count("skipped") + suite.count("disabled"));
+        binary.passed = tests.saturating_sub(binary.failed + binary.ignored);
+    }
+    if binary.duration_secs.is_none() {
+        let times = suite
+            .children("testcase")
+            .filter_map(|case| case.attribute("time")?.parse::<f64>().ok());
+        binary.duration_secs = Some(times.sum());
+    }
+    binary
+}
+
+/// Nextest stores the captured output in `system-out` and `system-err`, where the panic usually
+/// is, and a description of the failure, often the panic, as the text of `failure`.
+fn test_failure(name: String, case: &Element, problem: &Element) -> TestFailure {
+    let output_of = |name: &str| case.child(name).map_or("", |output| output.text.as_str());
+    let output = format!("{}\n{}", output_of("system-out"), output_of("system-err"));
+    let duration = case.attribute("time").and_then(|time| time.parse().ok());
+
+    let mut failure = failure_from_output(name, &output, duration);
+    if failure.message.is_none() {
+        let description = failure_from_output(String::new(), &problem.text, None);
+        failure.location = description.location;
+        failure.message = description
+            .message
+            .or(description.stdout)
+            .or_else(|| problem.attribute("message").map(str::to_owned));
+    }
+    failure
+}
+
+/// Parses the subset of XML that JUnit reports use: elements, attributes, text, CDATA, comments
+/// and the XML declaration.
+fn parse_xml(xml: &str) -> Result<Element, String> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    let mut rest = xml;
+
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            append_text(&mut stack, rest);
+            break;
+        };
+        append_text(&mut stack, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or("unterminated CDATA section")?;
+            if let Some(element) = stack.last_mut() {
+                element.text.push_str(&cdata[..end]);
+            }
+            rest = &cdata[end + 3..];
+        } else if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").ok_or("unterminated comment")?;
+            rest = &comment[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest.find('>').ok_or("unterminated declaration")?;
+            rest = &rest[end + 1..];
+        } else if let Some(closing) = rest.strip_prefix("</") {
+            let end = closing.find('>').ok_or("unterminated closing tag")?;
+            let name = closing[..end].trim();
+            let element = stack.pop().ok_or_else(|| format!("unexpected </{name}>"))?;
+            if element.name != name {
+                return Err(format!("expected </{}>, found </{name}>", element.name));
+            }
+            close_element(&mut stack, &mut root, element);
+            rest = &closing[end + 1..];
+        } else {
+            let end = tag_end(rest).ok_or("unterminated tag")?;
+            let tag = &rest[1..end];
+            let (tag, self_closing) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let element = parse_tag(tag)?;
+            if self_closing {
+                close_element(&mut stack, &mut root, element);
+            } else {
+                stack.push(element);
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    if let Some(element) = stack.last() {
+        return Err(format!("unclosed <{}>", element.name));
+    }
+    root.ok_or_else(|| "no root element".to_owned())
+}
+
+fn append_text(stack: &mut [Element], text: &str) {
+    if let Some(element) = stack.last_mut() {
+        element.text.push_str(&unescape(text));
+    }
+}
+
+fn close_element(stack: &mut [Element], root: &mut Option<Element>, element: Element) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(element),
+        None => *root = Some(element),
+    }
+}
+
+/// Index of the `>` that ends the tag at the start of `xml`, skipping quoted attribute values.
+fn tag_end(xml: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in xml.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn parse_tag(tag: &str) -> Result<Element, String> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let mut element = Element {
+        name: tag[..name_end].to_owned(),
+        ..Default::default()
+    };
+
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let (key, after_key) = rest
+            .split_once('=')
+            .ok_or_else(|| format!("attribute without value in <{}>", element.name))?;
+        let after_key = after_key.trim_start();
+        let quote = after_key
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("unquoted attribute value in <{}>", element.name))?;
+        let value_end = after_key[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute value in <{}>", element.name))?;
+        element.attributes.push((
+            key.trim().to_owned(),
+            unescape(&after_key[1..value_end + 1]),
+        ));
+        rest = after_key[value_end + 2..].trim_start();
+    }
+    Ok(element)
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_owned();
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .map(|end| (&rest[1..end], end))
+            .and_then(|(name, end)| Some((entity_char(name)?, end)));
+        match entity {
+            Some((c, end)) => {
+                unescaped.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn entity_char(name: &str) -> Option<char> {
+    match name {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => {
+            let code = match name.strip_prefix("#x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="nextest-run" tests="5" failures="1" errors="0" uuid="45c50d2d" timestamp="2026-01-09T07:50:12.664+00:00" time="0.023">
+    <testsuite name="app" tests="3" disabled="0" errors="0" failures="1">
+        <testcase name="tests::passes" classname="app" timestamp="2026-01-09T07:50:12.665+00:00" time="0.004">
+        </testcase>
+        <testcase name="tests::fails" classname="app" timestamp="2026-01-09T07:50:12.666+00:00" time="0.008">
+            <failure type="test failure">thread &apos;tests::fails&apos; panicked at src/lib.rs:20:9:
+assertion `left == right` failed
+  left: 3
+ right: 4</failure>
+            <rerunFailure timestamp="2026-01-09T07:50:12.670+00:00" time="0.007" type="test failure"/>
+            <system-out>captured &lt;output&gt;
+</system-out>
+            <system-err>
+thread &apos;tests::fails&apos; (4242) panicked at src/lib.rs:20:9:
+assertion `left == right` failed
+  left: 3
+ right: 4
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+</system-err>
+        </testcase>
+        <testcase name="tests::flaky" classname="app" timestamp="2026-01-09T07:50:12.667+00:00" time="0.002">
+            <flakyFailure timestamp="2026-01-09T07:50:12.667+00:00" time="0.001" type="test failure"><![CDATA[flaky <one>]]></flakyFailure>
+        </testcase>
+    </testsuite>
+    <!-- integration tests -->
+    <testsuite name="app::api" tests="2" disabled="0" errors="1" failures="0">
+        <testcase name="slow" classname="app::api" time="60.001">
+            <error message="test timed out" type="test timeout"/>
+        </testcase>
+        <testcase name="quick" classname="app::api" time="0.001"/>
+    </testsuite>
+</testsuites>
+"#;
+
+    #[test]
+    fn test_parse_junit() {
+        let binaries = parse_junit(REPORT).unwrap();
+        assert_eq!(binaries.len(), 2);
+
+        let unit = &binaries[0];
+        assert_eq!(unit.target, "app");
+        assert_eq!((unit.passed, unit.failed, unit.ignored), (2, 1, 0));
+        assert_eq!(unit.flaky, ["tests::flaky"]);
+        assert_eq!(
+            unit.failures,
+            [TestFailure {
+                name: "tests::fails".into(),
+                message: Some("assertion `left == right` failed\n  left: 3\n right: 4".into()),
+                location: Some("src/lib.rs:20:9".into()),
+                stdout: Some("captured <output>".into()),
+                duration_secs: Some(0.008),
+                backtrace: None,
+            }]
+        );
+
+        let api = &binaries[1];
+        assert_eq!(api.target, "app::api");
+        assert_eq!((api.passed, api.failed), (1, 1));
+        assert_eq!(api.failures[0].message.as_deref(), Some("test timed out"));
+        assert_eq!(api.failures[0].location, None);
+    }
+
+    #[test]
+    fn test_parse_xml_errors() {
+        assert!(parse_xml("<a><b></a>").is_err());
+        assert!(parse_xml("<a attr=x/>").is_err());
+        assert!(parse_xml("<a>").is_err());
+        assert_eq!(
+            unescape("a &amp;&#x41;&#66; &unknown; &"),
+            "a &AB &unknown; &"
+        );
+    }
+}
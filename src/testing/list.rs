@@ -0,0 +1,236 @@
+//! Test lists of `cargo test -- --list` and `cargo nextest list`.
+
+use std::fmt::Write;
+
+use super::parse::{RunningTarget, is_running_line, running_targets};
+use crate::{
+    command::{Output, Stderr, Stdout},
+    diagnostics::filter_cargo_status,
+};
+
+/// Most test names listed in the text summary, the structured content has all of them.
+const MAX_LISTED_TESTS: usize = 300;
+
+/// Tests of one test binary.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct ListedBinary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) package: Option<String>,
+    /// What runs the tests, e.g. `unittests src/lib.rs`, `tests/api.rs` or `Doc-tests my_crate`
+    /// for cargo, or the binary id such as `my-crate::api` for nextest
+    pub(crate) target: String,
+    /// `lib`, `bin`, `test`, `bench`, `example`, `proc-macro` or `doctest`
+    pub(crate) kind: String,
+    pub(crate) tests: Vec<ListedTest>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct ListedTest {
+    pub(crate) name: String,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) ignored: bool,
+}
+
+/// Takes the test names of each binary out of the output of `cargo test -- --list`, dropping
+/// cargo's "Running ..." and progress lines from stderr like [`super::TestReport::from_output`].
+pub(crate) fn take_test_list(output: &mut Output) -> Vec<(RunningTarget, Vec<String>)> {
+    let stderr = output.stderr.take().map(|s| s.0).unwrap_or_default();
+    let targets = running_targets(&stderr);
+    let stderr: Vec<&str> = stderr
+        .lines()
+        .filter(|line| !is_running_line(line))
+        .collect();
+    output.stderr = filter_cargo_status(Stderr(stderr.join("\n")));
+
+    let stdout = output.stdout.take().map(|s| s.0).unwrap_or_default();
+    let binaries = parse_libtest_list(&stdout, &targets);
+    if binaries.is_empty() && !stdout.is_empty() {
+        output.stdout = Some(Stdout(stdout));
+    }
+    binaries
+}
+
+/// Parses the stdout of `cargo test -- --list`, where each binary lists "name: test" lines and
+/// ends with a "N tests, M benchmarks" line. `targets` names the binaries in the order they ran.
+fn parse_libtest_list(
+    stdout: &str,
+    targets: &[RunningTarget],
+) -> Vec<(RunningTarget, Vec<String>)> {
+    let mut binaries = Vec::new();
+    let mut tests = Vec::new();
+    let mut targets = targets.iter();
+    for line in stdout.lines() {
+        if let Some(name) = line
+            .strip_suffix(": test")
+            .or_else(|| line.strip_suffix(": benchmark"))
+        {
+            tests.push(name.to_owned());
+        } else if is_list_summary(line) {
+            let target = targets.next().cloned().unwrap_or_else(|| RunningTarget {
+                target: "unknown".to_owned(),
+                executable: None,
+                rerun_args: None,
+            });
+            binaries.push((target, std::mem::take(&mut tests)));
+        }
+    }
+    binaries
+}
+
+/// "0 tests, 0 benchmarks" or "1 test, 0 benchmarks".
+fn is_list_summary(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    let mut count = |names: [&str; 2]| {
+        words.next().is_some_and(|n| n.parse::<usize>().is_ok())
+            && words
+                .next()
+                .is_some_and(|word| names.contains(&word.trim_end_matches(',')))
+    };
+    count(["test", "tests"]) && count(["benchmark", "benchmarks"])
+}
+
+/// Renders the tests of each binary grouped by module, with ignored tests marked. `narrow_hint`
+/// names the arguments that shorten a list that is too long.
+pub(crate) fn render_test_list(binaries: &[ListedBinary], narrow_hint: &str) -> String {
+    let total: usize = binaries.iter().map(|binary| binary.tests.len()).sum();
+    if total == 0 {
+        return "No tests found".to_owned();
+    }
+    let listed_binaries = binaries.iter().filter(|binary| !binary.tests.is_empty());
+    let mut output = match listed_binaries.clone().count() {
+        1 => format!("{total} test(s) in 1 test binary\n"),
+        count => format!("{total} test(s) in {count} test binaries\n"),
+    };
+
+    let mut listed = 0;
+    for binary in listed_binaries {
+        output.push('\n');
+        // Nextest's binary ids already start with the package
+        if let Some(package) = &binary.package
+            && !binary.target.starts_with(package.as_str())
+        {
+            let _ = write!(output, "{package}: ");
+        }
+        let _ = writeln!(
+            output,
+            "{} ({}), {} test(s)",
+            binary.target,
+            binary.kind,
+            binary.tests.len()
+        );
+
+        let mut current_module = "";
+        for test in &binary.tests {
+            if listed == MAX_LISTED_TESTS {
+                let _ = writeln!(
+                    output,
+                    "\n... {} more omitted, narrow the list with {narrow_hint}",
+                    total - listed
+                );
+                return output;
+            }
+            listed += 1;
+
+            let ignored = if test.ignored { " (ignored)" } else { "" };
+            // Doc-test names are paths with the item and line, e.g. "src/lib.rs - add (line 1)"
+            let split = (binary.kind != "doctest")
+                .then(|| test.name.rsplit_once("::"))
+                .flatten();
+            match split {
+                Some((module, name)) => {
+                    if module != current_module {
+                        let _ = writeln!(output, "  {module}::");
+                        current_module = module;
+                    }
+                    let _ = writeln!(output, "    {name}{ignored}");
+                }
+                None => {
+                    current_module = "";
+                    let _ = writeln!(output, "  {}{ignored}", test.name);
+                }
+            }
+        }
+    }
+    output.truncate(output.trim_end().len());
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_libtest_list() {
+        let stdout = "tests::fails: test
+tests::passes: test
+
+2 tests, 0 benchmarks
+
+0 tests, 0 benchmarks
+src/lib.rs - add (line 1): test
+
+1 test, 0 benchmarks
+all doctests ran in 0.24s; merged doctests compilation took 0.24s";
+        let target = |name: &str| RunningTarget {
+            target: name.to_owned(),
+            executable: None,
+            rerun_args: None,
+        };
+        let targets = [
+            target("unittests src/lib.rs"),
+            target("tests/it.rs"),
+            target("Doc-tests tst"),
+        ];
+
+        let binaries = parse_libtest_list(stdout, &targets);
+        assert_eq!(binaries.len(), 3);
+        assert_eq!(binaries[0].0.target, "unittests src/lib.rs");
+        assert_eq!(binaries[0].1, ["tests::fails", "tests::passes"]);
+        assert!(binaries[1].1.is_empty());
+        assert_eq!(binaries[2].1, ["src/lib.rs - add (line 1)"]);
+    }
+
+    #[test]
+    fn test_render_test_list() {
+        let test = |name: &str, ignored: bool| ListedTest {
+            name: name.to_owned(),
+            ignored,
+        };
+        let binaries = [
+            ListedBinary {
+                package: Some("app".into()),
+                target: "unittests src/lib.rs".into(),
+                kind: "lib".into(),
+                tests: vec![
+                    test("top_level", false),
+                    test("parse::tests::empty", false),
+                    test("parse::tests::slow", true),
+                    test("render::tests::basic", false),
+                ],
+            },
+            ListedBinary {
+                package: Some("app".into()),
+                target: "Doc-tests app".into(),
+                kind: "doctest".into(),
+                tests: vec![test("src/lib.rs - parse::Parser (line 3)", false)],
+            },
+        ];
+
+        assert_eq!(
+            render_test_list(&binaries, "`package`"),
+            "5 test(s) in 2 test binaries\n\
+             \n\
+             app: unittests src/lib.rs (lib), 4 test(s)\n\
+             \x20 top_level\n\
+             \x20 parse::tests::\n\
+             \x20   empty\n\
+             \x20   slow (ignored)\n\
+             \x20 render::tests::\n\
+             \x20   basic\n\
+             \n\
+             app: Doc-tests app (doctest), 1 test(s)\n\
+             \x20 src/lib.rs - parse::Parser (line 3)"
+        );
+        assert_eq!(render_test_list(&[], "`package`"), "No tests found");
+    }
+}
@@ -0,0 +1,224 @@
+//! Test results parsed from libtest output, in the JSON format on nightly and the text format
+//! otherwise.
+
+mod backtrace;
+#[cfg(feature = "cargo-nextest")]
+mod junit;
+mod list;
+mod parse;
+mod render;
+mod store;
+mod timeout;
+
+use rmcp::model::{AnnotateAble, Annotations};
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    Response,
+    command::{Output, Stderr, text_with_description},
+    diagnostics::filter_cargo_status,
+    tools::workspace_root,
+};
+
+#[cfg(feature = "cargo-nextest")]
+pub(crate) use junit::parse_junit;
+pub(crate) use list::{ListedBinary, ListedTest, render_test_list, take_test_list};
+pub(crate) use parse::{RunningTarget, parse_libtest};
+pub(crate) use store::{FailedTests, failed_tests, remember_failures};
+pub(crate) use timeout::{HangDetector, TimedOutTest};
+
+/// A failed test with its captured output split into the parts the agent needs.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct TestFailure {
+    pub(crate) name: String,
+    /// Panic message, e.g. "assertion `left == right` failed" with the values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+    /// Location of the panic, e.g. `src/lib.rs:20:9`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) location: Option<String>,
+    /// Output the test printed before it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) stdout: Option<String>,
+    /// Only reported by the JSON format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_secs: Option<f64>,
+    /// Frames in workspace code, printed when the test ran with `RUST_BACKTRACE` set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) backtrace: Option<backtrace::Backtrace>,
+}
+
+/// Results of one test binary, e.g. the unit tests of a library or a doc-test run.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub(crate) struct TestBinary {
+    /// What cargo runs, e.g. `unittests src/lib.rs`, `tests/api.rs` or `Doc-tests my_crate`
+    pub(crate) target: String,
+    /// Path of the test executable, absent for doc-tests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) executable: Option<String>,
+    /// Cargo arguments that select this binary, e.g. `-p app --lib`, reported when it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rerun_args: Option<String>,
+    pub(crate) passed: usize,
+    pub(crate) failed: usize,
+    pub(crate) ignored: usize,
+    pub(crate) measured: usize,
+    pub(crate) filtered_out: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) duration_secs: Option<f64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) failures: Vec<TestFailure>,
+    /// Tests that failed at first and passed when retried
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) flaky: Vec<String>,
+}
+
+/// Test results of one `cargo test` invocation, returned as `structured_content`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct TestReport {
+    pub(crate) success: bool,
+    pub(crate) passed: usize,
+    pub(crate) failed: usize,
+    pub(crate) ignored: usize,
+    pub(crate) filtered_out: usize,
+    pub(crate) binaries: Vec<TestBinary>,
+    /// Tests killed because they ran longer than the time limit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) timed_out: Vec<TimedOutTest>,
+}
+
+impl TestReport {
+    pub(crate) fn new(success: bool, binaries: Vec<TestBinary>) -> Self {
+        let sum = |count: fn(&TestBinary) -> usize| binaries.iter().map(count).sum();
+        Self {
+            success,
+            passed: sum(|binary| binary.passed),
+            failed: sum(|binary| binary.failed),
+            ignored: sum(|binary| binary.ignored),
+            filtered_out: sum(|binary| binary.filtered_out),
+            binaries,
+            timed_out: Vec::new(),
+        }
+    }
+
+    /// Takes the test results out of the output of `cargo test`.
+    ///
+    /// Binary names come from cargo's "Running ..." lines on stderr, which are in the same order
+    /// as the test runs on stdout. Those lines and cargo's progress lines are dropped from stderr,
+    /// and stdout is dropped once it has been parsed.
+    pub(crate) fn from_output(output: &mut Output) -> Self {
+        let stderr = output.stderr.take().map(|s| s.0).unwrap_or_default();
+        let targets = parse::running_targets(&stderr);
+        let stderr: Vec<&str> = stderr
+            .lines()
+            .filter(|line| !parse::is_running_line(line))
+            .collect();
+        output.stderr = filter_cargo_status(Stderr(stderr.join("\n")));
+
+        let stdout = output.stdout.take().map(|s| s.0).unwrap_or_default();
+        let binaries = parse_libtest(&stdout, targets);
+        if binaries.is_empty() && !stdout.is_empty() {
+            output.stdout = Some(crate::command::Stdout(stdout));
+        }
+
+        Self::new(output.success(), binaries)
+    }
+
+    /// Drops the backtrace frames outside the workspace and attaches the source of the others.
+    fn trim_backtraces(&mut self) {
+        let root = workspace_root();
+        let mut files = HashMap::new();
+        let mut budget = backtrace::SNIPPETS_BUDGET;
+        for failure in self
+            .binaries
+            .iter_mut()
+            .flat_map(|binary| &mut binary.failures)
+        {
+            trim_backtrace(
+                &mut failure.backtrace,
+                failure.location.as_deref(),
+                &root,
+                &mut files,
+                &mut budget,
+            );
+        }
+    }
+
+    /// Builds the tool response with a failure-first summary and the report as
+    /// `structured_content`.
+    pub(crate) fn into_response(mut self, output: Output) -> Response {
+        self.trim_backtraces();
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(render::render_report(&self), "test results")
+                .annotate(Annotations::default()),
+        );
+        response.set_structured_content(
+            serde_json::to_value(&self).expect("test report is always serializable"),
+        );
+        response
+    }
+}
+
+/// Trims a backtrace to workspace code, dropping it if no frame is left, e.g. in doc-tests, which
+/// run from a temporary directory.
+fn trim_backtrace(
+    backtrace: &mut Option<backtrace::Backtrace>,
+    panic_location: Option<&str>,
+    root: &Path,
+    files: &mut HashMap<String, Option<String>>,
+    budget: &mut usize,
+) {
+    if let Some(trace) = backtrace {
+        trace.trim(root, panic_location);
+        trace.attach_snippets(root, files, budget);
+        if trace.frames.is_empty() {
+            *backtrace = None;
+        }
+    }
+}
+
+/// A panic of a program run outside the test harness, e.g. with `cargo run`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct ProgramPanic {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+    pub(crate) location: String,
+    /// Frames in workspace code, printed when the program ran with `RUST_BACKTRACE` set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) backtrace: Option<backtrace::Backtrace>,
+}
+
+impl ProgramPanic {
+    /// Parses the first panic in the stderr of a program, with the backtrace trimmed to workspace
+    /// code.
+    pub(crate) fn from_stderr(stderr: &str) -> Option<Self> {
+        let failure = parse::failure_from_output(String::new(), stderr, None);
+        let mut panic = Self {
+            message: failure.message,
+            location: failure.location?,
+            backtrace: failure.backtrace,
+        };
+        trim_backtrace(
+            &mut panic.backtrace,
+            Some(&panic.location),
+            &workspace_root(),
+            &mut HashMap::new(),
+            &mut backtrace::SNIPPETS_BUDGET.clone(),
+        );
+        Some(panic)
+    }
+
+    pub(crate) fn render(&self) -> String {
+        let mut output = format!("PANICKED at {}\n", self.location);
+        if let Some(message) = &self.message {
+            render::push_indented(&mut output, message);
+        }
+        if let Some(backtrace) = &self.backtrace {
+            backtrace.render(&mut output);
+        }
+        output.truncate(output.trim_end().len());
+        output
+    }
+}
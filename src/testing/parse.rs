@@ -0,0 +1,567 @@
+use super::{TestBinary, TestFailure, backtrace::parse_backtrace};
+
+/// A test binary as announced by cargo on stderr.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RunningTarget {
+    pub(crate) target: String,
+    pub(crate) executable: Option<String>,
+    /// Cargo arguments that select the binary, from cargo's hint after it failed
+    pub(crate) rerun_args: Option<String>,
+}
+
+/// Lines like "Running unittests src/lib.rs (target/debug/deps/app-1a2b)" or "Doc-tests app".
+pub(super) fn is_running_line(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("Running ") || line.starts_with("Doc-tests ")
+}
+
+pub(super) fn running_targets(stderr: &str) -> Vec<RunningTarget> {
+    let mut targets: Vec<RunningTarget> = Vec::new();
+    for line in stderr.lines().map(str::trim) {
+        if let Some(running) = line.strip_prefix("Running ") {
+            let (target, executable) = match running.rsplit_once(" (") {
+                Some((target, executable)) => {
+                    (target, Some(executable.trim_end_matches(')').to_owned()))
+                }
+                None => (running, None),
+            };
+            targets.push(RunningTarget {
+                target: target.to_owned(),
+                executable,
+                rerun_args: None,
+            });
+        } else if line.starts_with("Doc-tests ") {
+            targets.push(RunningTarget {
+                target: line.to_owned(),
+                executable: None,
+                rerun_args: None,
+            });
+        } else if let Some(args) = rerun_hint(line)
+            && let Some(target) = targets.last_mut()
+        {
+            target.rerun_args = Some(args.to_owned());
+        }
+    }
+    targets
+}
+
+/// Parses "error: test failed, to rerun pass `-p app --lib`", which cargo prints right after a
+/// failed test binary.
+fn rerun_hint(line: &str) -> Option<&str> {
+    let (_, args) = line.split_once("failed, to rerun pass `")?;
+    args.strip_suffix('`')
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Event {
+    Suite(SuiteEvent),
+    Test(TestEvent),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct SuiteEvent {
+    event: String,
+    #[serde(default)]
+    passed: usize,
+    #[serde(default)]
+    failed: usize,
+    #[serde(default)]
+    ignored: usize,
+    #[serde(default)]
+    measured: usize,
+    #[serde(default)]
+    filtered_out: usize,
+    exec_time: Option<f64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TestEvent {
+    event: String,
+    name: String,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
+}
+
+/// Parses the stdout of `cargo test`, in libtest's JSON or text format, into one entry per test
+/// binary. `targets` names the binaries in the order they ran.
+pub(crate) fn parse_libtest(stdout: &str, targets: Vec<RunningTarget>) -> Vec<TestBinary> {
+    let mut parser = Parser {
+        targets: targets.into_iter(),
+        ..Default::default()
+    };
+    for line in stdout.lines() {
+        if line.starts_with('{')
+            && let Ok(event) = serde_json::from_str::<Event>(line)
+        {
+            parser.json_event(event);
+        } else {
+            parser.text_line(line);
+        }
+    }
+    // A binary that crashed or was killed before its result line keeps the failures reported
+    // so far
+    parser.finish_binary();
+    parser.binaries
+}
+
+#[derive(Default)]
+struct Parser<'a> {
+    targets: std::vec::IntoIter<RunningTarget>,
+    binaries: Vec<TestBinary>,
+    current: Option<TestBinary>,
+    /// Name and lines of the "---- name stdout ----" section being read in the text format
+    section: Option<(String, Vec<&'a str>)>,
+    /// Tests reported as FAILED in the text format, in case they have no output section
+    failed_names: Vec<String>,
+}
+
+impl<'a> Parser<'a> {
+    fn start_binary(&mut self) {
+        self.finish_binary();
+        let target = self.targets.next();
+        self.current = Some(TestBinary {
+            target: target
+                .as_ref()
+                .map_or_else(|| "unknown".to_owned(), |t| t.target.clone()),
+            executable: target.as_ref().and_then(|t| t.executable.clone()),
+            rerun_args: target.and_then(|t| t.rerun_args),
+            ..Default::default()
+        });
+    }
+
+    fn finish_binary(&mut self) {
+        self.finish_section();
+        if let Some(mut binary) = self.current.take() {
+            for name in self.failed_names.drain(..) {
+                if !binary.failures.iter().any(|failure| failure.name == name) {
+                    binary.failures.push(failure_from_output(name, "", None));
+                }
+            }
+            self.binaries.push(binary);
+        }
+        self.failed_names.clear();
+    }
+
+    fn finish_section(&mut self) {
+        if let Some((name, lines)) = self.section.take()
+            && let Some(binary) = &mut self.current
+        {
+            binary
+                .failures
+                .push(failure_from_output(name, &lines.join("\n"), None));
+        }
+    }
+
+    fn json_event(&mut self, event: Event) {
+        match event {
+            Event::Suite(suite) if suite.event == "started" => self.start_binary(),
+            Event::Suite(suite) => {
+                if let Some(binary) = &mut self.current {
+                    binary.passed = suite.passed;
+                    binary.failed = suite.failed;
+                    binary.ignored = suite.ignored;
+                    binary.measured = suite.measured;
+                    binary.filtered_out = suite.filtered_out;
+                    binary.duration_secs = suite.exec_time;
+                }
+                self.finish_binary();
+            }
+            // "timeout" is libtest's warning that a test has been running for over 60 seconds,
+            // its result follows
+            Event::Test(test) if test.event == "failed" => {
+                if let Some(binary) = &mut self.current {
+                    binary.failures.push(failure_from_output(
+                        test.name,
+                        test.stdout.as_deref().unwrap_or(""),
+                        test.exec_time,
+                    ));
+                }
+            }
+            Event::Test(_) | Event::Other => {}
+        }
+    }
+
+    fn text_line(&mut self, line: &'a str) {
+        if line.starts_with("running ") && line.trim_end().ends_with(['t', 's']) {
+            if line
+                .split_whitespace()
+                .nth(1)
+                .is_some_and(|count| count.parse::<usize>().is_ok())
+            {
+                self.start_binary();
+            }
+            return;
+        }
+        if self.current.is_none() {
+            return;
+        }
+
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|rest| rest.strip_suffix(" stdout ----"))
+        {
+            self.finish_section();
+            self.section = Some((name.to_owned(), Vec::new()));
+        } else if line == "failures:" {
+            self.finish_section();
+        } else if let Some(result) = line.strip_prefix("test result: ") {
+            if let Some(binary) = &mut self.current {
+                parse_result_counts(binary, result);
+            }
+            self.finish_binary();
+        } else if let Some((_, lines)) = &mut self.section {
+            lines.push(line);
+        } else if let Some(test) = line.strip_prefix("test ")
+            && let Some(name) = test.strip_suffix(" ... FAILED")
+        {
+            self.failed_names.push(name.to_owned());
+        }
+    }
+}
+
+/// Parses "ok. 1 passed; 2 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s".
+fn parse_result_counts(binary: &mut TestBinary, result: &str) {
+    let counts = result.split_once(". ").map_or(result, |(_, counts)| counts);
+    for part in counts.split("; ") {
+        if let Some(duration) = part.strip_prefix("finished in ") {
+            binary.duration_secs = duration.trim_end_matches('s').parse().ok();
+            continue;
+        }
+        let Some((count, what)) = part.split_once(' ') else {
+            continue;
+        };
+        let Ok(count) = count.parse() else {
+            continue;
+        };
+        match what {
+            "passed" => binary.passed = count,
+            "failed" => binary.failed = count,
+            "ignored" => binary.ignored = count,
+            "measured" => binary.measured = count,
+            "filtered out" => binary.filtered_out = count,
+            _ => {}
+        }
+    }
+}
+
+/// Splits the captured output of a failed test into what it printed and the panic.
+pub(super) fn failure_from_output(
+    name: String,
+    output: &str,
+    duration_secs: Option<f64>,
+) -> TestFailure {
+    let lines: Vec<&str> = output.lines().collect();
+    let panic = lines
+        .iter()
+        .position(|line| line.starts_with("thread '") && line.contains(" panicked at "));
+
+    let (printed, message, location, backtrace) = match panic {
+        Some(index) => {
+            let (message, location) = parse_panic(&lines[index..]);
+            let backtrace = parse_backtrace(&lines[index..]);
+            (&lines[..index], message, location, backtrace)
+        }
+        None => (&lines[..], None, None, None),
+    };
+
+    let stdout = printed.join("\n");
+    let stdout = stdout.trim();
+    TestFailure {
+        name,
+        message,
+        location,
+        stdout: (!stdout.is_empty()).then(|| stdout.to_owned()),
+        duration_secs,
+        backtrace,
+    }
+}
+
+/// Parses the panic starting at `lines[0]`, either
+/// "thread 'name' (1234) panicked at src/lib.rs:20:9:" followed by the message, or the older
+/// "thread 'name' panicked at 'message', src/lib.rs:20:9".
+fn parse_panic(lines: &[&str]) -> (Option<String>, Option<String>) {
+    let (_, at) = lines[0]
+        .split_once(" panicked at ")
+        .expect("panic lines contain 'panicked at'");
+
+    if let Some(old_format) = at.strip_prefix('\'')
+        && let Some((message, location)) = old_format.rsplit_once("', ")
+    {
+        return (Some(message.to_owned()), Some(location.to_owned()));
+    }
+
+    let message: Vec<&str> = lines[1..]
+        .iter()
+        .take_while(|line| !is_panic_trailer(line))
+        .copied()
+        .collect();
+    let message = message.join("\n");
+    let message = message.trim_end();
+    (
+        (!message.is_empty()).then(|| message.to_owned()),
+        Some(at.trim_end_matches(':').to_owned()),
+    )
+}
+
+/// Lines that follow the panic message.
+fn is_panic_trailer(line: &str) -> bool {
+    line.starts_with("note: run with `RUST_BACKTRACE")
+        || line.starts_with("stack backtrace:")
+        || line.starts_with("note: Some details are omitted")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::backtrace::{Backtrace, Frame};
+
+    const STDERR: &str = "   Compiling tst v0.1.0 (/tmp/tst)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.53s
+     Running unittests src/lib.rs (target/debug/deps/tst-e2ea1d773f7e4d88)
+error: test failed, to rerun pass `--lib`
+     Running tests/it.rs (target/debug/deps/it-dd1b3ff3573679b8)
+   Doc-tests tst
+error: 1 target failed:
+    `--lib`";
+
+    const TEXT_STDOUT: &str = "
+running 4 tests
+test tests::fails ... FAILED
+test tests::ignored ... ignored, slow
+test tests::panics ... FAILED
+test tests::passes ... ok
+
+failures:
+
+---- tests::fails stdout ----
+captured output
+
+thread 'tests::fails' (22736) panicked at src/lib.rs:20:9:
+assertion `left == right` failed
+  left: 3
+ right: 4
+stack backtrace:
+   0: __rustc::rust_begin_unwind
+             at /rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/std/src/panicking.rs:689:5
+note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.
+
+---- tests::panics stdout ----
+
+thread 'tests::panics' (22737) panicked at src/lib.rs:29:9:
+boom
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::fails
+    tests::panics
+
+test result: FAILED. 1 passed; 2 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+
+running 1 test
+test integration_ok ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 3 filtered out; finished in 0.00s
+
+
+running 1 test
+test src/lib.rs - add (line 1) ... ok
+
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+
+all doctests ran in 0.24s; merged doctests compilation took 0.24s";
+
+    const JSON_STDOUT: &str = r#"{ "type": "suite", "event": "started", "test_count": 4 }
+{ "type": "test", "event": "started", "name": "tests::fails" }
+{ "type": "test", "name": "tests::fails", "event": "failed", "exec_time": 0.014107697, "stdout": "captured output\n\nthread 'tests::fails' (22858) panicked at src/lib.rs:20:9:\nassertion `left == right` failed\n  left: 3\n right: 4\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n" }
+{ "type": "test", "name": "tests::ignored", "event": "ignored", "message": "slow" }
+{ "type": "test", "name": "tests::passes", "event": "ok", "exec_time": 0.000000437 }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.015045961 }
+{ "type": "suite", "event": "started", "test_count": 1 }
+{ "type": "test", "name": "integration_ok", "event": "ok", "exec_time": 0.00000073 }
+{ "type": "suite", "event": "ok", "passed": 1, "failed": 0, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 0.000254505 }
+{ "type": "report", "total_time": 0.275951859, "compilation_time": 0.27164819 }"#;
+
+    #[test]
+    fn test_running_targets() {
+        assert_eq!(
+            running_targets(STDERR),
+            [
+                RunningTarget {
+                    target: "unittests src/lib.rs".into(),
+                    executable: Some("target/debug/deps/tst-e2ea1d773f7e4d88".into()),
+                    rerun_args: Some("--lib".into()),
+                },
+                RunningTarget {
+                    target: "tests/it.rs".into(),
+                    executable: Some("target/debug/deps/it-dd1b3ff3573679b8".into()),
+                    rerun_args: None,
+                },
+                RunningTarget {
+                    target: "Doc-tests tst".into(),
+                    executable: None,
+                    rerun_args: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_text_format() {
+        let binaries = parse_libtest(TEXT_STDOUT, running_targets(STDERR));
+        assert_eq!(binaries.len(), 3);
+
+        let unit = &binaries[0];
+        assert_eq!(unit.target, "unittests src/lib.rs");
+        assert_eq!((unit.passed, unit.failed, unit.ignored), (1, 2, 1));
+        assert_eq!(unit.duration_secs, Some(0.01));
+        assert_eq!(
+            unit.failures[0],
+            TestFailure {
+                name: "tests::fails".into(),
+                message: Some("assertion `left == right` failed\n  left: 3\n right: 4".into()),
+                location: Some("src/lib.rs:20:9".into()),
+                stdout: Some("captured output".into()),
+                duration_secs: None,
+                backtrace: Some(Backtrace {
+                    frames: vec![Frame {
+                        index: 0,
+                        function: "__rustc::rust_begin_unwind".into(),
+                        location: Some(
+                            "/rustc/59807616e1fa2540724bfbac14d7976d7e4a3860/library/std/src/panicking.rs:689:5"
+                                .into()
+                        ),
+                        snippet: None,
+                    }],
+                    omitted: 0,
+                }),
+            }
+        );
+        assert_eq!(unit.failures[1].message.as_deref(), Some("boom"));
+        assert_eq!(unit.failures[1].stdout, None);
+
+        assert_eq!(binaries[1].target, "tests/it.rs");
+        assert_eq!(binaries[1].filtered_out, 3);
+        assert_eq!(binaries[2].target, "Doc-tests tst");
+        assert!(binaries[2].failures.is_empty());
+    }
+
+    #[test]
+    fn test_parse_json_format() {
+        let binaries = parse_libtest(JSON_STDOUT, running_targets(STDERR));
+        assert_eq!(binaries.len(), 2);
+
+        let unit = &binaries[0];
+        assert_eq!((unit.passed, unit.failed, unit.ignored), (1, 1, 1));
+        assert_eq!(unit.duration_secs, Some(0.015045961));
+        assert_eq!(
+            unit.failures,
+            [TestFailure {
+                name: "tests::fails".into(),
+                message: Some("assertion `left == right` failed\n  left: 3\n right: 4".into()),
+                location: Some("src/lib.rs:20:9".into()),
+                stdout: Some("captured output".into()),
+                duration_secs: Some(0.014107697),
+                backtrace: None,
+            }]
+        );
+        assert_eq!(binaries[1].target, "tests/it.rs");
+        assert_eq!(binaries[1].passed, 1);
+    }
+
+    #[test]
+    fn test_slow_test_is_not_a_failure() {
+        let stdout = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "started", "name": "tests::slow" }
+{ "type": "test", "event": "timeout", "name": "tests::slow" }
+{ "type": "test", "name": "tests::slow", "event": "ok", "exec_time": 61.2 }
+{ "type": "test", "event": "started", "name": "tests::slow_failing" }
+{ "type": "test", "event": "timeout", "name": "tests::slow_failing" }
+{ "type": "test", "name": "tests::slow_failing", "event": "failed", "exec_time": 62.5, "stdout": "" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 0, "measured": 0, "filtered_out": 0, "exec_time": 62.5 }"#;
+        let binaries = parse_libtest(stdout, Vec::new());
+        let names: Vec<&str> = binaries[0]
+            .failures
+            .iter()
+            .map(|failure| failure.name.as_str())
+            .collect();
+        assert_eq!(names, ["tests::slow_failing"]);
+    }
+
+    #[test]
+    fn test_truncated_output() {
+        let text = "
+running 3 tests
+test tests::fails ... FAILED
+test tests::passes ... ok
+
+failures:
+
+---- tests::fails stdout ----
+
+thread 'tests::fails' (22736) panicked at src/lib.rs:20:9:
+boom
+";
+        let binaries = parse_libtest(text, running_targets(STDERR));
+        assert_eq!(binaries.len(), 1);
+        assert_eq!(binaries[0].target, "unittests src/lib.rs");
+        assert_eq!(binaries[0].failures.len(), 1);
+        assert_eq!(binaries[0].failures[0].message.as_deref(), Some("boom"));
+
+        let text = "running 2 tests\ntest tests::aborts ... FAILED\ntest tests::hangs ... ";
+        let binaries = parse_libtest(text, Vec::new());
+        assert_eq!(binaries[0].failures[0].name, "tests::aborts");
+
+        let json = r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "event": "started", "name": "tests::fails" }
+{ "type": "test", "name": "tests::fails", "event": "failed", "exec_time": 0.01, "stdout": "" }
+{ "type": "test", "event": "started", "name": "tests::hangs" }"#;
+        let binaries = parse_libtest(json, running_targets(STDERR));
+        assert_eq!(binaries.len(), 1);
+        assert_eq!(binaries[0].failures[0].name, "tests::fails");
+    }
+
+    #[test]
+    fn test_failure_without_panic_or_section() {
+        let stdout = "
+running 1 test
+test returns_err ... FAILED
+
+failures:
+
+---- returns_err stdout ----
+Error: \"not found\"
+
+failures:
+    returns_err
+
+test result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let binaries = parse_libtest(stdout, Vec::new());
+        assert_eq!(binaries[0].target, "unknown");
+        assert_eq!(
+            binaries[0].failures[0].stdout.as_deref(),
+            Some("Error: \"not found\"")
+        );
+        assert_eq!(binaries[0].failures[0].message, None);
+
+        let stdout = "running 1 test\ntest aborted ... FAILED\ntest result: FAILED. 0 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s";
+        let binaries = parse_libtest(stdout, Vec::new());
+        assert_eq!(binaries[0].failures[0].name, "aborted");
+    }
+
+    #[test]
+    fn test_parse_old_panic_format() {
+        let failure = failure_from_output(
+            "t".into(),
+            "thread 't' panicked at 'boom', src/lib.rs:3:5\nnote: run with `RUST_BACKTRACE=1`",
+            None,
+        );
+        assert_eq!(failure.message.as_deref(), Some("boom"));
+        assert_eq!(failure.location.as_deref(), Some("src/lib.rs:3:5"));
+    }
+}
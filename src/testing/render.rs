@@ -0,0 +1,146 @@
+use std::fmt::Write;
+
+use super::{TestBinary, TestReport};
+
+/// Renders failures first, each with its panic and output, followed by one line per binary.
+pub(super) fn render_report(report: &TestReport) -> String {
+    if report.binaries.is_empty() && report.timed_out.is_empty() {
+        return "No tests ran".to_owned();
+    }
+
+    let mut output = format!(
+        "{}: {} passed, {} failed, {} ignored",
+        if report.success { "ok" } else { "FAILED" },
+        report.passed,
+        report.failed,
+        report.ignored
+    );
+    if !report.timed_out.is_empty() {
+        let _ = write!(output, ", {} timed out", report.timed_out.len());
+    }
+    if report.filtered_out > 0 {
+        let _ = write!(output, ", {} filtered out", report.filtered_out);
+    }
+    let _ = match report.binaries.len() {
+        // The run was killed before a test binary finished
+        0 => writeln!(output),
+        1 => writeln!(output, " in 1 test binary"),
+        binaries => writeln!(output, " in {binaries} test binaries"),
+    };
+
+    for test in &report.timed_out {
+        let _ = writeln!(
+            output,
+            "\nTIMED OUT {} ({}) after {}s, killed with the remaining tests",
+            test.name, test.target, test.running_secs
+        );
+    }
+
+    for binary in &report.binaries {
+        for failure in &binary.failures {
+            let _ = write!(output, "\nFAILED {} ({})", failure.name, binary.target);
+            if let Some(duration) = failure.duration_secs {
+                let _ = write!(output, " after {duration:.2}s");
+            }
+            output.push('\n');
+            if let Some(location) = &failure.location {
+                let _ = writeln!(output, "  at {location}");
+            }
+            if let Some(message) = &failure.message {
+                push_indented(&mut output, message);
+            }
+            if let Some(backtrace) = &failure.backtrace {
+                backtrace.render(&mut output);
+            }
+            if let Some(stdout) = &failure.stdout {
+                output.push_str("  stdout:\n");
+                push_indented(&mut output, stdout);
+            }
+        }
+    }
+
+    output.push('\n');
+    for binary in &report.binaries {
+        let _ = writeln!(output, "{}", binary_summary(binary));
+    }
+    output.truncate(output.trim_end().len());
+    output
+}
+
+fn binary_summary(binary: &TestBinary) -> String {
+    let mut summary = format!(
+        "{}: {} passed, {} failed, {} ignored",
+        binary.target, binary.passed, binary.failed, binary.ignored
+    );
+    if binary.filtered_out > 0 {
+        let _ = write!(summary, ", {} filtered out", binary.filtered_out);
+    }
+    if !binary.flaky.is_empty() {
+        let _ = write!(summary, ", flaky: {}", binary.flaky.join(", "));
+    }
+    if let Some(duration) = binary.duration_secs {
+        let _ = write!(summary, " ({duration:.2}s)");
+    }
+    summary
+}
+
+pub(super) fn push_indented(output: &mut String, text: &str) {
+    for line in text.lines() {
+        output.push_str(format!("    {line}").trim_end());
+        output.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestFailure;
+
+    #[test]
+    fn test_render_report() {
+        let binaries = vec![
+            TestBinary {
+                target: "unittests src/lib.rs".into(),
+                passed: 1,
+                failed: 1,
+                ignored: 1,
+                duration_secs: Some(0.01),
+                failures: vec![TestFailure {
+                    name: "tests::fails".into(),
+                    message: Some("assertion `left == right` failed\n  left: 3\n right: 4".into()),
+                    location: Some("src/lib.rs:20:9".into()),
+                    stdout: Some("captured output".into()),
+                    duration_secs: None,
+                    backtrace: None,
+                }],
+                ..Default::default()
+            },
+            TestBinary {
+                target: "tests/it.rs".into(),
+                passed: 1,
+                filtered_out: 3,
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            render_report(&TestReport::new(false, binaries)),
+            "FAILED: 2 passed, 1 failed, 1 ignored, 3 filtered out in 2 test binaries\n\
+             \n\
+             FAILED tests::fails (unittests src/lib.rs)\n\
+             \x20 at src/lib.rs:20:9\n\
+             \x20   assertion `left == right` failed\n\
+             \x20     left: 3\n\
+             \x20    right: 4\n\
+             \x20 stdout:\n\
+             \x20   captured output\n\
+             \n\
+             unittests src/lib.rs: 1 passed, 1 failed, 1 ignored (0.01s)\n\
+             tests/it.rs: 1 passed, 0 failed, 0 ignored, 3 filtered out"
+        );
+        assert_eq!(
+            render_report(&TestReport::new(true, Vec::new())),
+            "No tests ran"
+        );
+    }
+}
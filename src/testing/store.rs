@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+
+use super::TestReport;
+
+/// Failed tests of one test binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FailedTests {
+    /// What cargo ran, e.g. `unittests src/lib.rs`
+    pub(crate) target: String,
+    /// Cargo arguments that select the binary, e.g. `["-p", "app", "--lib"]`
+    pub(crate) cargo_args: Vec<String>,
+    /// Names of the failed tests, empty if the binary failed without reporting any, e.g. when it
+    /// crashed
+    pub(crate) names: Vec<String>,
+}
+
+/// Failed tests of the last `cargo-test` run that ran tests.
+static FAILED: Mutex<Vec<FailedTests>> = Mutex::new(Vec::new());
+
+/// Remembers the failed tests of a run. Runs without test results, e.g. because the tests did not
+/// compile, keep the failures of the previous run.
+pub(crate) fn remember_failures(report: &TestReport) {
+    if report.binaries.is_empty() {
+        return;
+    }
+    let failed = report
+        .binaries
+        .iter()
+        .filter(|binary| binary.failed > 0 || !binary.failures.is_empty())
+        .filter_map(|binary| {
+            Some(FailedTests {
+                target: binary.target.clone(),
+                cargo_args: binary
+                    .rerun_args
+                    .as_deref()?
+                    .split_whitespace()
+                    .map(str::to_owned)
+                    .collect(),
+                names: binary
+                    .failures
+                    .iter()
+                    .map(|failure| failure.name.clone())
+                    .collect(),
+            })
+        })
+        .collect();
+    if let Ok(mut remembered) = FAILED.lock() {
+        *remembered = failed;
+    }
+}
+
+/// Returns the failed tests of the last run, grouped by test binary.
+pub(crate) fn failed_tests() -> Vec<FailedTests> {
+    FAILED
+        .lock()
+        .map(|failed| failed.clone())
+        .unwrap_or_default()
+}
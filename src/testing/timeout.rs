@@ -0,0 +1,193 @@
+//! Detection of tests that run longer than a time limit, from the output of `cargo test` as it
+//! is printed.
+
+use std::{
+    ops::ControlFlow,
+    time::{Duration, Instant},
+};
+
+use super::parse::{is_running_line, running_targets};
+use crate::command::Watched;
+
+/// How long a test runs before libtest's text format reports it.
+const LIBTEST_WARNING_SECS: u64 = 60;
+
+/// A test that was killed because it ran longer than the limit.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct TimedOutTest {
+    pub(crate) name: String,
+    pub(crate) target: String,
+    /// How long the test had been running when it was killed, at least the limit
+    pub(crate) running_secs: u64,
+}
+
+/// Tracks the tests that are running and stops the run when one exceeds the limit.
+///
+/// The JSON format of nightly toolchains reports when each test starts. The text format does so
+/// only on a single test thread, and otherwise reports tests that have been running for over 60
+/// seconds, so hangs are detected after 60 seconds at the earliest.
+pub(crate) struct HangDetector {
+    limit: Duration,
+    target: String,
+    running: Vec<(String, Instant)>,
+    timed_out: Vec<TimedOutTest>,
+}
+
+impl HangDetector {
+    pub(crate) fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            target: String::new(),
+            running: Vec::new(),
+            timed_out: Vec::new(),
+        }
+    }
+
+    /// Watches the output of `cargo test`, breaking once a test exceeds the limit.
+    pub(crate) fn watch(&mut self, event: Watched) -> ControlFlow<()> {
+        let now = Instant::now();
+        match event {
+            Watched::Stderr(line) if is_running_line(line) => {
+                if let Some(running) = running_targets(line).pop() {
+                    self.target = running.target;
+                }
+                self.running.clear();
+            }
+            Watched::Stdout(line) | Watched::UnfinishedStdout(line) => self.observe(line, now),
+            Watched::Stderr(_) | Watched::Tick => {}
+        }
+        self.check(now)
+    }
+
+    fn observe(&mut self, line: &str, now: Instant) {
+        if line.starts_with('{') {
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+                return;
+            };
+            let Some(name) = event["name"].as_str().filter(|_| event["type"] == "test") else {
+                return;
+            };
+            match event["event"].as_str() {
+                Some("started") => self.running.push((name.to_owned(), now)),
+                // Libtest's own warning after 60 seconds, the test is still running
+                Some("timeout") => {}
+                _ => self.finished(name),
+            }
+        } else if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|line| line.strip_suffix(" has been running for over 60 seconds"))
+        {
+            if !self.running.iter().any(|(running, _)| running == name) {
+                let warning = Duration::from_secs(LIBTEST_WARNING_SECS);
+                let started = now.checked_sub(warning).unwrap_or(now);
+                self.running.push((name.to_owned(), started));
+            }
+        } else if let Some((name, result)) = line
+            .strip_prefix("test ")
+            .and_then(|line| line.split_once(" ..."))
+        {
+            // On a single thread, libtest prints "test name ... " when the test starts and the
+            // result once it finishes
+            if result.trim().is_empty() {
+                if !self.running.iter().any(|(running, _)| running == name) {
+                    self.running.push((name.to_owned(), now));
+                }
+            } else {
+                self.finished(name);
+            }
+        }
+    }
+
+    fn finished(&mut self, name: &str) {
+        self.running.retain(|(running, _)| running != name);
+    }
+
+    fn check(&mut self, now: Instant) -> ControlFlow<()> {
+        for (name, started) in &self.running {
+            let running = now.duration_since(*started);
+            if running >= self.limit {
+                self.timed_out.push(TimedOutTest {
+                    name: name.clone(),
+                    target: self.target.clone(),
+                    running_secs: running.as_secs(),
+                });
+            }
+        }
+        if self.timed_out.is_empty() {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    }
+
+    pub(crate) fn into_timed_out(self) -> Vec<TimedOutTest> {
+        self.timed_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_events() {
+        let mut detector = HangDetector::new(Duration::from_secs(5));
+        let start = Instant::now();
+        let _ = detector.watch(Watched::Stderr(
+            "     Running unittests src/lib.rs (target/debug/deps/app-1a2b)",
+        ));
+        detector.observe(
+            r#"{ "type": "test", "event": "started", "name": "tests::quick" }"#,
+            start,
+        );
+        detector.observe(
+            r#"{ "type": "test", "event": "started", "name": "tests::hangs" }"#,
+            start,
+        );
+        detector.observe(
+            r#"{ "type": "test", "name": "tests::quick", "event": "ok" }"#,
+            start,
+        );
+        detector.observe(
+            r#"{ "type": "test", "event": "timeout", "name": "tests::hangs" }"#,
+            start,
+        );
+
+        assert!(detector.check(start + Duration::from_secs(4)).is_continue());
+        assert!(detector.check(start + Duration::from_secs(6)).is_break());
+        assert_eq!(
+            detector.into_timed_out(),
+            [TimedOutTest {
+                name: "tests::hangs".into(),
+                target: "unittests src/lib.rs".into(),
+                running_secs: 6,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_text_warning() {
+        let mut detector = HangDetector::new(Duration::from_secs(90));
+        let now = Instant::now();
+        detector.observe(
+            "test tests::hangs has been running for over 60 seconds",
+            now,
+        );
+        detector.observe("test tests::quick ... ok", now);
+        assert!(detector.check(now + Duration::from_secs(29)).is_continue());
+        assert!(detector.check(now + Duration::from_secs(31)).is_break());
+
+        let mut detector = HangDetector::new(Duration::from_secs(5));
+        detector.observe("test tests::quick ... ", now);
+        detector.observe("test tests::quick ... ok", now);
+        detector.observe("test tests::hangs ... ", now);
+        detector.observe("test tests::hangs ... ", now + Duration::from_secs(1));
+        assert!(detector.check(now + Duration::from_secs(4)).is_continue());
+        assert!(detector.check(now + Duration::from_secs(5)).is_break());
+
+        let mut detector = HangDetector::new(Duration::from_secs(90));
+        detector.observe("test tests::slow has been running for over 60 seconds", now);
+        detector.observe("test tests::slow ... ok", now);
+        assert!(detector.check(now + Duration::from_secs(60)).is_continue());
+    }
+}
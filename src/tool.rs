@@ -2,7 +2,7 @@ use rmcp::ErrorData;
 use rmcp::model::CallToolRequestParam;
 use schemars::JsonSchema;
 
-use crate::Response;
+use crate::{Response, arguments::coerce_arguments, command::Warning};
 
 /// Dyn compatible Tool trait
 pub(crate) trait DynTool {
@@ -20,6 +20,11 @@ pub(crate) trait Tool {
     const DESCRIPTION: &'static str;
     type RequestArgs: serde::de::DeserializeOwned + schemars::JsonSchema;
 
+    /// Rejects contradictory argument combinations before any command is built
+    fn validate(&self, _request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData>;
 }
 
@@ -44,53 +49,39 @@ where
     }
 
     fn call_rmcp_tool(&self, request: CallToolRequestParam) -> Result<Response, ErrorData> {
-        let Some(args) = request.arguments else {
+        let Some(mut args) = request.arguments else {
             return Err(ErrorData::invalid_params("Missing tool arguments", None));
         };
 
+        let coercions = coerce_arguments(&self.json_schema(), &mut args);
+
         let args: T::RequestArgs = serde_json::from_value(args.into()).map_err(|e| {
             ErrorData::invalid_params(format!("Failed to parse tool arguments: {e}"), None)
         })?;
 
-        self.call_rmcp_tool(args)
+        self.validate(&args)?;
+        let mut response = self.call_rmcp_tool(args)?;
+        for coercion in coercions {
+            response.add_content(Warning(coercion).into());
+        }
+        Ok(response)
     }
 }
 
-fn json_schema_impl<T: JsonSchema>() -> serde_json::Map<String, serde_json::Value> {
-    use schemars::schema_for;
-    use serde_json::Value;
-
-    let schema = schema_for!(T).to_value();
-    if let serde_json::Value::Object(mut map) = schema {
-        map.remove("$schema");
-
-        // Gemini doesn't like "type": ["string", "null"]
-        let null_string = Value::String("null".to_string());
-        if let Some(Value::Object(props_map)) = map.get_mut("properties") {
-            for value in props_map.values_mut() {
-                if let Value::Object(prop_obj) = value
-                    && let Some(Value::Array(ty)) = prop_obj.get("type")
-                    && ty.len() == 2
-                    && ty.contains(&null_string)
-                {
-                    let new_ty = ty.iter().find(|v| v != &&null_string).cloned();
-
-                    if let Some(new_ty) = new_ty {
-                        prop_obj.insert("type".to_string(), new_ty);
-                    }
-                }
-            }
-        }
-
-        map
-    } else {
-        panic!("Expected schema to be an object, got: {schema:?}");
+/// Canonical JSON Schema 2020-12 of the tool arguments.
+///
+/// Client-specific adjustments are made by [`crate::schema::SchemaDialect`] when tools are listed.
+pub(crate) fn json_schema_impl<T: JsonSchema>() -> serde_json::Map<String, serde_json::Value> {
+    match schemars::schema_for!(T).to_value() {
+        serde_json::Value::Object(map) => map,
+        schema => panic!("Expected schema to be an object, got: {schema:?}"),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::json_schema_impl;
+    use crate::schema::SchemaDialect;
 
     #[test]
     fn test_tool_json_schema_removes_null_type_first() {
@@ -100,7 +91,7 @@ mod tests {
             value: Option<String>,
         }
 
-        let schema = json_schema_impl::<Example>();
+        let schema = SchemaDialect::Gemini.apply(json_schema_impl::<Example>());
         let props = schema.get("properties").unwrap();
         let value_schema = props.get("value").unwrap();
         if let serde_json::Value::Object(obj) = value_schema {
@@ -120,7 +111,7 @@ mod tests {
             value: Option<i32>,
         }
 
-        let schema = json_schema_impl::<Example>();
+        let schema = SchemaDialect::Gemini.apply(json_schema_impl::<Example>());
         let props = schema.get("properties").unwrap();
         let value_schema = props.get("value").unwrap();
         if let serde_json::Value::Object(obj) = value_schema {
@@ -139,7 +130,7 @@ mod tests {
             value: String,
         }
 
-        let schema = json_schema_impl::<Example>();
+        let schema = SchemaDialect::Gemini.apply(json_schema_impl::<Example>());
         let props = schema.get("properties").unwrap();
         let value_schema = props.get("value").unwrap();
         if let serde_json::Value::Object(obj) = value_schema {
@@ -159,7 +150,7 @@ mod tests {
             num: i32,
         }
 
-        let schema = json_schema_impl::<Example>();
+        let schema = SchemaDialect::Gemini.apply(json_schema_impl::<Example>());
         let props = schema.get("properties").unwrap();
         let opt_schema = props.get("opt").unwrap();
         let num_schema = props.get("num").unwrap();
@@ -186,7 +177,7 @@ mod tests {
             value: bool,
         }
 
-        let schema = json_schema_impl::<Example>();
+        let schema = SchemaDialect::Gemini.apply(json_schema_impl::<Example>());
         let props = schema.get("properties").unwrap();
         let value_schema = props.get("value").unwrap();
         if let serde_json::Value::Object(obj) = value_schema {
@@ -0,0 +1,112 @@
+use std::fmt::Write;
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response, Tool,
+    command::text_with_description,
+    diagnostics::{SuggestionsResult, apply_suggestions, no_diagnostics_remembered},
+    tools::{
+        cargo::{CargoCheckRmcpTool, CargoClippyRmcpTool},
+        workspace_root,
+    },
+};
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ApplySuggestionsRequest {
+    /// Ids of the diagnostics to fix, as reported by `cargo-check`, `cargo-build` or
+    /// `cargo-clippy`.
+    pub ids: Vec<String>,
+
+    /// Also apply suggestions marked `MaybeIncorrect`, which may not compile or may change
+    /// behavior. By default only `MachineApplicable` suggestions are applied.
+    #[serde(default)]
+    allow_maybe_incorrect: Option<bool>,
+
+    /// Return the diff without writing the files.
+    #[serde(default)]
+    dry_run: Option<bool>,
+}
+
+pub struct ApplySuggestionsRmcpTool;
+
+impl Tool for ApplySuggestionsRmcpTool {
+    const NAME: &'static str = "apply-suggestions";
+    const TITLE: &'static str = "Apply compiler suggestions";
+    const DESCRIPTION: &'static str = "Applies the suggested fixes of individual diagnostics by id, from the latest cargo-check, cargo-build or cargo-clippy run. Only applies suggestions that rustc marks as safe unless allowed otherwise. Works on a dirty tree and returns the diff.";
+    type RequestArgs = ApplySuggestionsRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        if request.ids.is_empty() {
+            return Err(ErrorData::invalid_params(
+                "`ids` must contain at least one diagnostic id",
+                None,
+            ));
+        }
+        if no_diagnostics_remembered() {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "No diagnostics to apply yet. Run #{} or #{} first and pass the ids it reports",
+                    CargoCheckRmcpTool::NAME,
+                    CargoClippyRmcpTool::NAME
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let dry_run = request.dry_run.unwrap_or(false);
+        let result = apply_suggestions(
+            &workspace_root(),
+            &request.ids,
+            request.allow_maybe_incorrect.unwrap_or(false),
+            dry_run,
+        )
+        .map_err(|e| ErrorData::internal_error(format!("Failed to write the fixes: {e}"), None))?;
+
+        let mut response = Response::default();
+        response.add_content(
+            text_with_description(render_summary(&result, dry_run), "applied suggestions")
+                .annotate(Annotations::default()),
+        );
+        if !result.diff.is_empty() {
+            response.add_content(
+                text_with_description(result.diff.clone(), "diff").annotate(Annotations::default()),
+            );
+        }
+        if !result.applied.is_empty() && !dry_run {
+            response.add_recommendation(format!(
+                "Run #{} again to verify the changes and get up-to-date diagnostics",
+                CargoCheckRmcpTool::NAME
+            ));
+        }
+        response.set_structured_content(
+            serde_json::to_value(&result).expect("suggestions result is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+fn render_summary(result: &SuggestionsResult, dry_run: bool) -> String {
+    let verb = if dry_run { "Would apply" } else { "Applied" };
+    let mut summary = format!("{verb} {} suggestion(s)", result.applied.len());
+    for applied in &result.applied {
+        let _ = write!(
+            summary,
+            "\n- {}: {} [{:?}]",
+            applied.id, applied.message, applied.applicability
+        );
+    }
+    if !result.skipped.is_empty() {
+        let _ = write!(summary, "\nSkipped {}", result.skipped.len());
+        for skipped in &result.skipped {
+            let _ = write!(summary, "\n- {}: {}", skipped.id, skipped.reason);
+        }
+    }
+    summary
+}
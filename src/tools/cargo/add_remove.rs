@@ -3,6 +3,7 @@ use std::process::Command;
 use crate::{
     Response, Tool, execute_command,
     serde_utils::{PackageWithVersion, deserialize_string, deserialize_string_vec},
+    tools::common::{LOCKFILE_LOCKING_MODE_DESC, lockfile_locking_mode_to_cli_flags},
 };
 use rmcp::ErrorData;
 
@@ -26,33 +27,33 @@ fn dependency_type_to_cli_flag(
 /// Adds a dependency to a Rust project using cargo add.
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoAddRequest {
-    #[schemars(description = "")]
+    /// Dependency to add.
     #[serde(flatten)]
     pub package_spec: PackageWithVersion,
-    #[schemars(description = "")]
+    /// One of: regular, dev, build. Defaults to regular.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     pub dependency_type: Option<String>,
-    #[schemars(description = "")]
+    /// Mark the dependency as optional. Cargo creates an implicit feature with the dependency name.
     #[serde(default)]
     pub optional: bool,
-    #[schemars(description = "")]
+    /// Disable the default features of the dependency.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub no_default_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Re-enable the default features of the dependency.
     #[serde(default)]
     pub default_features: bool,
-    #[schemars(description = "")]
+    /// Features of the dependency to activate.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     pub features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Name to use for the dependency in the code.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -60,18 +61,21 @@ pub struct CargoAddRequest {
     )]
     pub rename: Option<String>,
 
-    #[schemars(description = "")]
+    /// Workspace member to add the dependency to.
     pub target_package: String,
-    #[schemars(description = "")]
+    /// Add the dependency as platform-specific, e.g. `cfg(unix)` or a target triple.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     pub target: Option<String>,
-    #[schemars(description = "")]
+    /// Show the changes without writing `Cargo.toml`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dry_run: Option<bool>,
+    #[schemars(description = LOCKFILE_LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    pub locking_mode: Option<String>,
 }
 
 impl CargoAddRequest {
@@ -119,6 +123,10 @@ impl CargoAddRequest {
             cmd.arg("--dry-run");
         }
 
+        cmd.args(lockfile_locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+        )?);
+
         Ok(cmd)
     }
 }
@@ -139,16 +147,16 @@ impl Tool for CargoAddRmcpTool {
 /// Remove dependencies from a Cargo.toml manifest file.
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoRemoveRequest {
-    #[schemars(description = "")]
+    /// Names of the dependencies to remove.
     pub dep_id: Vec<String>,
-    #[schemars(description = "")]
+    /// One of: regular, dev, build. Defaults to regular.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     pub dependency_type: Option<String>,
-    #[schemars(description = "")]
+    /// Remove the dependencies from the given platform-specific section.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -156,11 +164,14 @@ pub struct CargoRemoveRequest {
     )]
     pub target: Option<String>,
 
-    #[schemars(description = "")]
+    /// Workspace member to remove the dependencies from.
     pub target_package: String,
-    #[schemars(description = "")]
+    /// Show the changes without writing `Cargo.toml`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub dry_run: Option<bool>,
+    #[schemars(description = LOCKFILE_LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    pub locking_mode: Option<String>,
 }
 
 impl CargoRemoveRequest {
@@ -191,6 +202,10 @@ impl CargoRemoveRequest {
             cmd.arg("--dry-run");
         }
 
+        cmd.args(lockfile_locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+        )?);
+
         Ok(cmd)
     }
 }
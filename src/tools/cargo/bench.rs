@@ -0,0 +1,687 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+use crate::{
+    Response, Tool,
+    command::{Stderr, text_with_description},
+    diagnostics::filter_cargo_status,
+    execute_command,
+    serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::{
+        common::{LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags},
+        target_directory,
+    },
+};
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use super::test::TestSelection;
+
+/// Change in percent below which a benchmark counts as unchanged, unless set in the request.
+const DEFAULT_NOISE_THRESHOLD_PERCENT: f64 = 2.0;
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoBenchRequest {
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`. Benchmarks with the built-in `#[bench]` attribute need `nightly`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Only run benchmarks whose name contains this string.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    benchname: Option<String>,
+
+    /// Arguments passed to the bench binaries after `--`, e.g. `["--sample-size", "20"]` for
+    /// Criterion.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string_vec"
+    )]
+    bench_args: Option<Vec<String>>,
+
+    /// Save the results under this name, e.g. `before`, to compare later runs against them with
+    /// `baseline`. Results of benchmarks that did not run are kept.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    save_baseline: Option<String>,
+
+    /// Compare the results with the baseline saved under this name. Baselines saved by Criterion
+    /// itself with `--save-baseline` are used as well, e.g. `base` for its previous run.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    baseline: Option<String>,
+
+    /// Changes of at most this many percent are reported as noise. Defaults to 2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    noise_threshold_percent: Option<f64>,
+
+    #[serde(flatten)]
+    selection: TestSelection,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+impl CargoBenchRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("bench");
+        if let Some(benchname) = &self.benchname {
+            cmd.arg(benchname);
+        }
+        self.selection.add_package_and_target_args(&mut cmd);
+        self.selection.add_feature_args(&mut cmd);
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        if let Some(bench_args) = self.bench_args.as_ref().filter(|args| !args.is_empty()) {
+            cmd.arg("--").args(bench_args);
+        }
+        Ok(cmd)
+    }
+
+    fn noise_threshold(&self) -> f64 {
+        self.noise_threshold_percent
+            .unwrap_or(DEFAULT_NOISE_THRESHOLD_PERCENT)
+    }
+}
+
+/// Mean time of one iteration, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Measurement {
+    time_ns: f64,
+    /// Lower end of the range the time likely is in: Criterion's confidence interval, or the
+    /// deviation libtest reports
+    lower_ns: f64,
+    upper_ns: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BenchSource {
+    Criterion,
+    Libtest,
+}
+
+/// A benchmark that ran.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct BenchResult {
+    name: String,
+    source: BenchSource,
+    #[serde(flatten)]
+    measurement: Measurement,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<Comparison>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Verdict {
+    Improved,
+    Regressed,
+    /// The change is within the noise threshold, or the ranges of both times overlap
+    Unchanged,
+}
+
+/// Change of a benchmark compared with the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+struct Comparison {
+    baseline_ns: f64,
+    /// Positive if the benchmark got slower
+    change_percent: f64,
+    verdict: Verdict,
+}
+
+impl Comparison {
+    fn new(current: &Measurement, baseline: &Measurement, noise_threshold: f64) -> Self {
+        let change_percent = (current.time_ns - baseline.time_ns) / baseline.time_ns * 100.0;
+        let overlapping =
+            current.lower_ns <= baseline.upper_ns && baseline.lower_ns <= current.upper_ns;
+        let verdict = if change_percent.abs() <= noise_threshold || overlapping {
+            Verdict::Unchanged
+        } else if change_percent > 0.0 {
+            Verdict::Regressed
+        } else {
+            Verdict::Improved
+        };
+        Self {
+            baseline_ns: baseline.time_ns,
+            change_percent,
+            verdict,
+        }
+    }
+}
+
+/// Results of a `cargo bench` run, returned as `structured_content`.
+#[derive(Debug, serde::Serialize)]
+struct BenchReport {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    saved_baseline: Option<String>,
+    noise_threshold_percent: f64,
+    benchmarks: Vec<BenchResult>,
+}
+
+impl BenchReport {
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for bench in &self.benchmarks {
+            let Measurement {
+                time_ns,
+                lower_ns,
+                upper_ns,
+            } = bench.measurement;
+            let _ = write!(
+                text,
+                "{}: {} ({} .. {})",
+                bench.name,
+                format_ns(time_ns),
+                format_ns(lower_ns),
+                format_ns(upper_ns)
+            );
+            if let Some(comparison) = &bench.comparison {
+                let verdict = match comparison.verdict {
+                    Verdict::Improved => "improved",
+                    Verdict::Regressed => "regressed",
+                    Verdict::Unchanged => "within noise",
+                };
+                let _ = write!(
+                    text,
+                    ", {:+.1}% from {}: {verdict}",
+                    comparison.change_percent,
+                    format_ns(comparison.baseline_ns)
+                );
+            }
+            text.push('\n');
+        }
+
+        let count = |verdict| {
+            self.benchmarks
+                .iter()
+                .filter(|bench| bench.comparison.is_some_and(|c| c.verdict == verdict))
+                .count()
+        };
+        let _ = match self.benchmarks.len() {
+            1 => write!(text, "1 benchmark"),
+            count => write!(text, "{count} benchmarks"),
+        };
+        if let Some(baseline) = &self.baseline {
+            let compared = self
+                .benchmarks
+                .iter()
+                .filter(|bench| bench.comparison.is_some())
+                .count();
+            let _ = write!(
+                text,
+                ", compared with baseline `{baseline}`: {} regressed, {} improved, {} within the noise threshold of {}%",
+                count(Verdict::Regressed),
+                count(Verdict::Improved),
+                count(Verdict::Unchanged),
+                self.noise_threshold_percent
+            );
+            if compared < self.benchmarks.len() {
+                let _ = write!(
+                    text,
+                    ", {} not in the baseline",
+                    self.benchmarks.len() - compared
+                );
+            }
+        }
+        if let Some(saved) = &self.saved_baseline {
+            let _ = write!(text, "\nSaved as baseline `{saved}`");
+        }
+        text
+    }
+}
+
+/// Drops the lines Criterion prints on stderr while it measures, e.g. `Benchmarking parse:
+/// Warming up for 3.0000 s`.
+fn filter_criterion_progress(stderr: Stderr) -> Option<Stderr> {
+    let lines: Vec<&str> = stderr
+        .0
+        .lines()
+        .filter(|line| !line.starts_with("Benchmarking "))
+        .collect();
+    (!lines.is_empty()).then(|| Stderr(lines.join("\n")))
+}
+
+/// Formats a time with a unit that keeps the number readable, e.g. `1.23 µs`.
+fn format_ns(ns: f64) -> String {
+    let (value, unit) = match ns.abs() {
+        ns if ns < 1e3 => (ns, "ns"),
+        ns if ns < 1e6 => (ns / 1e3, "µs"),
+        ns if ns < 1e9 => (ns / 1e6, "ms"),
+        ns => (ns / 1e9, "s"),
+    };
+    format!("{value:.2} {unit}")
+}
+
+/// Parses libtest's bench lines, e.g. `test bench_add ... bench: 1,234.50 ns/iter (+/- 56.10)`.
+fn parse_libtest_benches(stdout: &str) -> Vec<BenchResult> {
+    let parse_number = |number: &str| number.trim().replace(',', "").parse::<f64>().ok();
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let (name, rest) = line.strip_prefix("test ")?.split_once(" ... bench:")?;
+            let (time, rest) = rest.split_once(" ns/iter")?;
+            let time_ns = parse_number(time)?;
+            let deviation = rest
+                .trim()
+                .strip_prefix("(+/-")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(parse_number)
+                .unwrap_or(0.0);
+            Some(BenchResult {
+                name: name.trim().to_owned(),
+                source: BenchSource::Libtest,
+                measurement: Measurement {
+                    time_ns,
+                    lower_ns: (time_ns - deviation).max(0.0),
+                    upper_ns: time_ns + deviation,
+                },
+                comparison: None,
+            })
+        })
+        .collect()
+}
+
+/// Directories of Criterion's benchmarks below `dir`, the ones with a `new` measurement.
+fn criterion_benchmark_dirs(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return dirs;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() || entry.file_name() == "report" {
+            continue;
+        }
+        if path.join("new").join("estimates.json").is_file() {
+            dirs.push(path);
+        } else {
+            dirs.extend(criterion_benchmark_dirs(&path));
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+/// Reads the mean and its confidence interval from Criterion's `estimates.json`.
+fn read_criterion_estimates(path: &Path) -> Option<Measurement> {
+    let estimates: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    let mean = &estimates["mean"];
+    Some(Measurement {
+        time_ns: mean["point_estimate"].as_f64()?,
+        lower_ns: mean["confidence_interval"]["lower_bound"].as_f64()?,
+        upper_ns: mean["confidence_interval"]["upper_bound"].as_f64()?,
+    })
+}
+
+/// Name and measurement of a Criterion benchmark in `dir`, from the `new` measurement or a
+/// baseline.
+fn read_criterion_benchmark(
+    criterion_dir: &Path,
+    dir: &Path,
+    baseline: &str,
+) -> Option<(String, Measurement)> {
+    let measurement = read_criterion_estimates(&dir.join(baseline).join("estimates.json"))?;
+    let name = std::fs::read_to_string(dir.join(baseline).join("benchmark.json"))
+        .ok()
+        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+        .and_then(|benchmark| benchmark["full_id"].as_str().map(str::to_owned))
+        .unwrap_or_else(|| {
+            dir.strip_prefix(criterion_dir)
+                .unwrap_or(dir)
+                .to_string_lossy()
+                .into_owned()
+        });
+    Some((name, measurement))
+}
+
+/// Criterion's benchmarks measured since `since`.
+fn read_criterion_results(criterion_dir: &Path, since: SystemTime) -> Vec<BenchResult> {
+    criterion_benchmark_dirs(criterion_dir)
+        .into_iter()
+        .filter_map(|dir| {
+            let modified = std::fs::metadata(dir.join("new").join("estimates.json"))
+                .and_then(|metadata| metadata.modified())
+                .ok()?;
+            if modified < since {
+                return None;
+            }
+            let (name, measurement) = read_criterion_benchmark(criterion_dir, &dir, "new")?;
+            Some(BenchResult {
+                name,
+                source: BenchSource::Criterion,
+                measurement,
+                comparison: None,
+            })
+        })
+        .collect()
+}
+
+/// Baselines saved by this tool, by benchmark name.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Baseline {
+    benchmarks: BTreeMap<String, Measurement>,
+}
+
+fn baselines_dir(target_dir: &Path) -> PathBuf {
+    target_dir.join("rust-mcp-server").join("bench-baselines")
+}
+
+fn read_baseline(target_dir: &Path, name: &str) -> Option<Baseline> {
+    let json =
+        std::fs::read_to_string(baselines_dir(target_dir).join(format!("{name}.json"))).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Loads a baseline saved by this tool, completed with Criterion's own baseline of that name.
+fn load_baseline(target_dir: &Path, name: &str) -> Result<Baseline, ErrorData> {
+    let saved = read_baseline(target_dir, name);
+    let criterion_dir = target_dir.join("criterion");
+    let criterion = criterion_benchmark_dirs(&criterion_dir)
+        .iter()
+        .filter_map(|dir| read_criterion_benchmark(&criterion_dir, dir, name))
+        .collect::<BTreeMap<_, _>>();
+    if saved.is_none() && criterion.is_empty() {
+        let saved = saved_baselines(target_dir);
+        let hint = if saved.is_empty() {
+            "No baselines are saved yet".to_owned()
+        } else {
+            format!("Saved baselines: {}", saved.join(", "))
+        };
+        return Err(ErrorData::invalid_params(
+            format!("No baseline `{name}`. {hint}. Save one with `save_baseline: \"{name}\"`"),
+            None,
+        ));
+    }
+    let mut baseline = saved.unwrap_or_default();
+    for (bench, measurement) in criterion {
+        baseline.benchmarks.entry(bench).or_insert(measurement);
+    }
+    Ok(baseline)
+}
+
+/// Names of the baselines this tool saved.
+fn saved_baselines(target_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(baselines_dir(target_dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension()? == "json").then(|| path.file_stem()?.to_str().map(str::to_owned))?
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Adds the results to the baseline, keeping the benchmarks that did not run.
+fn save_baseline(target_dir: &Path, name: &str, results: &[BenchResult]) -> std::io::Result<()> {
+    let mut baseline = read_baseline(target_dir, name).unwrap_or_default();
+    for result in results {
+        baseline
+            .benchmarks
+            .insert(result.name.clone(), result.measurement);
+    }
+    let dir = baselines_dir(target_dir);
+    std::fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(&baseline).map_err(std::io::Error::other)?;
+    std::fs::write(dir.join(format!("{name}.json")), json)
+}
+
+/// Accepts names that are safe as file names, e.g. `main` or `before-refactor`.
+fn validate_baseline_name(argument: &str, name: &str) -> Result<(), ErrorData> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorData::invalid_params(
+            format!(
+                "Invalid `{argument}` '{name}'. Baseline names consist of letters, digits, '-', '_' and '.'"
+            ),
+            None,
+        ))
+    }
+}
+
+pub struct CargoBenchRmcpTool;
+
+impl Tool for CargoBenchRmcpTool {
+    const NAME: &'static str = "cargo-bench";
+    const TITLE: &'static str = "cargo bench";
+    const DESCRIPTION: &'static str = "Run `cargo bench` and report the time of each Criterion or libtest benchmark. Saves results as named baselines and compares runs with them, reporting the change of each benchmark and whether it exceeds the noise threshold.";
+    type RequestArgs = CargoBenchRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()?;
+        request.selection.reject_doc("cargo bench")?;
+        if let Some(name) = &request.save_baseline {
+            validate_baseline_name("save_baseline", name)?;
+        }
+        if let Some(name) = &request.baseline {
+            validate_baseline_name("baseline", name)?;
+        }
+        if request
+            .noise_threshold_percent
+            .is_some_and(|threshold| !(0.0..=100.0).contains(&threshold))
+        {
+            return Err(ErrorData::invalid_params(
+                "`noise_threshold_percent` must be between 0 and 100",
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let target_dir = target_directory();
+        let criterion_dir = target_dir.join("criterion");
+        // Read before the run, as Criterion replaces its `base` baseline with the new results
+        let baseline = match &request.baseline {
+            Some(name) => Some(load_baseline(&target_dir, name)?),
+            None => None,
+        };
+
+        let started = SystemTime::now();
+        let mut output = execute_command(request.build_cmd()?, Self::NAME)?;
+        let stdout = output.stdout.as_ref().map(|s| s.0.as_str()).unwrap_or("");
+        let mut benchmarks = read_criterion_results(&criterion_dir, started);
+        benchmarks.extend(parse_libtest_benches(stdout));
+
+        let noise_threshold = request.noise_threshold();
+        if let Some(baseline) = &baseline {
+            for bench in &mut benchmarks {
+                bench.comparison = baseline
+                    .benchmarks
+                    .get(&bench.name)
+                    .map(|base| Comparison::new(&bench.measurement, base, noise_threshold));
+            }
+        }
+
+        let mut saved_baseline = None;
+        if let Some(name) = &request.save_baseline
+            && !benchmarks.is_empty()
+        {
+            save_baseline(&target_dir, name, &benchmarks).map_err(|e| {
+                ErrorData::internal_error(format!("Failed to save baseline `{name}`: {e}"), None)
+            })?;
+            saved_baseline = Some(name.clone());
+        }
+
+        let report = BenchReport {
+            success: output.success(),
+            baseline: request.baseline.clone(),
+            saved_baseline,
+            noise_threshold_percent: noise_threshold,
+            benchmarks,
+        };
+
+        output.stderr = output.stderr.take().and_then(filter_cargo_status);
+        if report.success && !report.benchmarks.is_empty() {
+            // Each result is in the report, with Criterion's progress and outlier lines dropped
+            output.stdout = None;
+            output.stderr = output.stderr.take().and_then(filter_criterion_progress);
+        }
+        let rejected_args = request.bench_args.is_some()
+            && output
+                .stderr
+                .as_ref()
+                .is_some_and(|stderr| stderr.0.contains("Unrecognized option"));
+        let mut response: Response = output.into();
+        if rejected_args {
+            response.add_recommendation(
+                "The libtest harness of a target rejected `bench_args`. Select only the Criterion bench targets with `bench`, or set `bench = false` for the other targets in `Cargo.toml`",
+            );
+        }
+        if report.benchmarks.is_empty() {
+            if report.success {
+                response.add_recommendation(
+                    "No benchmark results found. Check that the selection includes bench targets, e.g. with `benches: true`",
+                );
+            }
+        } else {
+            response.add_content(
+                text_with_description(report.render(), "benchmark results")
+                    .annotate(Annotations::default()),
+            );
+            if request.baseline.is_none() && request.save_baseline.is_none() {
+                response.add_recommendation(format!(
+                    "To measure the effect of a change, run #{} with `save_baseline: \"before\"` first and with `baseline: \"before\"` after the change",
+                    Self::NAME
+                ));
+            }
+        }
+        if report.saved_baseline.is_none()
+            && let Some(name) = &request.save_baseline
+        {
+            response.add_content(
+                text_with_description(
+                    format!("Baseline `{name}` was not saved, there are no results"),
+                    "baseline",
+                )
+                .annotate(Annotations::default()),
+            );
+        }
+        response.set_structured_content(
+            serde_json::to_value(&report).expect("bench report is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_libtest_benches() {
+        let stdout = "\nrunning 3 tests\n\
+            test tests::parse_small ... bench:          12.50 ns/iter (+/- 0.75)\n\
+            test tests::parse_large ... bench:       1,234,567 ns/iter (+/- 8,901)\n\
+            test tests::works ... ignored\n\n\
+            test result: ok. 0 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out\n";
+        let benches = parse_libtest_benches(stdout);
+        assert_eq!(benches.len(), 2);
+        assert_eq!(benches[0].name, "tests::parse_small");
+        assert_eq!(
+            benches[0].measurement,
+            Measurement {
+                time_ns: 12.5,
+                lower_ns: 11.75,
+                upper_ns: 13.25
+            }
+        );
+        assert_eq!(benches[1].measurement.time_ns, 1_234_567.0);
+        assert_eq!(benches[1].measurement.upper_ns, 1_243_468.0);
+    }
+
+    #[test]
+    fn test_comparison() {
+        let measurement = |time_ns: f64, spread: f64| Measurement {
+            time_ns,
+            lower_ns: time_ns - spread,
+            upper_ns: time_ns + spread,
+        };
+        let baseline = measurement(100.0, 1.0);
+
+        let slower = Comparison::new(&measurement(110.0, 1.0), &baseline, 2.0);
+        assert_eq!(slower.verdict, Verdict::Regressed);
+        assert!((slower.change_percent - 10.0).abs() < 1e-9);
+
+        let faster = Comparison::new(&measurement(90.0, 1.0), &baseline, 2.0);
+        assert_eq!(faster.verdict, Verdict::Improved);
+
+        let noise = Comparison::new(&measurement(101.5, 0.1), &baseline, 2.0);
+        assert_eq!(noise.verdict, Verdict::Unchanged);
+
+        // Beyond the threshold, but the ranges overlap
+        let overlapping = Comparison::new(&measurement(110.0, 20.0), &baseline, 2.0);
+        assert_eq!(overlapping.verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn test_format_ns() {
+        assert_eq!(format_ns(12.345), "12.35 ns");
+        assert_eq!(format_ns(1_234.0), "1.23 µs");
+        assert_eq!(format_ns(45_600_000.0), "45.60 ms");
+        assert_eq!(format_ns(2e9), "2.00 s");
+    }
+
+    #[test]
+    fn test_baseline_name() {
+        assert!(validate_baseline_name("baseline", "before-refactor_2").is_ok());
+        assert!(validate_baseline_name("baseline", "../main").is_err());
+        assert!(validate_baseline_name("baseline", "").is_err());
+    }
+
+    #[test]
+    fn test_validate_rejects_doc() {
+        let request =
+            |args: serde_json::Value| serde_json::from_value::<CargoBenchRequest>(args).unwrap();
+        let error = CargoBenchRmcpTool
+            .validate(&request(serde_json::json!({ "doc": true })))
+            .unwrap_err();
+        assert!(error.message.contains("`doc`"));
+        assert!(
+            CargoBenchRmcpTool
+                .validate(&request(serde_json::json!({ "doc": false, "lib": true })))
+                .is_ok()
+        );
+    }
+}
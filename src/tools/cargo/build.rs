@@ -1,15 +1,33 @@
 use std::process::Command;
 
 use crate::{
-    Response, Tool, execute_command,
+    Response, Tool,
+    diagnostics::{DiagnosticOptions, DiagnosticReport},
+    execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
-    tools::cargo::CargoCheckRmcpTool,
+    tools::{
+        cargo::CargoCheckRmcpTool,
+        common::{
+            LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags, validate_features,
+            validate_profile,
+        },
+    },
 };
 use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoBuildRequest {
-    #[schemars(description = "")]
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Packages to operate on. By default, the package in the current directory or the default
+    /// workspace members are selected.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -17,11 +35,11 @@ pub struct CargoBuildRequest {
     )]
     package: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Operate on all packages in the workspace.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     workspace: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -29,15 +47,15 @@ pub struct CargoBuildRequest {
     )]
     exclude: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Include the library target.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     lib: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include all binary targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     bins: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the binary target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -45,11 +63,11 @@ pub struct CargoBuildRequest {
     )]
     bin: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all example targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     examples: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the example target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -57,11 +75,11 @@ pub struct CargoBuildRequest {
     )]
     example: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all test targets. Also includes unit tests of the library and binaries.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tests: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the integration test target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -69,11 +87,11 @@ pub struct CargoBuildRequest {
     )]
     test: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all bench targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     benches: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the bench target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -81,11 +99,11 @@ pub struct CargoBuildRequest {
     )]
     bench: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all targets. Equivalent to setting `lib`, `bins`, `tests`, `benches` and `examples`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_targets: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -93,19 +111,19 @@ pub struct CargoBuildRequest {
     )]
     features: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_default_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Build with the `release` profile. Same as `profile: "release"`, so don't set both.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     release: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Build with the given profile, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -113,15 +131,15 @@ pub struct CargoBuildRequest {
     )]
     profile: Option<String>,
 
-    #[schemars(description = "")]
+    /// Number of parallel jobs. Defaults to the number of logical CPUs.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     jobs: Option<u32>,
 
-    #[schemars(description = "")]
+    /// Build as many crates as possible instead of stopping at the first failure.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     keep_going: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Target triple to build for, e.g. `x86_64-unknown-linux-gnu`. Defaults to the host.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -129,15 +147,23 @@ pub struct CargoBuildRequest {
     )]
     target: Option<String>,
 
-    #[schemars(description = "")]
+    /// Treat compiler warnings as errors. Sets `RUSTFLAGS=-D warnings`, which invalidates the build
+    /// cache for the next call without it.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     warnings_as_errors: Option<bool>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+
+    #[serde(flatten)]
+    diagnostics: DiagnosticOptions,
 }
 
 impl CargoBuildRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
-        let mut cmd = Command::new("cargo");
-        cmd.arg("build");
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("build").arg("--message-format=json");
 
         // Package selection
         if let Some(packages) = &self.package {
@@ -234,6 +260,12 @@ impl CargoBuildRequest {
         if self.warnings_as_errors.unwrap_or(false) {
             cmd.env("RUSTFLAGS", "-D warnings");
         }
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 }
@@ -247,13 +279,22 @@ impl Tool for CargoBuildRmcpTool {
         "Builds a Rust project using Cargo. Usually, run without any additional arguments.";
     type RequestArgs = CargoBuildRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_profile(request.release, request.profile.as_deref())?;
+        validate_features(request.all_features, request.features.as_deref())?;
+        request.diagnostics.validate()?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         let cmd = request.build_cmd()?;
         let start_time = std::time::Instant::now();
-        let output = execute_command(cmd, Self::NAME)?;
+        let mut output = execute_command(cmd, Self::NAME)?;
         let duration = start_time.elapsed();
 
-        let mut response: Response = output.into();
+        let mut report = DiagnosticReport::from_output(&mut output);
+        report.apply_options(&request.diagnostics, request.toolchain.as_deref())?;
+        let mut response: Response = report.into_response(output);
         if duration.as_secs() >= 60 {
             response.add_recommendation(format!(
                 "Consider using #{} tool for faster feedback",
@@ -3,123 +3,149 @@ use std::process::Command;
 use crate::{
     Tool,
     command::execute_command,
+    diagnostics::{DiagnosticOptions, DiagnosticReport},
     serde_utils::{deserialize_string, deserialize_string_vec},
-    tools::cargo::CargoFmtRmcpTool,
+    tools::{
+        apply_suggestions::ApplySuggestionsRmcpTool,
+        cargo::CargoFmtRmcpTool,
+        common::{
+            LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags, require,
+            validate_features, validate_profile,
+        },
+    },
 };
 use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoClippyRequest {
-    #[schemars(description = "")]
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Packages to operate on. By default, the package in the current directory or the default
+    /// workspace members are selected.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     package: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Operate on all packages in the workspace.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     workspace: Option<bool>,
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     exclude: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Run Clippy only on the selected packages, not on their path dependencies.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_deps: Option<bool>,
-    #[schemars(description = "")]
+    /// Allow `fix` to run when the working directory has uncommitted changes. Requires `fix`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     allow_dirty: Option<bool>,
-    #[schemars(description = "")]
+    /// Automatically apply lint suggestions. Implies `no_deps` and `all_targets`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     fix: Option<bool>,
-    #[schemars(description = "")]
+    /// Build with the `release` profile. Same as `profile: "release"`, so don't set both.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     release: Option<bool>,
-    #[schemars(description = "")]
+    /// Include all targets. Equivalent to setting `lib`, `bins`, `tests`, `benches` and `examples`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_targets: Option<bool>,
-    #[schemars(description = "")]
+    /// Include the library target.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     lib: Option<bool>,
-    #[schemars(description = "")]
+    /// Include all binary targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     bins: Option<bool>,
-    #[schemars(description = "")]
+    /// Include only the binary target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     bin: Option<String>,
-    #[schemars(description = "")]
+    /// Include all example targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     examples: Option<bool>,
-    #[schemars(description = "")]
+    /// Include only the example target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     example: Option<String>,
-    #[schemars(description = "")]
+    /// Include all test targets. Also includes unit tests of the library and binaries.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tests: Option<bool>,
-    #[schemars(description = "")]
+    /// Include only the integration test target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     test: Option<String>,
-    #[schemars(description = "")]
+    /// Include all bench targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     benches: Option<bool>,
-    #[schemars(description = "")]
+    /// Include only the bench target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     bench: Option<String>,
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_default_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Build with the given profile, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     profile: Option<String>,
-    #[schemars(description = "")]
+    /// Target triple to build for, e.g. `x86_64-unknown-linux-gnu`. Defaults to the host.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     target: Option<String>,
-    #[schemars(description = "")]
+    /// Treat compiler warnings as errors. Sets `RUSTFLAGS=-D warnings`, which invalidates the build
+    /// cache for the next call without it.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     warnings_as_errors: Option<bool>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+
+    #[serde(flatten)]
+    diagnostics: DiagnosticOptions,
 }
 impl CargoClippyRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
-        let mut cmd = Command::new("cargo");
-        cmd.arg("clippy");
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("clippy").arg("--message-format=json");
 
         // Package selection
         if let Some(packages) = &self.package {
@@ -222,6 +248,11 @@ impl CargoClippyRequest {
             cmd.env("RUSTFLAGS", "-D warnings");
         }
 
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 }
@@ -235,17 +266,33 @@ impl Tool for CargoClippyRmcpTool {
         "Checks a Rust package to catch common mistakes and improve code quality using Clippy";
     type RequestArgs = CargoClippyRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_profile(request.release, request.profile.as_deref())?;
+        validate_features(request.all_features, request.features.as_deref())?;
+        request.diagnostics.validate()?;
+        require(
+            ("allow_dirty", request.allow_dirty.unwrap_or(false)),
+            ("fix", request.fix.unwrap_or(false)),
+            "`allow_dirty` only lets `fix` modify a dirty working tree. Set `fix: true` to apply fixes, or remove `allow_dirty`",
+        )?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         let cmd = request.build_cmd()?;
-        let output = execute_command(cmd, Self::NAME)?;
+        let mut output = execute_command(cmd, Self::NAME)?;
+        let mut report = DiagnosticReport::from_output(&mut output);
 
-        let add_fix_recommendation = !request.fix.unwrap_or(false) && output.stderr.is_some();
+        let add_fix_recommendation =
+            !request.fix.unwrap_or(false) && report.has_machine_applicable_suggestions();
+        report.apply_options(&request.diagnostics, request.toolchain.as_deref())?;
         let add_fmt_recommendation = request.fix.unwrap_or(false);
-        let mut response: crate::Response = output.into();
+        let mut response = report.into_response(output);
 
         if add_fix_recommendation {
             response.add_recommendation(format!(
-                "Run #{} with the `fix` and `allow_dirty` options to automatically fix the issues",
+                "Run #{} with the ids of the diagnostics to fix, or #{} with the `fix` and `allow_dirty` options to fix all of them",
+                ApplySuggestionsRmcpTool::NAME,
                 Self::NAME
             ));
         }
@@ -315,4 +362,24 @@ mod tests {
         assert_eq!(tool.all_features, None);
         assert_eq!(tool.allow_dirty, None);
     }
+
+    #[test]
+    fn test_allow_dirty_without_fix_is_rejected() {
+        use crate::tool::DynTool;
+        use rmcp::model::CallToolRequestParam;
+
+        let request = CallToolRequestParam {
+            name: CargoClippyRmcpTool::NAME.into(),
+            arguments: json!({ "allow_dirty": true }).as_object().cloned(),
+        };
+
+        let Err(err) = DynTool::call_rmcp_tool(&CargoClippyRmcpTool, request) else {
+            panic!("`allow_dirty` without `fix` should be rejected");
+        };
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert!(
+            err.message.contains("`allow_dirty` requires `fix`"),
+            "{err:?}"
+        );
+    }
 }
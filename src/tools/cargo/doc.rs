@@ -6,101 +6,121 @@ use rmcp::{ErrorData, model::RawContent};
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
-    tools::WORKSPACE_ROOT,
+    tools::{
+        WORKSPACE_ROOT,
+        common::{
+            LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags, validate_features,
+            validate_profile,
+        },
+    },
 };
 
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoDocRequest {
-    #[schemars(description = "")]
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Packages to operate on. By default, the package in the current directory or the default
+    /// workspace members are selected.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     package: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Operate on all packages in the workspace.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     workspace: Option<bool>,
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     exclude: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Don't build documentation for dependencies.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_deps: Option<bool>,
-    #[schemars(description = "")]
+    /// Include private items in the documentation.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     document_private_items: Option<bool>,
-    #[schemars(description = "")]
+    /// Build with `--cfg docsrs`, as docs.rs does. Enables items gated behind `#[cfg(docsrs)]`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     docsrs_config: Option<bool>,
-    #[schemars(description = "")]
+    /// Document only the library target.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     lib: Option<bool>,
-    #[schemars(description = "")]
+    /// Document all binary targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     bins: Option<bool>,
-    #[schemars(description = "")]
+    /// Document only the binary target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     bin: Option<String>,
-    #[schemars(description = "")]
+    /// Document all example targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     examples: Option<bool>,
-    #[schemars(description = "")]
+    /// Document only the example target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     example: Option<String>,
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string_vec"
     )]
     features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_default_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Build with the `release` profile. Same as `profile: "release"`, so don't set both.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     release: Option<bool>,
-    #[schemars(description = "")]
+    /// Build with the given profile, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     profile: Option<String>,
-    #[schemars(description = "")]
+    /// Number of parallel jobs. Defaults to the number of logical CPUs.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     jobs: Option<u32>,
-    #[schemars(description = "")]
+    /// Build as many crates as possible instead of stopping at the first failure.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     keep_going: Option<bool>,
-    #[schemars(description = "")]
+    /// Target triple to build for, e.g. `x86_64-unknown-linux-gnu`. Defaults to the host.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
         deserialize_with = "deserialize_string"
     )]
     target: Option<String>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
 }
 
 impl CargoDocRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
-        let mut cmd = Command::new("cargo");
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
         cmd.arg("doc");
 
         // Package selection
@@ -189,6 +209,11 @@ impl CargoDocRequest {
             cmd.arg("--target").arg(target);
         }
 
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 
@@ -262,6 +287,12 @@ impl Tool for CargoDocRmcpTool {
     const DESCRIPTION: &'static str = "Build documentation for a Rust package using Cargo. Recommended to use with no_deps and specific package for faster builds. Returns path to generated documentation index.";
     type RequestArgs = CargoDocRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_profile(request.release, request.profile.as_deref())?;
+        validate_features(request.all_features, request.features.as_deref())?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         use rmcp::model::{AnnotateAble, Annotations};
 
@@ -3,24 +3,29 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{PackageWithVersion, deserialize_string},
+    tools::common::{LOCKING_MODE_DESC, locking_mode_to_cli_flags},
 };
 use rmcp::ErrorData;
 
-/// Display information about a package. Information includes package description, list of available features, etc. Equivalent to 'cargo info <SPEC>'.
+/// Display information about a package. Information includes package description, list of available
+/// features, etc. Equivalent to 'cargo info <SPEC>'.
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoInfoRequest {
-    #[schemars(description = "")]
+    /// Package to show information about.
     #[serde(flatten)]
     pub package_spec: PackageWithVersion,
-    #[schemars(description = "")]
+    /// Registry index URL to search the package in.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub index: Option<String>,
-    #[schemars(description = "")]
+    /// Registry to search the package in instead of crates.io.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub registry: Option<String>,
-    #[schemars(description = "")]
+    /// Override a cargo configuration value, e.g. `net.git-fetch-with-cli=true`.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub config: Option<String>,
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    pub locking_mode: Option<String>,
 }
 impl CargoInfoRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
@@ -41,6 +46,11 @@ impl CargoInfoRequest {
             cmd.arg("--config").arg(config);
         }
 
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 }
@@ -3,30 +3,37 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
-    tools::cargo::CargoWorkspaceInfoRmcpTool,
+    tools::{
+        cargo::CargoWorkspaceInfoRmcpTool,
+        common::{LOCKING_MODE_DESC, locking_mode_to_cli_flags, validate_features},
+    },
 };
 use rmcp::ErrorData;
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoMetadataRequest {
-    #[schemars(description = "")]
+    /// Only include dependencies resolved for the given target triple.
     #[serde(default, deserialize_with = "deserialize_string")]
     filter_platform: Option<String>,
-    #[schemars(description = "")]
+    /// Output information only about workspace members, without resolving dependencies.
     #[serde(default)]
     no_deps: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Override a cargo configuration value, e.g. `net.git-fetch-with-cli=true`.
     #[serde(default, deserialize_with = "deserialize_string")]
     config: Option<String>,
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default)]
     all_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default)]
     no_default_features: Option<bool>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
 }
 impl CargoMetadataRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
@@ -62,6 +69,11 @@ impl CargoMetadataRequest {
             cmd.arg("--no-default-features");
         }
 
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 }
@@ -74,6 +86,11 @@ impl Tool for CargoMetadataRmcpTool {
     const DESCRIPTION: &'static str = "Outputs a listing of a project's resolved dependencies and metadata in machine-readable format (JSON).";
     type RequestArgs = CargoMetadataRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_features(request.all_features, request.features.as_deref())?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         let cmd = request.build_cmd()?;
         let mut response: crate::Response = execute_command(cmd, Self::NAME)?.into();
@@ -95,6 +112,7 @@ impl Tool for CargoMetadataRmcpTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{arguments::coerce_arguments, tool::DynTool};
     use serde_json::json;
 
     #[test]
@@ -126,14 +144,22 @@ mod tests {
 
     #[test]
     fn test_deserialize_with_features_string_array() {
-        let input = json!({
+        let mut input = json!({
             "features": "[\"serde\",\"tokio\"]",
-        });
-
-        let tool: Result<CargoMetadataRequest, _> = serde_json::from_value(input);
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+        let coercions = coerce_arguments(&CargoMetadataRmcpTool.json_schema(), &mut input);
+        assert_eq!(coercions.len(), 1);
+
+        let tool: Result<CargoMetadataRequest, _> = serde_json::from_value(input.into());
         let tool = tool
             .expect("Deserialization should succeed with features string that looks like array");
 
-        assert_eq!(tool.features.unwrap(), ["[\"serde\",\"tokio\"]".to_owned()]);
+        assert_eq!(
+            tool.features.unwrap(),
+            ["serde".to_owned(), "tokio".to_owned()]
+        );
     }
 }
@@ -1,4 +1,5 @@
 mod add_remove;
+mod bench;
 mod build;
 mod check;
 mod clippy;
@@ -6,12 +7,16 @@ mod doc;
 mod info;
 mod metadata;
 mod package;
+mod run;
 mod search;
 mod test;
+mod test_flaky;
+mod test_list;
 mod update;
 mod workspace_info;
 
 pub use add_remove::{CargoAddRmcpTool, CargoRemoveRmcpTool};
+pub use bench::CargoBenchRmcpTool;
 pub use build::CargoBuildRmcpTool;
 pub use check::CargoCheckRmcpTool;
 pub use clippy::CargoClippyRmcpTool;
@@ -19,8 +24,14 @@ pub use doc::CargoDocRmcpTool;
 pub use info::CargoInfoRmcpTool;
 pub use metadata::CargoMetadataRmcpTool;
 pub use package::CargoPackageRmcpTool;
+pub use run::CargoRunRmcpTool;
+pub(crate) use run::RunTarget;
 pub use search::CargoSearchRmcpTool;
 pub use test::CargoTestRmcpTool;
+#[cfg(feature = "cargo-llvm-cov")]
+pub(crate) use test::TestSelection;
+pub use test_flaky::CargoTestFlakyRmcpTool;
+pub use test_list::CargoTestListRmcpTool;
 pub use update::CargoUpdateRmcpTool;
 pub use workspace_info::CargoWorkspaceInfoRmcpTool;
 
@@ -29,14 +40,21 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::common::{
+        LOCKFILE_LOCKING_MODE_DESC, lockfile_locking_mode_to_cli_flags, reject_conflict,
+        validate_profile,
+    },
 };
 use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoGenerateLockfileRequest {
-    #[schemars(description = "")]
+    /// Ignore the `rust-version` field of the packages when resolving dependencies.
     #[serde(default)]
     ignore_rust_version: Option<bool>,
+    #[schemars(description = LOCKFILE_LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
 }
 
 impl CargoGenerateLockfileRequest {
@@ -48,6 +66,10 @@ impl CargoGenerateLockfileRequest {
             cmd.arg("--ignore-rust-version");
         }
 
+        cmd.args(lockfile_locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+        )?);
+
         Ok(cmd)
     }
 }
@@ -67,23 +89,23 @@ impl Tool for CargoGenerateLockfileRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoCleanRequest {
-    #[schemars(description = "")]
+    /// Packages to clean artifacts for. By default, the whole target directory is removed.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     package: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Remove only artifacts of the given profile.
     #[serde(default, deserialize_with = "deserialize_string")]
     profile: Option<String>,
-    #[schemars(description = "")]
+    /// Remove only the documentation directory in the target directory.
     #[serde(default)]
     doc: Option<bool>,
-    #[schemars(description = "")]
+    /// Show what would be removed without deleting anything.
     #[serde(default)]
     dry_run: Option<bool>,
-    #[schemars(description = "")]
+    /// Remove only artifacts of the `release` profile.
     #[serde(default)]
     release: Option<bool>,
-    #[schemars(description = "")]
+    /// Remove only artifacts built for the given target triple.
     #[serde(default, deserialize_with = "deserialize_string")]
     target: Option<String>,
 }
@@ -133,6 +155,11 @@ impl Tool for CargoCleanRmcpTool {
     const DESCRIPTION: &'static str = "Cleans the target directory for a Rust project using Cargo. By default, it cleans the entire workspace.";
     type RequestArgs = CargoCleanRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_profile(request.release, request.profile.as_deref())?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         execute_command(request.build_cmd()?, Self::NAME).map(Into::into)
     }
@@ -140,16 +167,17 @@ impl Tool for CargoCleanRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoFmtRequest {
-    #[schemars(description = "")]
+    /// Packages to format. By default, the package in the current directory is formatted.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     package: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Format all packages in the workspace, including path dependencies.
     #[serde(default)]
     all: bool,
-    #[schemars(description = "")]
+    /// Only check formatting. Returns an error and prints a diff if any file needs formatting,
+    /// without modifying files.
     #[serde(default)]
     check: bool,
-    #[schemars(description = "")]
+    /// Output format, `human` or `short`.
     #[serde(default, deserialize_with = "deserialize_string")]
     message_format: Option<String>,
 }
@@ -210,24 +238,25 @@ impl Tool for CargoFmtRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoNewRequest {
+    /// Directory to create the package in. Must not exist yet.
     pub path: String,
-    #[schemars(description = "")]
+    /// Package name. Defaults to the directory name.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub name: Option<String>,
-    #[schemars(description = "")]
+    /// Create a binary package. This is the default if neither `bin` nor `lib` is set.
     #[serde(default)]
     pub bin: bool,
-    #[schemars(description = "")]
+    /// Create a library package.
     #[serde(default)]
     pub lib: Option<bool>,
-    #[schemars(description = "")]
+    /// Rust edition, e.g. `2024`.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub edition: Option<String>,
 
-    #[schemars(description = "")]
+    /// Version control system to initialize, e.g. `git` or `none`.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub vcs: Option<String>,
-    #[schemars(description = "")]
+    /// Registry the package will be published to. Sets `publish` in `Cargo.toml`.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub registry: Option<String>,
 }
@@ -276,6 +305,15 @@ impl Tool for CargoNewRmcpTool {
     const DESCRIPTION: &'static str = "Create a new cargo package at <path>. Creates a new Rust project with the specified name and template.";
     type RequestArgs = CargoNewRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        reject_conflict(
+            ("bin", request.bin),
+            ("lib", request.lib.unwrap_or(false)),
+            "a package is created from either a binary or a library template. Set only one of them (`bin` is the default)",
+        )?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         execute_command(request.build_cmd()?, Self::NAME).map(Into::into)
     }
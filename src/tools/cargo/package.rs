@@ -3,12 +3,14 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::common::{LOCKING_MODE_DESC, locking_mode_to_cli_flags, validate_features},
 };
 use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoPackageRequest {
-    #[schemars(description = "")]
+    /// Packages to operate on. By default, the package in the current directory or the default
+    /// workspace members are selected.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -16,11 +18,11 @@ pub struct CargoPackageRequest {
     )]
     package: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Operate on all packages in the workspace.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     workspace: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -28,27 +30,27 @@ pub struct CargoPackageRequest {
     )]
     exclude: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Print the files that would be included in the package without creating it.
     #[serde(default)]
     list: bool,
 
-    #[schemars(description = "")]
+    /// Don't verify the package contents by building them.
     #[serde(default)]
     no_verify: bool,
 
-    #[schemars(description = "")]
+    /// Ignore warnings about missing metadata such as `description` or `license`.
     #[serde(default)]
     no_metadata: bool,
 
-    #[schemars(description = "")]
+    /// Allow packaging with uncommitted changes in the working directory.
     #[serde(default)]
     allow_dirty: bool,
 
-    #[schemars(description = "")]
+    /// Don't include `Cargo.lock` in the package.
     #[serde(default)]
     exclude_lockfile: bool,
 
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -56,15 +58,15 @@ pub struct CargoPackageRequest {
     )]
     features: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_default_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Target triple used to verify the package.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -72,7 +74,7 @@ pub struct CargoPackageRequest {
     )]
     target: Option<String>,
 
-    #[schemars(description = "")]
+    /// Directory for the packaged files and build artifacts.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -80,13 +82,17 @@ pub struct CargoPackageRequest {
     )]
     target_dir: Option<String>,
 
-    #[schemars(description = "")]
+    /// Number of parallel jobs. Defaults to the number of logical CPUs.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     jobs: Option<u32>,
 
-    #[schemars(description = "")]
+    /// Package as many crates as possible instead of stopping at the first failure.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     keep_going: Option<bool>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
 }
 
 impl CargoPackageRequest {
@@ -164,6 +170,11 @@ impl CargoPackageRequest {
             cmd.arg("--keep-going");
         }
 
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
         Ok(cmd)
     }
 }
@@ -176,6 +187,11 @@ impl Tool for CargoPackageRmcpTool {
     const DESCRIPTION: &'static str = "Assemble the local package into a distributable tarball for publishing. Validates build by default. Usually run without arguments for single-package projects.";
     type RequestArgs = CargoPackageRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_features(request.all_features, request.features.as_deref())?;
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
         let cmd = request.build_cmd()?;
         execute_command(cmd, Self::NAME).map(Into::into)
@@ -0,0 +1,780 @@
+use std::{
+    fmt::Write,
+    ops::ControlFlow,
+    path::Path,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Response, Tool,
+    command::{Stderr, Watched, execute_command_watched, text_with_description},
+    diagnostics::filter_cargo_status,
+    serde_utils::{deserialize_string, deserialize_string_vec},
+    testing::ProgramPanic,
+    tools::{
+        cargo::CargoBuildRmcpTool,
+        common::{
+            LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags, reject_conflict,
+            validate_features, validate_profile,
+        },
+        process::ProcessStartRmcpTool,
+    },
+};
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+/// Longest time a program may run.
+const MAX_TIMEOUT_SECS: u64 = 3600;
+
+/// Time the build may take by default, e.g. while waiting for the lock of another cargo.
+const DEFAULT_BUILD_TIMEOUT_SECS: u64 = 600;
+
+/// Output the program may print by default, stdout and stderr together.
+const DEFAULT_OUTPUT_LIMIT_BYTES: usize = 20_000;
+
+/// Largest output limit that can be requested.
+const MAX_OUTPUT_LIMIT_BYTES: usize = 1_000_000;
+
+/// What `cargo run` builds and runs, shared with the background process tools.
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub(crate) struct RunTarget {
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Package with the binary or example to run. By default, the package in the current directory
+    /// or the default workspace member.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    package: Option<String>,
+
+    /// Name of the binary to run. Required if the package has several binaries and no
+    /// `default-run`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    bin: Option<String>,
+
+    /// Name of the example to run instead of a binary.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    example: Option<String>,
+
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string_vec"
+    )]
+    features: Option<Vec<String>>,
+
+    /// Activate all available features of the selected package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    all_features: Option<bool>,
+
+    /// Do not activate the `default` feature of the selected package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    no_default_features: Option<bool>,
+
+    /// Build with the `release` profile. Same as `profile: "release"`, so don't set both.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    release: Option<bool>,
+
+    /// Build with the given profile, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    profile: Option<String>,
+
+    /// Arguments passed to the program.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string_vec"
+    )]
+    args: Option<Vec<String>>,
+
+    /// Environment variables to set for the build and the program, as `NAME=value`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string_vec"
+    )]
+    env: Option<Vec<String>>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoRunRequest {
+    #[serde(flatten)]
+    target: RunTarget,
+
+    /// Text written to the standard input of the program. By default, its input is empty.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    stdin: Option<String>,
+
+    /// Kill the program once it has run this many seconds, not counting the build. At most 3600.
+    timeout_secs: u64,
+
+    /// Kill cargo if the build has not finished after this many seconds, e.g. because it waits
+    /// for another cargo process to release the build directory. Defaults to 600, at most 3600.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    build_timeout_secs: Option<u64>,
+
+    /// Kill the program once its stdout and stderr together exceed this many bytes, and return
+    /// the output up to there. Defaults to 20000, at most 1000000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output_limit_bytes: Option<usize>,
+
+    /// Run with `RUST_BACKTRACE=1` and report the backtrace of a panic, trimmed to the frames in
+    /// workspace code with the source around them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backtrace: Option<bool>,
+}
+
+impl RunTarget {
+    /// Builds `cargo run` with the program arguments and environment.
+    pub(crate) fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("run");
+
+        if let Some(package) = &self.package {
+            cmd.arg("--package").arg(package);
+        }
+        if let Some(bin) = &self.bin {
+            cmd.arg("--bin").arg(bin);
+        }
+        if let Some(example) = &self.example {
+            cmd.arg("--example").arg(example);
+        }
+
+        // Feature selection
+        if let Some(features) = &self.features {
+            cmd.arg("--features").arg(features.join(","));
+        }
+        if self.all_features.unwrap_or(false) {
+            cmd.arg("--all-features");
+        }
+        if self.no_default_features.unwrap_or(false) {
+            cmd.arg("--no-default-features");
+        }
+
+        // Compilation options
+        if self.release.unwrap_or(false) {
+            cmd.arg("--release");
+        }
+        if let Some(profile) = &self.profile {
+            cmd.arg("--profile").arg(profile);
+        }
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        self.add_env(&mut cmd);
+        if let Some(args) = &self.args {
+            cmd.arg("--").args(args);
+        }
+        Ok(cmd)
+    }
+
+    /// Builds the command that runs an already built executable with the program arguments and
+    /// environment.
+    pub(crate) fn build_executable_cmd(&self, executable: &Path) -> Command {
+        let mut cmd = Command::new(executable);
+        self.add_env(&mut cmd);
+        cmd.args(self.args.iter().flatten());
+        cmd
+    }
+
+    fn add_env(&self, cmd: &mut Command) {
+        for variable in self.env.iter().flatten() {
+            let (name, value) = variable.split_once('=').unwrap_or((variable, ""));
+            cmd.env(name, value);
+        }
+    }
+
+    /// Name of the first cargo option that is set, which running a built executable can't apply.
+    pub(crate) fn cargo_option(&self) -> Option<&'static str> {
+        [
+            ("toolchain", self.toolchain.is_some()),
+            ("package", self.package.is_some()),
+            ("bin", self.bin.is_some()),
+            ("example", self.example.is_some()),
+            ("features", self.features.is_some()),
+            ("all_features", self.all_features.unwrap_or(false)),
+            (
+                "no_default_features",
+                self.no_default_features.unwrap_or(false),
+            ),
+            ("release", self.release.unwrap_or(false)),
+            ("profile", self.profile.is_some()),
+            ("locking_mode", self.locking_mode.is_some()),
+        ]
+        .into_iter()
+        .find_map(|(name, set)| set.then_some(name))
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), ErrorData> {
+        validate_profile(self.release, self.profile.as_deref())?;
+        validate_features(self.all_features, self.features.as_deref())?;
+        reject_conflict(
+            ("bin", self.bin.is_some()),
+            ("example", self.example.is_some()),
+            "cargo runs either a binary or an example. Set only one of them",
+        )?;
+        if let Some(variable) = self.env.iter().flatten().find(|variable| {
+            variable
+                .split_once('=')
+                .is_none_or(|(name, _)| name.is_empty())
+        }) {
+            return Err(ErrorData::invalid_params(
+                format!("Environment variable `{variable}` must be given as `NAME=value`"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl CargoRunRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = self.target.build_cmd()?;
+        if self.backtrace.unwrap_or(false) {
+            cmd.env("RUST_BACKTRACE", "1");
+        }
+        Ok(cmd)
+    }
+
+    fn build_timeout_secs(&self) -> u64 {
+        self.build_timeout_secs
+            .unwrap_or(DEFAULT_BUILD_TIMEOUT_SECS)
+    }
+
+    fn output_limit(&self) -> usize {
+        self.output_limit_bytes
+            .unwrap_or(DEFAULT_OUTPUT_LIMIT_BYTES)
+    }
+}
+
+/// Why the program was killed.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Killed {
+    BuildTimeout,
+    Timeout,
+    OutputLimit,
+}
+
+/// Watches `cargo run`, with the build and the program each timed from their start.
+struct RunWatcher {
+    build_timeout: Duration,
+    timeout: Duration,
+    output_limit: usize,
+    build_started: Instant,
+    started: Option<Instant>,
+    /// Whether the last build line was cargo waiting for another cargo's lock
+    waiting_for_lock: bool,
+    printed: usize,
+    killed: Option<Killed>,
+}
+
+impl RunWatcher {
+    fn new(request: &CargoRunRequest) -> Self {
+        Self {
+            build_timeout: Duration::from_secs(request.build_timeout_secs()),
+            timeout: Duration::from_secs(request.timeout_secs),
+            output_limit: request.output_limit(),
+            build_started: Instant::now(),
+            started: None,
+            waiting_for_lock: false,
+            printed: 0,
+            killed: None,
+        }
+    }
+
+    fn watch(&mut self, event: Watched) -> ControlFlow<()> {
+        let mut unfinished = 0;
+        match event {
+            Watched::Stderr(line) if self.started.is_none() => {
+                if is_cargo_running_line(line) {
+                    self.started = Some(Instant::now());
+                }
+                self.waiting_for_lock = line.trim_start().starts_with("Blocking waiting for");
+            }
+            Watched::Stdout(line) | Watched::Stderr(line) => self.printed += line.len() + 1,
+            Watched::UnfinishedStdout(line) => unfinished = line.len(),
+            Watched::Tick => {}
+        }
+        let Some(started) = self.started else {
+            if self.build_started.elapsed() >= self.build_timeout {
+                self.killed = Some(Killed::BuildTimeout);
+                return ControlFlow::Break(());
+            }
+            return ControlFlow::Continue(());
+        };
+        if started.elapsed() >= self.timeout {
+            self.killed = Some(Killed::Timeout);
+        } else if self.printed + unfinished > self.output_limit {
+            self.killed = Some(Killed::OutputLimit);
+        }
+        if self.killed.is_some() {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+}
+
+/// Cargo's "Running `target/debug/app`" line, printed right before it starts the program.
+fn is_cargo_running_line(line: &str) -> bool {
+    line.trim_start().starts_with("Running `")
+}
+
+/// Splits cargo's stderr into the build output and what the program printed.
+fn split_stderr(stderr: &str) -> (String, Option<String>) {
+    let lines: Vec<&str> = stderr.lines().collect();
+    match lines.iter().position(|line| is_cargo_running_line(line)) {
+        Some(index) => (
+            lines[..index].join("\n"),
+            Some(lines[index + 1..].join("\n")),
+        ),
+        None => (stderr.to_owned(), None),
+    }
+}
+
+/// Drops the raw backtrace from the program's stderr, once it is reported trimmed.
+fn strip_backtrace(stderr: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_backtrace = false;
+    for line in stderr.lines() {
+        if line.starts_with("stack backtrace:") {
+            in_backtrace = true;
+            continue;
+        }
+        if in_backtrace {
+            let frame = line.trim_start();
+            let is_frame = frame.starts_with("at ")
+                || frame
+                    .split_once(": ")
+                    .is_some_and(|(index, _)| index.parse::<usize>().is_ok());
+            if is_frame {
+                continue;
+            }
+            in_backtrace = false;
+            if line.starts_with("note: Some details are omitted") {
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Cuts `text` to at most `limit` bytes, on a character boundary.
+fn truncate(text: &mut String, limit: usize) -> bool {
+    if text.len() <= limit {
+        return false;
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    true
+}
+
+/// Cuts stdout and stderr to at most `limit` bytes together. Each stream keeps at least half of
+/// the limit if it printed that much, so a flood on one stream doesn't hide the other.
+fn truncate_output(stdout: &mut String, stderr: &mut String, limit: usize) -> bool {
+    let stderr_limit = stderr
+        .len()
+        .min((limit / 2).max(limit.saturating_sub(stdout.len())));
+    let stdout_truncated = truncate(stdout, limit - stderr_limit);
+    let stderr_truncated = truncate(stderr, stderr_limit);
+    stdout_truncated || stderr_truncated
+}
+
+/// Result of the program, returned as `structured_content`.
+#[derive(Debug, serde::Serialize)]
+struct RunReport {
+    /// Whether the build succeeded and cargo started the program
+    started: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    killed: Option<Killed>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<f64>,
+    /// Whether stdout or stderr were cut at the output limit
+    output_truncated: bool,
+    stdout: String,
+    stderr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    panic: Option<ProgramPanic>,
+}
+
+fn render_summary(report: &RunReport, request: &CargoRunRequest) -> String {
+    let mut summary = match (report.killed, report.exit_code) {
+        (Some(Killed::BuildTimeout), _) => format!(
+            "Build killed at the build timeout of {}s",
+            request.build_timeout_secs()
+        ),
+        (Some(Killed::Timeout), _) => {
+            format!("Program killed at the timeout of {}s", request.timeout_secs)
+        }
+        (Some(Killed::OutputLimit), _) => format!(
+            "Program killed after printing more than {} bytes",
+            request.output_limit()
+        ),
+        (None, Some(code)) => format!("Program exited with code {code}"),
+        (None, None) => "Program terminated by a signal".to_owned(),
+    };
+    if let Some(duration) = report.duration_secs
+        && report.killed != Some(Killed::Timeout)
+    {
+        let _ = write!(summary, " in {duration:.2}s");
+    }
+    if report.output_truncated {
+        summary.push_str(", output truncated");
+    }
+    summary
+}
+
+pub struct CargoRunRmcpTool;
+
+impl Tool for CargoRunRmcpTool {
+    const NAME: &'static str = "cargo-run";
+    const TITLE: &'static str = "cargo run";
+    const DESCRIPTION: &'static str = "Builds and runs a binary or example of the workspace with arguments, stdin and environment variables, within a timeout and an output limit. Reports the build output separately from what the program printed.";
+    type RequestArgs = CargoRunRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.target.validate()?;
+        if request.timeout_secs == 0 || request.timeout_secs > MAX_TIMEOUT_SECS {
+            return Err(ErrorData::invalid_params(
+                format!("`timeout_secs` must be between 1 and {MAX_TIMEOUT_SECS}"),
+                None,
+            ));
+        }
+        if request.build_timeout_secs() == 0 || request.build_timeout_secs() > MAX_TIMEOUT_SECS {
+            return Err(ErrorData::invalid_params(
+                format!("`build_timeout_secs` must be between 1 and {MAX_TIMEOUT_SECS}"),
+                None,
+            ));
+        }
+        if request.output_limit() == 0 || request.output_limit() > MAX_OUTPUT_LIMIT_BYTES {
+            return Err(ErrorData::invalid_params(
+                format!("`output_limit_bytes` must be between 1 and {MAX_OUTPUT_LIMIT_BYTES}"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let mut watcher = RunWatcher::new(&request);
+        let mut output = execute_command_watched(
+            request.build_cmd()?,
+            Self::NAME,
+            request.stdin.clone(),
+            |event| watcher.watch(event),
+        )?;
+        let duration = watcher.started.map(|started| started.elapsed());
+
+        let stderr = output.stderr.take().map(|s| s.0).unwrap_or_default();
+        let (build_output, program_stderr) = split_stderr(&stderr);
+        output.stderr = filter_cargo_status(Stderr(build_output));
+        let Some(mut program_stderr) = program_stderr else {
+            // The build failed or was killed, cargo did not start the program
+            let mut response: Response = output.into();
+            if watcher.killed == Some(Killed::BuildTimeout) {
+                let report = RunReport {
+                    started: false,
+                    exit_code: None,
+                    killed: watcher.killed,
+                    duration_secs: None,
+                    output_truncated: false,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    panic: None,
+                };
+                response.add_content(
+                    text_with_description(render_summary(&report, &request), "program result")
+                        .annotate(Annotations::default()),
+                );
+                if watcher.waiting_for_lock {
+                    response.add_recommendation(
+                        "Cargo was waiting for another cargo process to release its lock. Wait for that process to finish before running again",
+                    );
+                }
+                response.set_structured_content(
+                    serde_json::to_value(&report).expect("run report is always serializable"),
+                );
+            } else {
+                response.add_recommendation(format!(
+                    "Run #{} to get the build errors with their suggested fixes",
+                    CargoBuildRmcpTool::NAME
+                ));
+            }
+            return Ok(response);
+        };
+        let mut program_stdout = output.stdout.take().map(|s| s.0).unwrap_or_default();
+
+        let panic = ProgramPanic::from_stderr(&program_stderr);
+        if panic
+            .as_ref()
+            .is_some_and(|panic| panic.backtrace.is_some())
+        {
+            program_stderr = strip_backtrace(&program_stderr);
+        }
+        let output_truncated = truncate_output(
+            &mut program_stdout,
+            &mut program_stderr,
+            request.output_limit(),
+        );
+        let report = RunReport {
+            started: true,
+            exit_code: output.exit_status.0.code(),
+            killed: watcher.killed,
+            duration_secs: duration.map(|duration| duration.as_secs_f64()),
+            output_truncated,
+            stdout: program_stdout,
+            stderr: program_stderr,
+            panic,
+        };
+
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(render_summary(&report, &request), "program result")
+                .annotate(Annotations::default()),
+        );
+        if !report.stdout.is_empty() {
+            response.add_content(
+                text_with_description(report.stdout.clone(), "program stdout")
+                    .annotate(Annotations::default()),
+            );
+        }
+        if !report.stderr.is_empty() {
+            response.add_content(
+                text_with_description(report.stderr.clone(), "program stderr")
+                    .annotate(Annotations::default()),
+            );
+        }
+        if let Some(panic) = &report.panic {
+            response.add_content(
+                text_with_description(panic.render(), "panic").annotate(Annotations::default()),
+            );
+            if panic.backtrace.is_none() && !request.backtrace.unwrap_or(false) {
+                response.add_recommendation(format!(
+                    "Run #{} again with `backtrace: true` to see where the panic came from",
+                    Self::NAME
+                ));
+            }
+        }
+        if report.killed == Some(Killed::Timeout) && request.stdin.is_none() {
+            response.add_recommendation(format!(
+                "If the program waits for input, pass it in `stdin`. Start long-running programs like servers in the background with #{}",
+                ProcessStartRmcpTool::NAME
+            ));
+        }
+        response.set_structured_content(
+            serde_json::to_value(&report).expect("run report is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_build_cmd() {
+        let request: CargoRunRequest = serde_json::from_value(serde_json::json!({
+            "package": "app",
+            "bin": "server",
+            "features": ["tls"],
+            "args": ["--port", "8080"],
+            "env": ["RUST_LOG=debug"],
+            "timeout_secs": 10,
+        }))
+        .unwrap();
+        let cmd = request.build_cmd().unwrap();
+        assert_eq!(
+            args(&cmd),
+            [
+                "run",
+                "--package",
+                "app",
+                "--bin",
+                "server",
+                "--features",
+                "tls",
+                "--",
+                "--port",
+                "8080"
+            ]
+        );
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            [(
+                std::ffi::OsStr::new("RUST_LOG"),
+                Some(std::ffi::OsStr::new("debug"))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_split_stderr() {
+        let stderr = "   Compiling app v0.1.0 (/ws/app)\n\
+                      warning: unused variable: `x`\n\
+                      \x20   Finished `dev` profile [unoptimized + debuginfo] target(s) in 0.40s\n\
+                      \x20    Running `target/debug/app --port 8080`\n\
+                      listening\n\
+                      Running `this` is printed by the program";
+        let (build, program) = split_stderr(stderr);
+        assert!(build.ends_with("target(s) in 0.40s"));
+        assert_eq!(
+            program.as_deref(),
+            Some("listening\nRunning `this` is printed by the program")
+        );
+        assert_eq!(split_stderr("error[E0425]: cannot find value").1, None);
+
+        let stderr = "thread 'main' (16739) panicked at src/main.rs:4:19:\n\
+                      boom\n\
+                      stack backtrace:\n\
+                      \x20  4: app::parse\n\
+                      \x20            at ./src/main.rs:4:19\n\
+                      note: Some details are omitted, run with `RUST_BACKTRACE=full` for a verbose backtrace.\n\
+                      shutting down";
+        assert_eq!(
+            strip_backtrace(stderr),
+            "thread 'main' (16739) panicked at src/main.rs:4:19:\nboom\nshutting down"
+        );
+
+        let mut text = "héllo".to_owned();
+        assert!(truncate(&mut text, 2));
+        assert_eq!(text, "h");
+        assert!(!truncate(&mut text, 2));
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        let truncated = |stdout: &str, stderr: &str, limit| {
+            let (mut stdout, mut stderr) = (stdout.to_owned(), stderr.to_owned());
+            let truncated = truncate_output(&mut stdout, &mut stderr, limit);
+            (stdout, stderr, truncated)
+        };
+        assert_eq!(
+            truncated("out", "err", 10),
+            ("out".into(), "err".into(), false)
+        );
+        // One limit for both streams
+        assert_eq!(
+            truncated("aaaaaaaa", "bbbbbbbb", 10),
+            ("aaaaa".into(), "bbbbb".into(), true)
+        );
+        // Room a stream doesn't use goes to the other
+        assert_eq!(
+            truncated("aaaaaaaa", "bb", 10),
+            ("aaaaaaaa".into(), "bb".into(), false)
+        );
+        assert_eq!(
+            truncated("aaaaaaaaaaaa", "bb", 10),
+            ("aaaaaaaa".into(), "bb".into(), true)
+        );
+        assert_eq!(
+            truncated("a", "bbbbbbbbbbbb", 10),
+            ("a".into(), "bbbbbbbbb".into(), true)
+        );
+    }
+
+    #[test]
+    fn test_watcher() {
+        let mut watcher = RunWatcher {
+            build_timeout: Duration::from_secs(60),
+            timeout: Duration::from_secs(60),
+            output_limit: 10,
+            build_started: Instant::now(),
+            started: None,
+            waiting_for_lock: false,
+            printed: 0,
+            killed: None,
+        };
+        // Build output does not count
+        assert!(
+            watcher
+                .watch(Watched::Stderr("warning: unused variable: `x`"))
+                .is_continue()
+        );
+        assert!(
+            watcher
+                .watch(Watched::Stderr("     Running `target/debug/app`"))
+                .is_continue()
+        );
+        assert!(watcher.watch(Watched::Stdout("12345")).is_continue());
+        assert!(
+            watcher
+                .watch(Watched::UnfinishedStdout("123456"))
+                .is_break()
+        );
+        assert_eq!(watcher.killed, Some(Killed::OutputLimit));
+    }
+
+    #[test]
+    fn test_watcher_build_timeout() {
+        let mut watcher = RunWatcher {
+            build_timeout: Duration::ZERO,
+            timeout: Duration::from_secs(60),
+            output_limit: 10,
+            build_started: Instant::now(),
+            started: None,
+            waiting_for_lock: false,
+            printed: 0,
+            killed: None,
+        };
+        assert!(
+            watcher
+                .watch(Watched::Stderr(
+                    "    Blocking waiting for file lock on build directory"
+                ))
+                .is_break()
+        );
+        assert_eq!(watcher.killed, Some(Killed::BuildTimeout));
+        assert!(watcher.waiting_for_lock);
+    }
+}
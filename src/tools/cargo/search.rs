@@ -5,9 +5,11 @@ use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoSearchRequest {
+    /// Search terms, matched against crate names and descriptions.
     pub query: String,
+    /// Maximum number of results, at most 100. Defaults to 10.
     pub limit: Option<u32>,
-    #[schemars(description = "")]
+    /// Registry to search instead of crates.io.
     #[serde(default, deserialize_with = "deserialize_string")]
     pub registry: Option<String>,
 }
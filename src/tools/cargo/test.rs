@@ -1,14 +1,35 @@
-use std::process::Command;
+use std::{process::Command, time::Duration};
 
 use crate::{
-    Tool, execute_command,
+    Tool,
+    command::{Output, execute_command_watched},
+    execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
+    testing::{
+        FailedTests, HangDetector, TestReport, TimedOutTest, failed_tests, remember_failures,
+    },
+    tools::common::{
+        LOCKING_MODE_DESC, cargo_command, is_nightly, locking_mode_to_cli_flags, reject_conflict,
+        validate_features, validate_profile,
+    },
 };
 use rmcp::ErrorData;
 
+/// Makes libtest print one JSON event per line, which needs a nightly toolchain.
+const LIBTEST_JSON_ARGS: [&str; 4] = ["-Z", "unstable-options", "--format=json", "--report-time"];
+
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoTestRequest {
-    #[schemars(description = "")]
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Only run tests whose name contains this string.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -16,7 +37,7 @@ pub struct CargoTestRequest {
     )]
     testname: Option<String>,
 
-    #[schemars(description = "")]
+    /// Arguments passed to the test binaries after `--`, e.g. `--nocapture` or `--test-threads=1`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -24,15 +45,68 @@ pub struct CargoTestRequest {
     )]
     test_args: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Compile the tests but don't run them.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_run: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Run all test binaries even if one of them fails.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_fail_fast: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Kill the tests when one of them runs longer than this many seconds, and name it in the
+    /// result. Without the JSON format of a nightly toolchain, libtest reports when tests start
+    /// only with `--test-threads=1`, and otherwise after they ran for 60 seconds, so hangs are
+    /// detected after 60 seconds at the earliest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    test_timeout_secs: Option<u64>,
+
+    /// Run with `RUST_BACKTRACE=1` and report the backtrace of each panicking test, trimmed to the
+    /// frames in workspace code with the source around them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backtrace: Option<bool>,
+
+    /// Rerun only the tests that failed in the last run, with exact-name filters for each test
+    /// binary. Replaces the test name, package and target selection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rerun_failed: Option<bool>,
+
+    #[serde(flatten)]
+    selection: TestSelection,
+
+    /// Number of parallel jobs. Defaults to the number of logical CPUs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jobs: Option<u32>,
+
+    /// Build with the `release` profile. Same as `profile: "release"`, so don't set both.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    release: Option<bool>,
+
+    /// Build with the given profile, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    profile: Option<String>,
+
+    /// Target triple to build for, e.g. `x86_64-unknown-linux-gnu`. Defaults to the host.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    target: Option<String>,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+/// Package, target and feature selection shared by the test tools.
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub(crate) struct TestSelection {
+    /// Packages to operate on. By default, the package in the current directory or the default
+    /// workspace members are selected.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -40,11 +114,11 @@ pub struct CargoTestRequest {
     )]
     package: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Operate on all packages in the workspace.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     workspace: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -52,15 +126,15 @@ pub struct CargoTestRequest {
     )]
     exclude: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Include the library target.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     lib: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include all binary targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     bins: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the binary target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -68,11 +142,11 @@ pub struct CargoTestRequest {
     )]
     bin: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all example targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     examples: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the example target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -80,11 +154,11 @@ pub struct CargoTestRequest {
     )]
     example: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all test targets. Also includes unit tests of the library and binaries.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tests: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the integration test target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -92,11 +166,11 @@ pub struct CargoTestRequest {
     )]
     test: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all bench targets.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     benches: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Include only the bench target with this name.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -104,15 +178,15 @@ pub struct CargoTestRequest {
     )]
     bench: Option<String>,
 
-    #[schemars(description = "")]
+    /// Include all targets. Equivalent to setting `lib`, `bins`, `tests`, `benches` and `examples`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_targets: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Run only documentation tests.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     doc: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(
         default,
         skip_serializing_if = "Option::is_none",
@@ -120,57 +194,54 @@ pub struct CargoTestRequest {
     )]
     features: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     all_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     no_default_features: Option<bool>,
-
-    #[schemars(description = "")]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    jobs: Option<u32>,
-
-    #[schemars(description = "")]
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    release: Option<bool>,
-
-    #[schemars(description = "")]
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        deserialize_with = "deserialize_string"
-    )]
-    profile: Option<String>,
-
-    #[schemars(description = "")]
-    #[serde(
-        default,
-        skip_serializing_if = "Option::is_none",
-        deserialize_with = "deserialize_string"
-    )]
-    target: Option<String>,
 }
-impl CargoTestRequest {
-    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
-        let mut cmd = Command::new("cargo");
-        cmd.arg("test");
 
-        // Add testname argument if provided
-        if let Some(testname) = &self.testname {
-            cmd.arg(testname);
-        }
+impl TestSelection {
+    pub(crate) fn validate(&self) -> Result<(), ErrorData> {
+        validate_features(self.all_features, self.features.as_deref())
+    }
 
-        // Test compilation options
-        if self.no_run.unwrap_or(false) {
-            cmd.arg("--no-run");
+    /// Rejects `doc` for commands that can't run documentation tests, e.g. `cargo bench`.
+    pub(crate) fn reject_doc(&self, command: &str) -> Result<(), ErrorData> {
+        if self.doc.unwrap_or(false) {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "Argument `doc` is not supported by `{command}`, it only runs documentation tests"
+                ),
+                None,
+            ));
         }
+        Ok(())
+    }
 
-        if self.no_fail_fast.unwrap_or(false) {
-            cmd.arg("--no-fail-fast");
-        }
+    /// Arguments that select packages or targets, with whether they are set.
+    fn package_and_target_arguments(&self) -> [(&'static str, bool); 14] {
+        [
+            ("package", self.package.is_some()),
+            ("workspace", self.workspace.unwrap_or(false)),
+            ("exclude", self.exclude.is_some()),
+            ("lib", self.lib.unwrap_or(false)),
+            ("bins", self.bins.unwrap_or(false)),
+            ("bin", self.bin.is_some()),
+            ("examples", self.examples.unwrap_or(false)),
+            ("example", self.example.is_some()),
+            ("tests", self.tests.unwrap_or(false)),
+            ("test", self.test.is_some()),
+            ("benches", self.benches.unwrap_or(false)),
+            ("bench", self.bench.is_some()),
+            ("all_targets", self.all_targets.unwrap_or(false)),
+            ("doc", self.doc.unwrap_or(false)),
+        ]
+    }
 
+    pub(crate) fn add_package_and_target_args(&self, cmd: &mut Command) {
         // Package selection
         if let Some(packages) = &self.package {
             for package in packages {
@@ -232,8 +303,9 @@ impl CargoTestRequest {
         if self.doc.unwrap_or(false) {
             cmd.arg("--doc");
         }
+    }
 
-        // Feature selection
+    pub(crate) fn add_feature_args(&self, cmd: &mut Command) {
         if let Some(features) = &self.features
             && !features.is_empty()
         {
@@ -247,6 +319,53 @@ impl CargoTestRequest {
         if self.no_default_features.unwrap_or(false) {
             cmd.arg("--no-default-features");
         }
+    }
+}
+
+impl CargoTestRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        self.build_cmd_for(None)
+    }
+
+    /// Runs a test command, killing it if a test runs longer than `test_timeout_secs`.
+    fn execute(&self, cmd: Command) -> Result<(Output, Vec<TimedOutTest>), ErrorData> {
+        let Some(secs) = self.test_timeout_secs else {
+            return Ok((execute_command(cmd, CargoTestRmcpTool::NAME)?, Vec::new()));
+        };
+        let mut detector = HangDetector::new(Duration::from_secs(secs));
+        let output = execute_command_watched(cmd, CargoTestRmcpTool::NAME, None, |event| {
+            detector.watch(event)
+        })?;
+        Ok((output, detector.into_timed_out()))
+    }
+
+    /// Builds the command that runs the selected tests, or only the failed tests of one binary
+    /// if `rerun` is set.
+    fn build_cmd_for(&self, rerun: Option<&FailedTests>) -> Result<Command, ErrorData> {
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("test");
+
+        match rerun {
+            Some(failed) => {
+                cmd.args(&failed.cargo_args);
+            }
+            None => {
+                if let Some(testname) = &self.testname {
+                    cmd.arg(testname);
+                }
+                self.selection.add_package_and_target_args(&mut cmd);
+            }
+        }
+        self.selection.add_feature_args(&mut cmd);
+
+        // Test compilation options
+        if self.no_run.unwrap_or(false) {
+            cmd.arg("--no-run");
+        }
+
+        if self.no_fail_fast.unwrap_or(false) {
+            cmd.arg("--no-fail-fast");
+        }
 
         // Compilation options
         if let Some(jobs) = self.jobs {
@@ -265,12 +384,36 @@ impl CargoTestRequest {
             cmd.arg("--target").arg(target);
         }
 
-        // Pass test binary args after --
-        if let Some(test_args) = &self.test_args {
-            cmd.arg("--");
-            for arg in test_args {
-                cmd.arg(arg);
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        // Pass test binary args after --. On nightly, libtest reports results as JSON, which
+        // carries the captured output and duration of each failed test.
+        let mut test_args = Vec::new();
+        if let Some(failed) = rerun {
+            // Doc-tests don't match their names with `--exact`, but the names include the file
+            // and line, so substring filters select the same tests
+            if !failed.cargo_args.iter().any(|arg| arg == "--doc") {
+                test_args.push("--exact".to_owned());
             }
+            test_args.extend(failed.names.iter().cloned());
+        }
+        test_args.extend(self.test_args.iter().flatten().cloned());
+        let custom_output = test_args
+            .iter()
+            .any(|arg| arg.starts_with("--format") || arg == "--list");
+        if !self.no_run.unwrap_or(false) && !custom_output && is_nightly(self.toolchain.as_deref())
+        {
+            test_args.extend(LIBTEST_JSON_ARGS.map(str::to_owned));
+        }
+        if !test_args.is_empty() {
+            cmd.arg("--").args(test_args);
+        }
+
+        if self.backtrace.unwrap_or(false) {
+            cmd.env("RUST_BACKTRACE", "1");
         }
 
         Ok(cmd)
@@ -286,14 +429,102 @@ impl Tool for CargoTestRmcpTool {
         "Run `cargo test` to execute Rust tests in the current project.";
     type RequestArgs = CargoTestRequest;
 
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        validate_profile(request.release, request.profile.as_deref())?;
+        request.selection.validate()?;
+        if request.test_timeout_secs == Some(0) {
+            return Err(ErrorData::invalid_params(
+                "`test_timeout_secs` must be at least 1",
+                None,
+            ));
+        }
+        if request.rerun_failed.unwrap_or(false) {
+            validate_rerun_failed(request)?;
+        }
+        Ok(())
+    }
+
     fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
-        let cmd = request.build_cmd()?;
-        execute_command(cmd, Self::NAME).map(Into::into)
+        let (report, output) = if request.rerun_failed.unwrap_or(false) {
+            rerun_failed(&request)?
+        } else {
+            let (mut output, timed_out) = request.execute(request.build_cmd()?)?;
+            let mut report = TestReport::from_output(&mut output);
+            report.timed_out = timed_out;
+            (report, output)
+        };
+        remember_failures(&report);
+
+        let failed = !report.success && !report.binaries.is_empty();
+        let hanging: Vec<String> = report
+            .timed_out
+            .iter()
+            .map(|test| test.name.clone())
+            .collect();
+        let mut response = report.into_response(output);
+        for name in hanging {
+            response.add_recommendation(format!(
+                "Check `{name}` for deadlocks and waits without a timeout. Run the other tests with `test_args: [\"--skip\", \"{name}\"]`"
+            ));
+        }
+        if failed {
+            response.add_recommendation(
+                "After fixing the failures, run #cargo-test with `rerun_failed: true` to rerun only the failed tests",
+            );
+        }
+        Ok(response)
+    }
+}
+
+fn validate_rerun_failed(request: &CargoTestRequest) -> Result<(), ErrorData> {
+    let selection = [
+        ("testname", request.testname.is_some()),
+        ("no_run", request.no_run.unwrap_or(false)),
+    ]
+    .into_iter()
+    .chain(request.selection.package_and_target_arguments());
+    for argument in selection {
+        reject_conflict(
+            ("rerun_failed", true),
+            argument,
+            "`rerun_failed` selects the failed tests and their packages and targets itself. Remove the selection, or drop `rerun_failed` to run the selected tests",
+        )?;
+    }
+
+    if failed_tests().is_empty() {
+        return Err(ErrorData::invalid_params(
+            "No failed tests to rerun: the last #cargo-test run passed, or no tests have run yet. Run #cargo-test without `rerun_failed` first",
+            None,
+        ));
     }
+    Ok(())
 }
+
+/// Runs the failed tests of each test binary of the last run, one cargo invocation per binary so
+/// the exact-name filters only apply to the binary the tests failed in.
+fn rerun_failed(request: &CargoTestRequest) -> Result<(TestReport, Output), ErrorData> {
+    let mut outputs = Vec::new();
+    let mut binaries = Vec::new();
+    let mut timed_out = Vec::new();
+    for failed in failed_tests() {
+        let cmd = request.build_cmd_for(Some(&failed))?;
+        let (mut output, hanging) = request.execute(cmd)?;
+        binaries.extend(TestReport::from_output(&mut output).binaries);
+        timed_out.extend(hanging);
+        outputs.push(output);
+    }
+
+    let output = Output::combine(outputs)
+        .ok_or_else(|| ErrorData::invalid_params("No failed tests to rerun", None))?;
+    let mut report = TestReport::new(output.success(), binaries);
+    report.timed_out = timed_out;
+    Ok((report, output))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{arguments::coerce_arguments, tool::DynTool};
     use serde_json::json;
 
     #[test]
@@ -311,10 +542,10 @@ mod tests {
         let tool = tool
             .expect("Deserialization should succeed even if `package` is missing (it's Option)");
 
-        assert_eq!(tool.package, None);
-        assert_eq!(tool.workspace, Some(true));
-        assert_eq!(tool.all_features, Some(true));
-        assert_eq!(tool.all_targets, Some(true));
+        assert_eq!(tool.selection.package, None);
+        assert_eq!(tool.selection.workspace, Some(true));
+        assert_eq!(tool.selection.all_features, Some(true));
+        assert_eq!(tool.selection.all_targets, Some(true));
     }
 
     #[test]
@@ -327,11 +558,11 @@ mod tests {
         let tool = tool.expect("Deserialization should succeed with package array");
 
         assert_eq!(
-            tool.package.unwrap(),
+            tool.selection.package.unwrap(),
             ["my_package".to_owned(), "another_package".to_owned()]
         );
-        assert_eq!(tool.workspace, None);
-        assert_eq!(tool.all_features, None);
+        assert_eq!(tool.selection.workspace, None);
+        assert_eq!(tool.selection.all_features, None);
     }
 
     #[test]
@@ -343,7 +574,10 @@ mod tests {
         let tool: Result<CargoTestRequest, _> = serde_json::from_value(input);
         let tool = tool.expect("Deserialization should succeed with single-item package array");
 
-        assert_eq!(tool.package.unwrap(), ["single_package".to_owned()]);
+        assert_eq!(
+            tool.selection.package.unwrap(),
+            ["single_package".to_owned()]
+        );
     }
 
     #[test]
@@ -355,7 +589,10 @@ mod tests {
         let tool: Result<CargoTestRequest, _> = serde_json::from_value(input);
         let tool = tool.expect("Deserialization should succeed with single-item package array");
 
-        assert_eq!(tool.package.unwrap(), ["single_package".to_owned()]);
+        assert_eq!(
+            tool.selection.package.unwrap(),
+            ["single_package".to_owned()]
+        );
     }
 
     #[test]
@@ -368,7 +605,7 @@ mod tests {
         let tool = tool.expect("Deserialization should succeed with features array");
 
         assert_eq!(
-            tool.features.unwrap(),
+            tool.selection.features.unwrap(),
             ["serde".to_owned(), "tokio".to_owned()]
         );
     }
@@ -382,19 +619,76 @@ mod tests {
         let tool: Result<CargoTestRequest, _> = serde_json::from_value(input);
         let tool = tool.expect("Deserialization should succeed with single feature string");
 
-        assert_eq!(tool.features.unwrap(), ["serde".to_owned()]);
+        assert_eq!(tool.selection.features.unwrap(), ["serde".to_owned()]);
+    }
+
+    #[test]
+    fn test_build_rerun_cmd() {
+        let request: CargoTestRequest =
+            serde_json::from_value(json!({"rerun_failed": true, "features": ["serde"]})).unwrap();
+        let args = |failed: FailedTests| -> Vec<String> {
+            let cmd = request.build_cmd_for(Some(&failed)).unwrap();
+            cmd.get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .take_while(|arg| arg != "-Z")
+                .collect()
+        };
+
+        assert_eq!(
+            args(FailedTests {
+                target: "tests/api.rs".into(),
+                cargo_args: vec!["-p".into(), "b".into(), "--test".into(), "api".into()],
+                names: vec!["fails".into(), "mod::fails".into()],
+            }),
+            [
+                "test",
+                "-p",
+                "b",
+                "--test",
+                "api",
+                "--features",
+                "serde",
+                "--",
+                "--exact",
+                "fails",
+                "mod::fails"
+            ]
+        );
+        assert_eq!(
+            args(FailedTests {
+                target: "Doc-tests b".into(),
+                cargo_args: vec!["--doc".into()],
+                names: vec!["src/lib.rs - one (line 1)".into()],
+            }),
+            [
+                "test",
+                "--doc",
+                "--features",
+                "serde",
+                "--",
+                "src/lib.rs - one (line 1)"
+            ]
+        );
     }
 
     #[test]
     fn test_deserialize_with_features_string_array() {
-        let input = json!({
+        let mut input = json!({
             "features": "[\"serde\",\"tokio\"]",
-        });
-
-        let tool: Result<CargoTestRequest, _> = serde_json::from_value(input);
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+        let coercions = coerce_arguments(&CargoTestRmcpTool.json_schema(), &mut input);
+        assert_eq!(coercions.len(), 1);
+
+        let tool: Result<CargoTestRequest, _> = serde_json::from_value(input.into());
         let tool = tool
             .expect("Deserialization should succeed with features string that looks like array");
 
-        assert_eq!(tool.features.unwrap(), ["[\"serde\",\"tokio\"]".to_owned()]);
+        assert_eq!(
+            tool.selection.features.unwrap(),
+            ["serde".to_owned(), "tokio".to_owned()]
+        );
     }
 }
@@ -0,0 +1,485 @@
+use std::{
+    fmt::Write,
+    hash::{BuildHasher, RandomState},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Response, Tool,
+    command::{Output, text_with_description},
+    execute_command,
+    serde_utils::deserialize_string,
+    testing::TestReport,
+    tools::common::{
+        LOCKING_MODE_DESC, locking_mode_to_cli_flags, unchecked_cargo_command, validate_toolchain,
+    },
+};
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use super::test::TestSelection;
+
+/// Runs when neither `runs` nor `duration_secs` is set.
+const DEFAULT_RUNS: u32 = 10;
+const MAX_RUNS: u32 = 1000;
+const MAX_DURATION_SECS: u64 = 600;
+/// Most seeds reported per distinct failure.
+const MAX_SEEDS: usize = 5;
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoTestFlakyRequest {
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Run the tests whose name contains this string, e.g. `tests::parse_empty`.
+    testname: String,
+
+    /// Only run the test named exactly `testname`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exact: Option<bool>,
+
+    /// How often to run the tests, at most 1000. Defaults to 10, or to as many runs as fit in
+    /// `duration_secs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    runs: Option<u32>,
+
+    /// Keep starting runs until this many seconds have passed, at most 600. Combined with `runs`,
+    /// stops at whichever limit comes first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<u64>,
+
+    /// Run the tests one at a time with `--test-threads=1`, to tell failures caused by tests
+    /// running in parallel from failures of the test itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    single_threaded: Option<bool>,
+
+    /// Environment variable to set to a new random seed for each run, e.g. `PROPTEST_SEED`. The
+    /// seeds of the failed runs are reported so a failure can be reproduced.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    seed_env: Option<String>,
+
+    #[serde(flatten)]
+    selection: TestSelection,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+impl CargoTestFlakyRequest {
+    /// Builds the command of one run, with the seed for `seed_env`. The toolchain is checked once
+    /// in `validate` rather than for each run.
+    pub fn build_cmd(&self, seed: Option<u64>) -> Result<Command, ErrorData> {
+        let mut cmd = unchecked_cargo_command(self.toolchain.as_deref());
+        // A failure in one test binary must not keep the tests of the others from running
+        cmd.arg("test").arg(&self.testname).arg("--no-fail-fast");
+        self.selection.add_package_and_target_args(&mut cmd);
+        self.selection.add_feature_args(&mut cmd);
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        let mut test_args = Vec::new();
+        if self.exact.unwrap_or(false) {
+            test_args.push("--exact");
+        }
+        if self.single_threaded.unwrap_or(false) {
+            test_args.push("--test-threads=1");
+        }
+        if !test_args.is_empty() {
+            cmd.arg("--").args(test_args);
+        }
+
+        if let (Some(env), Some(seed)) = (&self.seed_env, seed) {
+            cmd.env(env, seed.to_string());
+        }
+        Ok(cmd)
+    }
+
+    /// Limits of the loop: the number of runs and the time after which no run starts.
+    fn limits(&self) -> (u32, Option<Duration>) {
+        let duration = self.duration_secs.map(Duration::from_secs);
+        let runs = match (self.runs, duration) {
+            (Some(runs), _) => runs,
+            (None, Some(_)) => MAX_RUNS,
+            (None, None) => DEFAULT_RUNS,
+        };
+        (runs, duration)
+    }
+}
+
+pub struct CargoTestFlakyRmcpTool;
+
+impl Tool for CargoTestFlakyRmcpTool {
+    const NAME: &'static str = "cargo-test-flaky";
+    const TITLE: &'static str = "Detect flaky tests";
+    const DESCRIPTION: &'static str = "Run the selected tests repeatedly to find out whether they fail intermittently. Reports the pass rate and the distinct failure messages with how often each occurred.";
+    type RequestArgs = CargoTestFlakyRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()?;
+        if request.testname.trim().is_empty() {
+            return Err(ErrorData::invalid_params(
+                "`testname` must not be empty. Find the name of the test with #cargo-test-list",
+                None,
+            ));
+        }
+        if request
+            .runs
+            .is_some_and(|runs| runs == 0 || runs > MAX_RUNS)
+        {
+            return Err(ErrorData::invalid_params(
+                format!("`runs` must be between 1 and {MAX_RUNS}"),
+                None,
+            ));
+        }
+        if request
+            .duration_secs
+            .is_some_and(|secs| secs == 0 || secs > MAX_DURATION_SECS)
+        {
+            return Err(ErrorData::invalid_params(
+                format!("`duration_secs` must be between 1 and {MAX_DURATION_SECS}"),
+                None,
+            ));
+        }
+        if let Some(toolchain) = &request.toolchain {
+            validate_toolchain(toolchain)?;
+        }
+        if let Some(env) = &request.seed_env
+            && (env.is_empty() || env.contains(['=', '\0']))
+        {
+            return Err(ErrorData::invalid_params(
+                format!("Invalid environment variable name '{env}' in `seed_env`"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        let (max_runs, duration) = request.limits();
+        let start = Instant::now();
+        let mut report = FlakyReport::new(request.testname.clone(), request.seed_env.clone());
+        let mut shown_output: Option<Output> = None;
+
+        for run in 0..max_runs {
+            if duration.is_some_and(|duration| start.elapsed() >= duration) {
+                break;
+            }
+            let seed = request
+                .seed_env
+                .is_some()
+                .then(|| RandomState::new().hash_one(run));
+            let mut output = execute_command(request.build_cmd(seed)?, Self::NAME)?;
+            let tests = TestReport::from_output(&mut output);
+
+            if run == 0 {
+                // The tests did not compile, or the filter matches nothing
+                if tests.binaries.is_empty() {
+                    return Ok(output.into());
+                }
+                if tests.passed + tests.failed == 0 {
+                    return Err(ErrorData::invalid_params(
+                        format!(
+                            "No test matches `{}`. Find the name of the test with #cargo-test-list",
+                            request.testname
+                        ),
+                        None,
+                    ));
+                }
+            }
+
+            let passed = report.record(&tests, seed);
+            // Show the output of a failed run, which may explain a crash
+            if !passed || shown_output.is_none() {
+                shown_output = Some(output);
+            }
+        }
+        report.duration_secs = start.elapsed().as_secs_f64();
+
+        let mut response: Response = shown_output.map(Response::from).unwrap_or_default();
+        response.add_content(
+            text_with_description(report.render(), "flaky test report")
+                .annotate(Annotations::default()),
+        );
+        if report.failed > 0 && report.passed > 0 && !request.single_threaded.unwrap_or(false) {
+            response.add_recommendation(
+                "Run #cargo-test-flaky with `single_threaded: true` to check whether the failures depend on tests running in parallel",
+            );
+        }
+        response.set_structured_content(
+            serde_json::to_value(&report).expect("flaky test report is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+/// Pass rate of the repeated runs, returned as `structured_content`.
+#[derive(Debug, serde::Serialize)]
+struct FlakyReport {
+    testname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed_env: Option<String>,
+    runs: u32,
+    passed: u32,
+    failed: u32,
+    pass_rate: f64,
+    duration_secs: f64,
+    /// Distinct failures, the most frequent first
+    failures: Vec<DistinctFailure>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize)]
+struct DistinctFailure {
+    test: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    count: u32,
+    /// Seeds of the first runs that failed this way
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    seeds: Vec<u64>,
+}
+
+impl FlakyReport {
+    fn new(testname: String, seed_env: Option<String>) -> Self {
+        Self {
+            testname,
+            seed_env,
+            runs: 0,
+            passed: 0,
+            failed: 0,
+            pass_rate: 0.0,
+            duration_secs: 0.0,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Adds the results of one run, returning whether it passed.
+    fn record(&mut self, tests: &TestReport, seed: Option<u64>) -> bool {
+        self.runs += 1;
+        let passed = tests.success && tests.failed == 0;
+        if passed {
+            self.passed += 1;
+        } else {
+            self.failed += 1;
+            let failures: Vec<(String, String, Option<String>)> = tests
+                .binaries
+                .iter()
+                .flat_map(|binary| &binary.failures)
+                .map(|failure| {
+                    let message = failure
+                        .message
+                        .clone()
+                        .unwrap_or_else(|| "failed without a panic message".to_owned());
+                    (failure.name.clone(), message, failure.location.clone())
+                })
+                .collect();
+            if failures.is_empty() {
+                self.add_failure(
+                    String::new(),
+                    "the run failed without reporting a failed test, e.g. because a test binary crashed".to_owned(),
+                    None,
+                    seed,
+                );
+            }
+            for (test, message, location) in failures {
+                self.add_failure(test, message, location, seed);
+            }
+        }
+        self.pass_rate = f64::from(self.passed) / f64::from(self.runs);
+        passed
+    }
+
+    fn add_failure(
+        &mut self,
+        test: String,
+        message: String,
+        location: Option<String>,
+        seed: Option<u64>,
+    ) {
+        let index = match self
+            .failures
+            .iter()
+            .position(|failure| failure.test == test && failure.message == message)
+        {
+            Some(index) => index,
+            None => {
+                self.failures.push(DistinctFailure {
+                    test,
+                    message,
+                    location,
+                    count: 0,
+                    seeds: Vec::new(),
+                });
+                self.failures.len() - 1
+            }
+        };
+        let failure = &mut self.failures[index];
+        failure.count += 1;
+        if let Some(seed) = seed
+            && failure.seeds.len() < MAX_SEEDS
+        {
+            failure.seeds.push(seed);
+        }
+        // Stable, so failures seen equally often stay in the order they first occurred
+        self.failures
+            .sort_by_key(|failure| std::cmp::Reverse(failure.count));
+    }
+
+    fn render(&self) -> String {
+        let verdict = match (self.passed, self.failed) {
+            (_, 0) => "passed every run",
+            (0, _) => "failed every run",
+            _ => "FLAKY",
+        };
+        let mut output = format!(
+            "{verdict}: `{}` passed {} of {} runs ({:.0}%) in {:.1}s",
+            self.testname,
+            self.passed,
+            self.runs,
+            self.pass_rate * 100.0,
+            self.duration_secs
+        );
+        for failure in &self.failures {
+            let _ = write!(output, "\n\n{}x", failure.count);
+            if !failure.test.is_empty() {
+                let _ = write!(output, " {}", failure.test);
+            }
+            if let Some(location) = &failure.location {
+                let _ = write!(output, " at {location}");
+            }
+            for line in failure.message.lines() {
+                let _ = write!(output, "\n    {line}");
+            }
+            if let (Some(env), false) = (&self.seed_env, failure.seeds.is_empty()) {
+                let seeds: Vec<String> = failure.seeds.iter().map(u64::to_string).collect();
+                let _ = write!(output, "\n  failed with {env}={}", seeds.join(", "));
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{TestBinary, TestFailure};
+    use serde_json::json;
+
+    fn failure(name: &str, message: &str) -> TestFailure {
+        TestFailure {
+            name: name.to_owned(),
+            message: Some(message.to_owned()),
+            location: Some("src/lib.rs:10:5".to_owned()),
+            stdout: None,
+            duration_secs: None,
+            backtrace: None,
+        }
+    }
+
+    fn run(failures: Vec<TestFailure>) -> TestReport {
+        let binary = TestBinary {
+            target: "unittests src/lib.rs".to_owned(),
+            passed: usize::from(failures.is_empty()),
+            failed: failures.len(),
+            failures,
+            ..Default::default()
+        };
+        TestReport::new(binary.failed == 0, vec![binary])
+    }
+
+    #[test]
+    fn test_build_cmd() {
+        let request: CargoTestFlakyRequest = serde_json::from_value(json!({
+            "testname": "tests::flaky",
+            "exact": true,
+            "single_threaded": true,
+            "seed_env": "PROPTEST_SEED",
+            "package": ["app"],
+        }))
+        .unwrap();
+        let cmd = request.build_cmd(Some(42)).unwrap();
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "test",
+                "tests::flaky",
+                "--no-fail-fast",
+                "--package",
+                "app",
+                "--",
+                "--exact",
+                "--test-threads=1"
+            ]
+        );
+        let envs: Vec<_> = cmd.get_envs().collect();
+        assert_eq!(
+            envs,
+            [(
+                std::ffi::OsStr::new("PROPTEST_SEED"),
+                Some(std::ffi::OsStr::new("42"))
+            )]
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let validate = |value| {
+            let request: CargoTestFlakyRequest = serde_json::from_value(value).unwrap();
+            CargoTestFlakyRmcpTool.validate(&request)
+        };
+        assert!(validate(json!({"testname": "flaky", "runs": 5})).is_ok());
+        assert!(validate(json!({"testname": " "})).is_err());
+        assert!(validate(json!({"testname": "flaky", "runs": 0})).is_err());
+        assert!(validate(json!({"testname": "flaky", "duration_secs": 601})).is_err());
+        assert!(validate(json!({"testname": "flaky", "seed_env": "A=B"})).is_err());
+
+        let request: CargoTestFlakyRequest =
+            serde_json::from_value(json!({"testname": "flaky", "duration_secs": 30})).unwrap();
+        assert_eq!(request.limits(), (MAX_RUNS, Some(Duration::from_secs(30))));
+    }
+
+    #[test]
+    fn test_flaky_report() {
+        let mut report = FlakyReport::new("flaky".to_owned(), Some("SEED".to_owned()));
+        assert!(report.record(&run(vec![]), Some(1)));
+        assert!(!report.record(&run(vec![failure("tests::flaky", "timeout")]), Some(2)));
+        assert!(!report.record(&run(vec![failure("tests::flaky", "bad value")]), Some(3)));
+        assert!(!report.record(&run(vec![failure("tests::flaky", "bad value")]), Some(4)));
+        report.duration_secs = 1.25;
+
+        assert_eq!((report.runs, report.passed, report.failed), (4, 1, 3));
+        assert_eq!(report.pass_rate, 0.25);
+        assert_eq!(report.failures[0].message, "bad value");
+        assert_eq!(report.failures[0].seeds, [3, 4]);
+        assert_eq!(
+            report.render(),
+            "FLAKY: `flaky` passed 1 of 4 runs (25%) in 1.2s\n\
+             \n\
+             2x tests::flaky at src/lib.rs:10:5\n\
+             \x20   bad value\n\
+             \x20 failed with SEED=3, 4\n\
+             \n\
+             1x tests::flaky at src/lib.rs:10:5\n\
+             \x20   timeout\n\
+             \x20 failed with SEED=2"
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    Response, Tool,
+    command::text_with_description,
+    execute_command,
+    serde_utils::deserialize_string,
+    testing::{ListedBinary, ListedTest, RunningTarget, render_test_list, take_test_list},
+    tools::{
+        apply_workspace_root,
+        common::{LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags},
+    },
+};
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use super::test::TestSelection;
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoTestListRequest {
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Only list tests whose name contains this string.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    testname: Option<String>,
+
+    #[serde(flatten)]
+    selection: TestSelection,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+impl CargoTestListRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        self.build_cmd_for(false)
+    }
+
+    /// Builds the command that lists the selected tests, or only the ignored ones.
+    fn build_cmd_for(&self, ignored: bool) -> Result<Command, ErrorData> {
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("test");
+
+        if let Some(testname) = &self.testname {
+            cmd.arg(testname);
+        }
+        self.selection.add_package_and_target_args(&mut cmd);
+        self.selection.add_feature_args(&mut cmd);
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        cmd.arg("--").arg("--list");
+        if ignored {
+            cmd.arg("--ignored");
+        }
+        Ok(cmd)
+    }
+}
+
+pub struct CargoTestListRmcpTool;
+
+impl Tool for CargoTestListRmcpTool {
+    const NAME: &'static str = "cargo-test-list";
+    const TITLE: &'static str = "List tests";
+    const DESCRIPTION: &'static str = "List the tests `cargo test` would run, grouped by package, test binary and module, without running them. Marks ignored tests and doc-tests.";
+    type RequestArgs = CargoTestListRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        let mut output = execute_command(request.build_cmd()?, Self::NAME)?;
+        let listed = take_test_list(&mut output);
+        if !output.success() || listed.is_empty() {
+            return Ok(output.into());
+        }
+
+        // Libtest lists ignored tests like the others, so ask for them separately. The tests are
+        // compiled by now, so this only runs the binaries again.
+        let mut ignored_output = execute_command(request.build_cmd_for(true)?, Self::NAME)?;
+        let ignored: HashSet<(String, String)> = take_test_list(&mut ignored_output)
+            .into_iter()
+            .flat_map(|(target, names)| {
+                names
+                    .into_iter()
+                    .map(move |name| (target.target.clone(), name))
+            })
+            .collect();
+
+        let targets = package_targets();
+        let binaries: Vec<ListedBinary> = listed
+            .into_iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(running, names)| {
+                let tests = names
+                    .into_iter()
+                    .map(|name| ListedTest {
+                        ignored: ignored.contains(&(running.target.clone(), name.clone())),
+                        name,
+                    })
+                    .collect();
+                let (package, kind) = match find_target(&targets, &running) {
+                    Some(target) => (Some(target.package.clone()), target.kind.clone()),
+                    None => (None, guess_kind(&running.target).to_owned()),
+                };
+                let kind = if running.target.starts_with("Doc-tests ") {
+                    "doctest".to_owned()
+                } else {
+                    kind
+                };
+                ListedBinary {
+                    package,
+                    target: running.target,
+                    kind,
+                    tests,
+                }
+            })
+            .collect();
+
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(
+                render_test_list(&binaries, "`testname`, `package` or a target selection"),
+                "test list",
+            )
+            .annotate(Annotations::default()),
+        );
+        response.set_structured_content(serde_json::json!({ "binaries": binaries }));
+        Ok(response)
+    }
+}
+
+/// A target of a workspace package, from `cargo metadata`.
+#[derive(Debug)]
+struct PackageTarget {
+    package: String,
+    /// Crate name, with `-` replaced by `_` as in executable names and "Doc-tests" lines
+    crate_name: String,
+    kind: String,
+    src_path: PathBuf,
+}
+
+/// Targets of the workspace packages, empty if `cargo metadata` fails.
+fn package_targets() -> Vec<PackageTarget> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["metadata", "--format-version", "1", "--no-deps"]);
+    apply_workspace_root(&mut cmd);
+    cmd.output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+        .map(|metadata| parse_package_targets(&metadata))
+        .unwrap_or_default()
+}
+
+fn parse_package_targets(metadata: &serde_json::Value) -> Vec<PackageTarget> {
+    let packages = metadata["packages"].as_array().into_iter().flatten();
+    packages
+        .flat_map(|package| {
+            let name = package["name"].as_str().unwrap_or_default();
+            let targets = package["targets"].as_array().into_iter().flatten();
+            targets.map(move |target| PackageTarget {
+                package: name.to_owned(),
+                crate_name: target["name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .replace('-', "_"),
+                kind: match target["kind"][0].as_str().unwrap_or_default() {
+                    "rlib" | "dylib" | "cdylib" | "staticlib" => "lib",
+                    kind => kind,
+                }
+                .to_owned(),
+                src_path: PathBuf::from(target["src_path"].as_str().unwrap_or_default()),
+            })
+        })
+        .collect()
+}
+
+/// Finds the target of a test binary: doc-tests by the crate name, other binaries by the crate
+/// name in the executable, e.g. `target/debug/deps/api-1a2b3c`, and the source path cargo prints,
+/// e.g. `tests/api.rs`. Returns `None` if that matches no target or several.
+fn find_target<'a>(
+    targets: &'a [PackageTarget],
+    running: &RunningTarget,
+) -> Option<&'a PackageTarget> {
+    let mut matches: Vec<&PackageTarget> = match running.target.strip_prefix("Doc-tests ") {
+        Some(crate_name) => targets
+            .iter()
+            .filter(|target| target.crate_name == crate_name)
+            .filter(|target| matches!(target.kind.as_str(), "lib" | "proc-macro"))
+            .collect(),
+        None => {
+            let source = running.target.trim_start_matches("unittests ");
+            let crate_name = running
+                .executable
+                .as_deref()
+                .and_then(|executable| Path::new(executable).file_name()?.to_str())
+                .map(|file_name| {
+                    file_name
+                        .rsplit_once('-')
+                        .map_or(file_name, |(name, _)| name)
+                });
+            targets
+                .iter()
+                .filter(|target| crate_name.is_none_or(|name| target.crate_name == name))
+                .filter(|target| target.src_path.ends_with(source))
+                .collect()
+        }
+    };
+    if matches.len() == 1 {
+        matches.pop()
+    } else {
+        None
+    }
+}
+
+/// Kind of a binary cargo ran when it matches no package target.
+fn guess_kind(target: &str) -> &'static str {
+    match target.strip_prefix("unittests ") {
+        Some(source) if source.ends_with("main.rs") || source.contains("/bin/") => "bin",
+        Some(_) => "lib",
+        None => "test",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_build_cmd() {
+        let tool: CargoTestListRequest = serde_json::from_value(serde_json::json!({
+            "testname": "parse",
+            "package": ["app"],
+            "features": ["serde"],
+        }))
+        .unwrap();
+        assert_eq!(
+            args(&tool.build_cmd().unwrap()),
+            [
+                "test",
+                "parse",
+                "--package",
+                "app",
+                "--features",
+                "serde",
+                "--",
+                "--list"
+            ]
+        );
+        assert_eq!(
+            args(&tool.build_cmd_for(true).unwrap()),
+            [
+                "test",
+                "parse",
+                "--package",
+                "app",
+                "--features",
+                "serde",
+                "--",
+                "--list",
+                "--ignored"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_target() {
+        let metadata = serde_json::json!({
+            "packages": [
+                {
+                    "name": "my-app",
+                    "targets": [
+                        {"name": "my-app", "kind": ["rlib"], "src_path": "/ws/app/src/lib.rs"},
+                        {"name": "my-app", "kind": ["bin"], "src_path": "/ws/app/src/main.rs"},
+                        {"name": "api", "kind": ["test"], "src_path": "/ws/app/tests/api.rs"}
+                    ]
+                },
+                {
+                    "name": "other",
+                    "targets": [
+                        {"name": "api", "kind": ["test"], "src_path": "/ws/other/tests/api.rs"}
+                    ]
+                }
+            ]
+        });
+        let targets = parse_package_targets(&metadata);
+        let running = |target: &str, executable: Option<&str>| RunningTarget {
+            target: target.to_owned(),
+            executable: executable.map(str::to_owned),
+            rerun_args: None,
+        };
+        let find = |target, executable| {
+            find_target(&targets, &running(target, executable))
+                .map(|target| (target.package.as_str(), target.kind.as_str()))
+        };
+
+        assert_eq!(
+            find(
+                "unittests src/lib.rs",
+                Some("target/debug/deps/my_app-1a2b3c")
+            ),
+            Some(("my-app", "lib"))
+        );
+        assert_eq!(
+            find(
+                "unittests src/main.rs",
+                Some("target/debug/deps/my_app-4d5e6f")
+            ),
+            Some(("my-app", "bin"))
+        );
+        assert_eq!(find("Doc-tests my_app", None), Some(("my-app", "lib")));
+        // Both packages have a `tests/api.rs`
+        assert_eq!(
+            find("tests/api.rs", Some("target/debug/deps/api-7a8b9c")),
+            None
+        );
+        assert_eq!(guess_kind("unittests src/main.rs"), "bin");
+        assert_eq!(guess_kind("tests/api.rs"), "test");
+    }
+}
@@ -3,38 +3,44 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::common::{LOCKFILE_LOCKING_MODE_DESC, lockfile_locking_mode_to_cli_flags},
 };
 use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct CargoUpdateRequest {
-    #[schemars(description = "")]
+    /// Packages to update, e.g. `serde` or `serde@1.0.200`. By default, all dependencies are
+    /// updated.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     spec: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Show what would be updated without writing `Cargo.lock`.
     #[serde(default)]
     dry_run: Option<bool>,
-    #[schemars(description = "")]
+    /// Also update the dependencies of the packages in `spec`.
     #[serde(default)]
     recursive: Option<bool>,
-    #[schemars(description = "")]
+    /// Update the single package in `spec` to exactly this version.
     #[serde(default, deserialize_with = "deserialize_string")]
     precise: Option<String>,
-    #[schemars(description = "")]
+    /// Update `spec` to the latest SemVer-incompatible version and rewrite `Cargo.toml`. Unstable,
+    /// requires `unstable_flags: ["unstable-options"]` on nightly.
     #[serde(default)]
     breaking: Option<bool>,
-    #[schemars(description = "")]
+    /// Only update workspace members, e.g. after a version bump in `Cargo.toml`.
     #[serde(default)]
     workspace: Option<bool>,
-    #[schemars(description = "")]
+    /// Ignore the `rust-version` field of the packages when resolving dependencies.
     #[serde(default)]
     ignore_rust_version: Option<bool>,
-    #[schemars(description = "")]
+    /// Override a cargo configuration value, e.g. `net.git-fetch-with-cli=true`.
     #[serde(default, deserialize_with = "deserialize_string")]
     config: Option<String>,
-    #[schemars(description = "")]
+    /// Unstable `-Z` flags, nightly only.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     unstable_flags: Option<Vec<String>>,
+    #[schemars(description = LOCKFILE_LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
 }
 impl CargoUpdateRequest {
     pub fn build_cmd(&self) -> Result<Command, ErrorData> {
@@ -83,6 +89,10 @@ impl CargoUpdateRequest {
             }
         }
 
+        cmd.args(lockfile_locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+        )?);
+
         Ok(cmd)
     }
 }
@@ -100,3 +110,27 @@ impl Tool for CargoUpdateRmcpTool {
         execute_command(cmd, Self::NAME).map(Into::into)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locking_mode_flags() {
+        let args = |request: serde_json::Value| {
+            serde_json::from_value::<CargoUpdateRequest>(request)
+                .unwrap()
+                .build_cmd()
+                .unwrap()
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(args(serde_json::json!({})), ["update"]);
+        assert_eq!(
+            args(serde_json::json!({ "locking_mode": "offline" })),
+            ["update", "--offline"]
+        );
+        assert!(lockfile_locking_mode_to_cli_flags(None).unwrap().is_empty());
+    }
+}
@@ -11,7 +11,7 @@ use serde::Deserialize;
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 #[schemars(title = "CargoWorkspaceInfoRequest")]
 pub struct CargoWorkspaceInfoRequest {
-    #[schemars(description = "")]
+    /// Include the dependencies of each package in the output.
     #[serde(default)]
     include_dependencies: Option<bool>,
 }
@@ -9,68 +9,71 @@ use rmcp::ErrorData;
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 #[schemars(description = "")]
 pub struct CargoDenyCheckRequest {
-    #[schemars(description = "")]
+    /// Checks to run, any of `advisories`, `bans`, `licenses`, `sources`. All checks run by
+    /// default.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     which: Option<Vec<String>>,
+    /// Path to the config file. Defaults to `deny.toml`.
     #[serde(default, deserialize_with = "deserialize_string")]
     config: Option<String>,
-    #[schemars(description = "")]
+    /// Path to a `cargo metadata` JSON file to use instead of running `cargo metadata`.
     #[serde(default, deserialize_with = "deserialize_string")]
     graph: Option<String>,
-    #[schemars(description = "")]
+    /// Hide the inclusion graph that explains why a crate is part of the dependency tree.
     #[serde(default)]
     hide_inclusion_graph: Option<bool>,
-    #[schemars(description = "")]
+    /// Don't fetch the advisory database, use the local copy.
     #[serde(default)]
     disable_fetch: Option<bool>,
-    #[schemars(description = "")]
+    /// Ignore dev-dependencies.
     #[serde(default)]
     exclude_dev: Option<bool>,
-    #[schemars(description = "")]
+    /// Print advisories in the same JSON format as `cargo audit`. Requires `format: "json"`.
     #[serde(default)]
     audit_compatible_output: Option<bool>,
-    #[schemars(description = "")]
+    /// Print a summary of the number of errors, warnings and notes per check.
     #[serde(default)]
     show_stats: Option<bool>,
-    #[schemars(description = "")]
+    /// Lints to report as warnings, overriding the config.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     warn: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Lints to allow, overriding the config.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     allow: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Lints to report as errors, overriding the config.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     deny: Option<Vec<String>>,
 
-    #[schemars(description = "")]
+    /// Depth of the feature edges shown in the inclusion graph.
     feature_depth: Option<u32>,
+    /// Log level, one of: off, error, warn, info, debug, trace.
     #[serde(default, deserialize_with = "deserialize_string")]
     log_level: Option<String>,
-    #[schemars(description = "")]
+    /// Output format, `human` or `json`.
     #[serde(default, deserialize_with = "deserialize_string")]
     format: Option<String>,
-    #[schemars(description = "")]
+    /// Check all packages in the workspace.
     #[serde(default)]
     workspace: Option<bool>,
-    #[schemars(description = "")]
+    /// Use the git index of crates.io instead of the sparse index.
     #[serde(default)]
     allow_git_index: Option<bool>,
-    #[schemars(description = "")]
+    /// Ignore workspace members that have `publish = false`.
     #[serde(default)]
     exclude_unpublished: Option<bool>,
-    #[schemars(description = "")]
+    /// Packages to exclude from the check.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     exclude: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Target triples to check dependencies for, in addition to the targets in the config.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     target: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Activate all available features of the selected packages.
     #[serde(default)]
     all_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Do not activate the `default` feature of the selected packages.
     #[serde(default)]
     no_default_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     features: Option<Vec<String>>,
 }
@@ -205,7 +208,7 @@ impl Tool for CargoDenyCheckRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoDenyInitRequest {
-    #[schemars(description = "")]
+    /// Path of the config file to create. Defaults to `deny.toml`.
     #[serde(default, deserialize_with = "deserialize_string")]
     config: Option<String>,
 }
@@ -238,15 +241,16 @@ impl Tool for CargoDenyInitRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoDenyListRequest {
-    #[schemars(description = "")]
+    /// Path to the config file. Defaults to `deny.toml`.
     #[serde(default, deserialize_with = "deserialize_string")]
     config: Option<String>,
 
-    #[schemars(description = "")]
+    /// Minimum confidence of the license detection, from 0.0 to 1.0.
     threshold: Option<f64>,
+    /// Output format, one of: human, json, tsv.
     #[serde(default, deserialize_with = "deserialize_string")]
     format: Option<String>,
-    #[schemars(description = "")]
+    /// Group the output by `license` or by `crate`.
     #[serde(default, deserialize_with = "deserialize_string")]
     layout: Option<String>,
 }
@@ -3,6 +3,7 @@ use std::process::Command;
 use crate::{
     Tool, execute_command,
     serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::common::{LOCKING_MODE_DESC, locking_mode_to_cli_flags},
 };
 use rmcp::ErrorData;
 
@@ -13,97 +14,108 @@ fn default_check() -> String {
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 #[schemars(description = "")]
 pub struct CargoHackRequest {
-    #[schemars(description = "")]
+    /// Cargo command to run for each combination. One of: check, test, build, clippy. Defaults to
+    /// check.
     #[serde(default = "default_check")]
     command: String,
+    /// Packages to run for. By default, the package in the current directory is selected.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     package: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Run for all packages in the workspace.
     #[serde(default)]
     workspace: Option<bool>,
-    #[schemars(description = "")]
+    /// Packages to exclude. Only used together with `workspace`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     exclude: Option<Vec<String>>,
-    #[schemars(description = "")]
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+    /// Deprecated, use `locking_mode` instead. `true` is the same as `locked`, `false` the same as
+    /// `unlocked`.
     #[serde(default)]
     locked: Option<bool>,
-    #[schemars(description = "")]
+    /// Features to always activate.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Run once for each feature, plus once with no features and once with all features.
     #[serde(default)]
     each_feature: Option<bool>,
-    #[schemars(description = "")]
+    /// Run once for each combination of features. The number of runs grows exponentially, so use
+    /// `depth` or `group_features` for crates with many features.
     #[serde(default)]
     feature_powerset: Option<bool>,
-    #[schemars(description = "")]
+    /// Treat optional dependencies as features. An empty list includes all of them.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     optional_deps: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Features to leave out of `each_feature` and `feature_powerset`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     exclude_features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Skip the run with `--no-default-features`.
     #[serde(default)]
     exclude_no_default_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Skip the run with `--all-features`.
     #[serde(default)]
     exclude_all_features: Option<bool>,
 
-    #[schemars(description = "")]
+    /// Maximum number of features combined in one `feature_powerset` run.
     depth: Option<u32>,
+    /// Features that are always activated together in `feature_powerset`, as a single group.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     group_features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Target triples to run for.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     target: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Features that must not be combined with each other in `feature_powerset`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     mutually_exclusive_features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Only use these features in `each_feature` and `feature_powerset`.
     #[serde(default, deserialize_with = "deserialize_string_vec")]
     include_features: Option<Vec<String>>,
-    #[schemars(description = "")]
+    /// Temporarily remove dev-dependencies, useful to check that the crate builds without them.
+    /// `Cargo.toml` is restored afterwards.
     #[serde(default)]
     no_dev_deps: Option<bool>,
-    #[schemars(description = "")]
+    /// Remove dev-dependencies from `Cargo.toml` and keep them removed.
     #[serde(default)]
     remove_dev_deps: Option<bool>,
-    #[schemars(description = "")]
+    /// Skip packages that have `publish = false`.
     #[serde(default)]
     no_private: Option<bool>,
-    #[schemars(description = "")]
+    /// Skip packages that have `publish = false`, but still list them in the output.
     #[serde(default)]
     ignore_private: Option<bool>,
-    #[schemars(description = "")]
+    /// Skip packages that don't have the requested features instead of failing.
     #[serde(default)]
     ignore_unknown_features: Option<bool>,
-    #[schemars(description = "")]
+    /// Run with the toolchain matching the `rust-version` field of each package.
     #[serde(default)]
     rust_version: Option<bool>,
-    #[schemars(description = "")]
+    /// Range of Rust versions to run with, e.g. `1.70..=1.80` or `..` for all versions since
+    /// `rust-version`.
     #[serde(default, deserialize_with = "deserialize_string")]
     version_range: Option<String>,
 
-    #[schemars(description = "")]
+    /// Step between versions in `version_range`.
     version_step: Option<u32>,
+    /// Run `cargo clean` before each run.
     #[serde(default)]
     clean_per_run: Option<bool>,
-    #[schemars(description = "")]
+    /// Run `cargo clean` before each Rust version.
     #[serde(default)]
     clean_per_version: Option<bool>,
-    #[schemars(description = "")]
+    /// Continue with the remaining combinations after a failure.
     #[serde(default)]
     keep_going: Option<bool>,
-    #[schemars(description = "")]
+    /// Run only a part of the combinations, e.g. `1/2`. Useful to split the work across CI jobs.
     #[serde(default, deserialize_with = "deserialize_string")]
     partition: Option<String>,
-    #[schemars(description = "")]
+    /// Group the log output, `none` or `github-actions`.
     #[serde(default, deserialize_with = "deserialize_string")]
     log_group: Option<String>,
-    #[schemars(description = "")]
+    /// Print the commands that would run without running them.
     #[serde(default)]
     print_command_list: Option<bool>,
-    #[schemars(description = "")]
+    /// Don't pass `--manifest-path` to the cargo command.
     #[serde(default)]
     no_manifest_path: Option<bool>,
 }
@@ -141,9 +153,23 @@ impl CargoHackRequest {
             }
         }
 
-        if self.locked.unwrap_or(true) {
-            cmd.arg("--locked");
-        }
+        let legacy_mode = self
+            .locked
+            .map(|locked| if locked { "locked" } else { "unlocked" });
+        if let (Some(mode), Some(legacy)) = (self.locking_mode.as_deref(), legacy_mode)
+            && mode != legacy
+        {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "`locked` conflicts with `locking_mode: \"{mode}\"`; use only `locking_mode`"
+                ),
+                None,
+            ));
+        }
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref().or(legacy_mode),
+            "locked",
+        )?);
 
         // Feature selection
         if let Some(features) = &self.features {
@@ -314,3 +340,35 @@ impl Tool for CargoHackInstallRmcpTool {
         execute_command(request.build_cmd()?, Self::NAME).map(Into::into)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deprecated_locked_flag() {
+        let args = |request: serde_json::Value| {
+            serde_json::from_value::<CargoHackRequest>(request)
+                .unwrap()
+                .build_cmd()
+                .map(|cmd| {
+                    cmd.get_args()
+                        .map(|arg| arg.to_string_lossy().into_owned())
+                        .collect::<Vec<_>>()
+                })
+        };
+        assert_eq!(
+            args(serde_json::json!({ "locked": true })).unwrap(),
+            ["hack", "--locked", "check"]
+        );
+        assert_eq!(
+            args(serde_json::json!({ "locked": false })).unwrap(),
+            ["hack", "check"]
+        );
+        assert_eq!(
+            args(serde_json::json!({ "locked": true, "locking_mode": "locked" })).unwrap(),
+            ["hack", "--locked", "check"]
+        );
+        assert!(args(serde_json::json!({ "locked": true, "locking_mode": "offline" })).is_err());
+    }
+}
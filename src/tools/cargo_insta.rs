@@ -0,0 +1,751 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response, Tool,
+    command::text_with_description,
+    diff::unified_diff,
+    execute_command,
+    serde_utils::deserialize_string_vec,
+    tools::{cargo::CargoTestRmcpTool, common::reject_conflict, workspace_root},
+};
+
+/// Total size of the diffs returned by one `cargo-insta-pending` call.
+const DIFFS_BUDGET: usize = 20_000;
+
+/// A snapshot that a test run recorded but that has not been accepted or rejected yet.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct PendingSnapshot {
+    /// Id to accept or reject it by: the `.snap.new` file relative to the workspace root, or
+    /// `file:line` of the assertion for inline snapshots
+    id: String,
+    /// `file` or `inline`
+    kind: &'static str,
+    /// Whether it replaces an accepted snapshot, otherwise it is the first one of the assertion
+    changed: bool,
+    /// Source file of the assertion, from the snapshot metadata
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expression: Option<String>,
+    /// Unified diff from the accepted snapshot, absent once the diffs exceed the size limit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+    #[serde(skip)]
+    location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Location {
+    /// A `.snap.new` file next to the `.snap` file it replaces
+    File(PathBuf),
+    /// An entry of the `.pending-snap` file that insta writes next to the source file
+    Inline {
+        pending_file: PathBuf,
+        source_file: PathBuf,
+        line: u64,
+    },
+}
+
+/// Absolute workspace root, the directory searched for pending snapshots.
+fn search_root() -> PathBuf {
+    std::path::absolute(workspace_root())
+        .or_else(|_| std::env::current_dir())
+        .unwrap_or_default()
+}
+
+/// Finds the pending snapshots under `root`, skipping `target` and hidden directories.
+fn find_pending(root: &Path) -> Vec<PendingSnapshot> {
+    let mut files = Vec::new();
+    collect_pending_files(root, &mut files);
+    files.sort();
+
+    let mut pending = Vec::new();
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        if file.to_string_lossy().ends_with(".snap.new") {
+            let accepted = fs::read_to_string(file.with_extension("")).ok();
+            pending.push(file_snapshot(root, file, &content, accepted.as_deref()));
+        } else {
+            pending.extend(inline_snapshots(root, &file, &content));
+        }
+    }
+    pending
+}
+
+fn collect_pending_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                collect_pending_files(&path, files);
+            }
+        } else if name.ends_with(".snap.new") || name.ends_with(".pending-snap") {
+            files.push(path);
+        }
+    }
+}
+
+fn file_snapshot(
+    root: &Path,
+    path: PathBuf,
+    content: &str,
+    accepted: Option<&str>,
+) -> PendingSnapshot {
+    let (header, body) = split_snapshot(content);
+    let accepted_body = accepted.map_or("", |accepted| split_snapshot(accepted).1);
+    let id = relative(root, &path);
+    PendingSnapshot {
+        diff: Some(unified_diff(
+            id.trim_end_matches(".new"),
+            accepted_body,
+            body,
+        )),
+        id,
+        kind: "file",
+        changed: accepted.is_some(),
+        source: header_value(header, "source"),
+        expression: header_value(header, "expression"),
+        location: Location::File(path),
+    }
+}
+
+/// Splits a snapshot file into its metadata header and the snapshot itself.
+fn split_snapshot(content: &str) -> (&str, &str) {
+    content
+        .strip_prefix("---\n")
+        .and_then(|rest| rest.split_once("\n---\n"))
+        .unwrap_or(("", content))
+}
+
+/// Content of the `.snap` file that accepting a `.snap.new` file writes. Like `cargo insta
+/// accept`, this drops `assertion_line` from the header, as the line is only kept while pending.
+fn accepted_content(content: &str) -> String {
+    let (header, body) = split_snapshot(content);
+    if header.is_empty() {
+        return content.to_owned();
+    }
+    let header: Vec<&str> = header
+        .lines()
+        .filter(|line| !line.starts_with("assertion_line:"))
+        .collect();
+    format!("---\n{}\n---\n{body}", header.join("\n"))
+}
+
+/// A single-line value of the metadata header, e.g. `source: src/lib.rs`.
+fn header_value(header: &str, key: &str) -> Option<String> {
+    header.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?.trim();
+        (!value.is_empty()).then(|| value.to_owned())
+    })
+}
+
+/// Parses a `.pending-snap` file, one JSON entry per line. A later entry for the same line
+/// replaces the earlier ones, and an entry without a new snapshot means the assertion passed.
+fn inline_snapshots(root: &Path, pending_file: &Path, content: &str) -> Vec<PendingSnapshot> {
+    let Some(source_file) = inline_source_file(pending_file) else {
+        return Vec::new();
+    };
+    let mut entries: BTreeMap<u64, serde_json::Value> = BTreeMap::new();
+    for entry in content.lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(entry) else {
+            continue;
+        };
+        let Some(line) = entry["line"].as_u64() else {
+            continue;
+        };
+        if entry["new"].is_null() {
+            entries.remove(&line);
+        } else {
+            entries.insert(line, entry);
+        }
+    }
+
+    let source = relative(root, &source_file);
+    entries
+        .into_iter()
+        .map(|(line, entry)| {
+            let id = format!("{source}:{line}");
+            let new = entry["new"]["snapshot"].as_str().unwrap_or_default();
+            let old = entry["old"]["snapshot"].as_str();
+            let metadata = &entry["new"]["metadata"];
+            PendingSnapshot {
+                diff: Some(unified_diff(&id, old.unwrap_or_default(), new)),
+                changed: old.is_some(),
+                id,
+                kind: "inline",
+                source: Some(source.clone()),
+                expression: metadata["expression"].as_str().map(str::to_owned),
+                location: Location::Inline {
+                    pending_file: pending_file.to_owned(),
+                    source_file: source_file.clone(),
+                    line,
+                },
+            }
+        })
+        .collect()
+}
+
+/// `src/lib.rs` for `src/.lib.rs.pending-snap`.
+fn inline_source_file(pending_file: &Path) -> Option<PathBuf> {
+    let name = pending_file.file_name()?.to_str()?;
+    let source = name.strip_prefix('.')?.strip_suffix(".pending-snap")?;
+    Some(pending_file.with_file_name(source))
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn render_pending(pending: &[PendingSnapshot]) -> String {
+    if pending.is_empty() {
+        return "No pending snapshots".to_owned();
+    }
+    let mut output = format!("{} pending snapshot(s)", pending.len());
+    for snapshot in pending {
+        let status = if snapshot.changed { "changed" } else { "new" };
+        let _ = write!(
+            output,
+            "\n\n{} ({} snapshot, {status})",
+            snapshot.id, snapshot.kind
+        );
+        if let Some(expression) = &snapshot.expression {
+            let _ = write!(output, " of `{expression}`");
+        }
+        match snapshot.diff.as_deref() {
+            Some("") => output.push_str("\nonly the metadata changed"),
+            Some(diff) => {
+                output.push('\n');
+                output.push_str(diff.trim_end());
+            }
+            None => output.push_str("\ndiff omitted, narrow the list with `snapshots`"),
+        }
+    }
+    output
+}
+
+/// Drops the diffs that don't fit in [`DIFFS_BUDGET`].
+fn limit_diffs(pending: &mut [PendingSnapshot]) {
+    let mut budget = DIFFS_BUDGET;
+    for snapshot in pending {
+        let size = snapshot.diff.as_ref().map_or(0, String::len);
+        if size > budget {
+            snapshot.diff = None;
+            budget = 0;
+        } else {
+            budget -= size;
+        }
+    }
+}
+
+/// Which pending snapshots to act on.
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct SnapshotSelection {
+    /// Ids of the snapshots, as reported by `cargo-insta-pending`: a `.snap.new` or `.snap` path
+    /// relative to the workspace root, or `file:line` of an inline snapshot.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    snapshots: Option<Vec<String>>,
+
+    /// Act on all pending snapshots in the workspace.
+    #[serde(default)]
+    all: Option<bool>,
+}
+
+impl SnapshotSelection {
+    fn validate(&self) -> Result<(), ErrorData> {
+        let listed = self.snapshots.as_ref().is_some_and(|s| !s.is_empty());
+        let all = self.all.unwrap_or(false);
+        reject_conflict(
+            ("all", all),
+            ("snapshots", listed),
+            "Set `all: true` to act on every pending snapshot, or list the ones to act on in `snapshots`",
+        )?;
+        if !all && !listed {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "No snapshots selected. List the ids reported by #{} in `snapshots`, or set `all: true`",
+                    CargoInstaPendingRmcpTool::NAME
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Picks the selected snapshots, or fails with the ids that are not pending.
+    fn select(
+        &self,
+        root: &Path,
+        pending: Vec<PendingSnapshot>,
+    ) -> Result<Vec<PendingSnapshot>, ErrorData> {
+        if self.all.unwrap_or(false) {
+            return Ok(pending);
+        }
+        let requested: Vec<String> = self
+            .snapshots
+            .iter()
+            .flatten()
+            .map(|id| normalize_id(root, id))
+            .collect();
+        let matches = |snapshot: &PendingSnapshot, id: &str| {
+            snapshot.id == id || snapshot.id.strip_suffix(".new") == Some(id)
+        };
+        let unknown: Vec<&str> = requested
+            .iter()
+            .filter(|id| !pending.iter().any(|snapshot| matches(snapshot, id)))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "No pending snapshot {}. Get the ids of the pending snapshots with #{}",
+                    unknown.join(", "),
+                    CargoInstaPendingRmcpTool::NAME
+                ),
+                None,
+            ));
+        }
+        Ok(pending
+            .into_iter()
+            .filter(|snapshot| requested.iter().any(|id| matches(snapshot, id)))
+            .collect())
+    }
+}
+
+/// Makes a snapshot id relative to the workspace root, with `/` separators.
+fn normalize_id(root: &Path, id: &str) -> String {
+    let id = id.trim().replace('\\', "/");
+    let id = Path::new(&id)
+        .strip_prefix(root)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or(id.clone());
+    id.trim_start_matches("./").to_owned()
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoInstaPendingRequest {
+    /// Only show these snapshots, by id, `.snap.new` or `.snap` path.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    snapshots: Option<Vec<String>>,
+}
+
+pub struct CargoInstaPendingRmcpTool;
+
+impl Tool for CargoInstaPendingRmcpTool {
+    const NAME: &'static str = "cargo-insta-pending";
+    const TITLE: &'static str = "List pending insta snapshots";
+    const DESCRIPTION: &'static str = "Lists the insta snapshots that tests recorded but that were not accepted yet, `.snap.new` files and pending inline snapshots, with a diff from the accepted snapshot.";
+    type RequestArgs = CargoInstaPendingRequest;
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let root = search_root();
+        let mut pending = find_pending(&root);
+        if request.snapshots.as_ref().is_some_and(|s| !s.is_empty()) {
+            let selection = SnapshotSelection {
+                snapshots: request.snapshots,
+                all: None,
+            };
+            pending = selection.select(&root, pending)?;
+        }
+        limit_diffs(&mut pending);
+
+        let mut response = Response::default();
+        response.add_content(
+            text_with_description(render_pending(&pending), "pending snapshots")
+                .annotate(Annotations::default()),
+        );
+        if !pending.is_empty() {
+            response.add_recommendation(format!(
+                "Accept the expected changes with #{} and reject the others with #{}, passing their ids in `snapshots`",
+                CargoInstaAcceptRmcpTool::NAME,
+                CargoInstaRejectRmcpTool::NAME
+            ));
+        }
+        response.set_structured_content(serde_json::json!({ "snapshots": pending }));
+        Ok(response)
+    }
+}
+
+/// A selected snapshot that could not be accepted or rejected.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct SkippedSnapshot {
+    id: String,
+    reason: String,
+}
+
+/// Accepts or rejects the selected snapshots: file snapshots directly, inline snapshots by
+/// editing the `.pending-snap` files, or with `cargo insta accept`, which rewrites the source.
+fn review(
+    selection: SnapshotSelection,
+    accept: bool,
+    tool_name: &str,
+) -> Result<Response, ErrorData> {
+    let root = search_root();
+    let selected = selection.select(&root, find_pending(&root))?;
+
+    let mut done = Vec::new();
+    let mut skipped = Vec::new();
+    let mut inline_accepts = Vec::new();
+    let mut inline_rejects: BTreeMap<PathBuf, HashSet<u64>> = BTreeMap::new();
+    for snapshot in selected {
+        match &snapshot.location {
+            Location::File(path) => {
+                let result = if accept {
+                    fs::read_to_string(path)
+                        .and_then(|content| {
+                            fs::write(path.with_extension(""), accepted_content(&content))
+                        })
+                        .and_then(|()| fs::remove_file(path))
+                } else {
+                    fs::remove_file(path)
+                };
+                match result {
+                    Ok(()) => done.push(snapshot.id),
+                    Err(e) => skipped.push(SkippedSnapshot {
+                        id: snapshot.id,
+                        reason: e.to_string(),
+                    }),
+                }
+            }
+            Location::Inline {
+                pending_file, line, ..
+            } if !accept => {
+                inline_rejects
+                    .entry(pending_file.clone())
+                    .or_default()
+                    .insert(*line);
+                done.push(snapshot.id);
+            }
+            Location::Inline { .. } => inline_accepts.push(snapshot),
+        }
+    }
+
+    for (pending_file, lines) in inline_rejects {
+        if let Err(e) = remove_inline_entries(&pending_file, &lines) {
+            return Err(ErrorData::internal_error(
+                format!("Failed to update {}: {e}", pending_file.display()),
+                None,
+            ));
+        }
+    }
+
+    let mut response = Response::default();
+    let mut not_installed = false;
+    if !inline_accepts.is_empty() {
+        let output = execute_command(accept_inline_cmd(&inline_accepts), tool_name)?;
+        not_installed = output
+            .stderr
+            .as_ref()
+            .is_some_and(|stderr| stderr.0.contains("no such command: `insta`"));
+        // `cargo insta` reports what it did in its own words, so check what is still pending
+        let still_pending: HashSet<String> = find_pending(&root)
+            .into_iter()
+            .map(|snapshot| snapshot.id)
+            .collect();
+        for snapshot in inline_accepts {
+            if still_pending.contains(&snapshot.id) {
+                skipped.push(SkippedSnapshot {
+                    id: snapshot.id,
+                    reason: if not_installed {
+                        "accepting inline snapshots needs `cargo insta`, which is not installed"
+                            .to_owned()
+                    } else {
+                        "`cargo insta accept` did not accept it".to_owned()
+                    },
+                });
+            } else {
+                done.push(snapshot.id);
+            }
+        }
+        response = output.into();
+    }
+
+    let verb = if accept { "Accepted" } else { "Rejected" };
+    let mut summary = format!("{verb} {} snapshot(s)", done.len());
+    for id in &done {
+        let _ = write!(summary, "\n- {id}");
+    }
+    if !skipped.is_empty() {
+        let _ = write!(summary, "\nSkipped {}", skipped.len());
+        for skipped in &skipped {
+            let _ = write!(summary, "\n- {}: {}", skipped.id, skipped.reason);
+        }
+    }
+    response.add_content(
+        text_with_description(summary, "reviewed snapshots").annotate(Annotations::default()),
+    );
+    if not_installed {
+        response.add_recommendation(format!(
+            "Install cargo-insta with #{}",
+            CargoInstaInstallRmcpTool::NAME
+        ));
+    }
+    if accept && !done.is_empty() {
+        response.add_recommendation(format!(
+            "Run #{} again to check that the accepted snapshots pass",
+            CargoTestRmcpTool::NAME
+        ));
+    }
+    let key = if accept { "accepted" } else { "rejected" };
+    response.set_structured_content(serde_json::json!({ key: done, "skipped": skipped }));
+    Ok(response)
+}
+
+/// `cargo insta accept` limited to the given inline snapshots, which it selects by the absolute
+/// path of the source file and the line of the assertion.
+fn accept_inline_cmd(snapshots: &[PendingSnapshot]) -> Command {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("insta").arg("accept").arg("--workspace");
+    for snapshot in snapshots {
+        if let Location::Inline {
+            source_file, line, ..
+        } = &snapshot.location
+        {
+            cmd.arg("--snapshot")
+                .arg(format!("{}:{line}", source_file.display()));
+        }
+    }
+    cmd
+}
+
+/// Drops the entries of the given lines from a `.pending-snap` file, and the file once it is
+/// empty.
+fn remove_inline_entries(pending_file: &Path, lines: &HashSet<u64>) -> std::io::Result<()> {
+    let content = fs::read_to_string(pending_file)?;
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|entry| {
+            let line = serde_json::from_str::<serde_json::Value>(entry)
+                .ok()
+                .and_then(|entry| entry["line"].as_u64());
+            line.is_none_or(|line| !lines.contains(&line))
+        })
+        .collect();
+    if kept.iter().all(|entry| entry.trim().is_empty()) {
+        fs::remove_file(pending_file)
+    } else {
+        fs::write(pending_file, kept.join("\n") + "\n")
+    }
+}
+
+pub struct CargoInstaAcceptRmcpTool;
+
+impl Tool for CargoInstaAcceptRmcpTool {
+    const NAME: &'static str = "cargo-insta-accept";
+    const TITLE: &'static str = "Accept insta snapshots";
+    const DESCRIPTION: &'static str = "Accepts pending insta snapshots by id, or all of them. File snapshots replace the accepted `.snap` files, inline snapshots are written into the source with `cargo insta`.";
+    type RequestArgs = SnapshotSelection;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.validate()
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        review(request, true, Self::NAME)
+    }
+}
+
+pub struct CargoInstaRejectRmcpTool;
+
+impl Tool for CargoInstaRejectRmcpTool {
+    const NAME: &'static str = "cargo-insta-reject";
+    const TITLE: &'static str = "Reject insta snapshots";
+    const DESCRIPTION: &'static str =
+        "Rejects pending insta snapshots by id, or all of them, keeping the accepted snapshots.";
+    type RequestArgs = SnapshotSelection;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.validate()
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        review(request, false, Self::NAME)
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoInstaInstallRequest {}
+
+impl CargoInstaInstallRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("install").arg("cargo-insta").arg("--locked");
+
+        Ok(cmd)
+    }
+}
+
+pub struct CargoInstaInstallRmcpTool;
+
+impl Tool for CargoInstaInstallRmcpTool {
+    const NAME: &'static str = "cargo-insta-install";
+    const TITLE: &'static str = "Install cargo-insta";
+    const DESCRIPTION: &'static str =
+        "Installs cargo-insta, needed to accept inline insta snapshots";
+    type RequestArgs = CargoInstaInstallRequest;
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        execute_command(request.build_cmd()?, Self::NAME).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCEPTED: &str =
+        "---\nsource: src/lib.rs\nexpression: render(&user)\n---\nname: Ann\nage: 30\n";
+    const NEW: &str = "---\nsource: src/lib.rs\nassertion_line: 12\nexpression: render(&user)\n---\nname: Ann\nage: 31\n";
+
+    #[test]
+    fn test_file_snapshot() {
+        let root = Path::new("/ws");
+        let path = PathBuf::from("/ws/src/snapshots/app__render.snap.new");
+        let snapshot = file_snapshot(root, path.clone(), NEW, Some(ACCEPTED));
+        assert_eq!(snapshot.id, "src/snapshots/app__render.snap.new");
+        assert!(snapshot.changed);
+        assert_eq!(snapshot.source.as_deref(), Some("src/lib.rs"));
+        assert_eq!(snapshot.expression.as_deref(), Some("render(&user)"));
+        assert_eq!(
+            snapshot.diff.as_deref(),
+            Some(
+                "--- a/src/snapshots/app__render.snap\n\
+                 +++ b/src/snapshots/app__render.snap\n\
+                 @@ -1,2 +1,2 @@\n\
+                 \x20name: Ann\n\
+                 -age: 30\n\
+                 +age: 31\n"
+            )
+        );
+
+        let first = file_snapshot(root, path, NEW, None);
+        assert!(!first.changed);
+        assert!(first.diff.unwrap().contains("@@ -0,0 +1,2 @@"));
+
+        assert_eq!(
+            accepted_content(NEW),
+            "---\nsource: src/lib.rs\nexpression: render(&user)\n---\nname: Ann\nage: 31\n"
+        );
+        assert_eq!(accepted_content("no header\n"), "no header\n");
+    }
+
+    #[test]
+    fn test_inline_snapshots() {
+        let content = [
+            r#"{"run_id":"1","line":12,"new":{"module_name":"app","snapshot_name":null,"metadata":{"source":"src/lib.rs","expression":"x"},"snapshot":"2"},"old":{"module_name":"app","snapshot_name":null,"metadata":{},"snapshot":"1"}}"#,
+            r#"{"run_id":"1","line":20,"new":{"module_name":"app","snapshot_name":null,"metadata":{},"snapshot":"a"},"old":null}"#,
+            r#"{"run_id":"2","line":20,"new":null,"old":null}"#,
+        ]
+        .join("\n");
+        let pending_file = Path::new("/ws/src/.lib.rs.pending-snap");
+        let snapshots = inline_snapshots(Path::new("/ws"), pending_file, &content);
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].id, "src/lib.rs:12");
+        assert!(snapshots[0].changed);
+        assert_eq!(snapshots[0].expression.as_deref(), Some("x"));
+        assert_eq!(
+            snapshots[0].location,
+            Location::Inline {
+                pending_file: pending_file.to_owned(),
+                source_file: PathBuf::from("/ws/src/lib.rs"),
+                line: 12,
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_and_select() {
+        let root = std::env::temp_dir().join(format!("insta-review-{}", std::process::id()));
+        let snapshots_dir = root.join("src/snapshots");
+        fs::create_dir_all(&snapshots_dir).unwrap();
+        fs::create_dir_all(root.join("target/snapshots")).unwrap();
+        fs::write(snapshots_dir.join("app__a.snap"), ACCEPTED).unwrap();
+        fs::write(snapshots_dir.join("app__a.snap.new"), NEW).unwrap();
+        fs::write(snapshots_dir.join("app__b.snap.new"), NEW).unwrap();
+        fs::write(root.join("target/snapshots/dep__c.snap.new"), NEW).unwrap();
+
+        let pending = find_pending(&root);
+        let ids: Vec<&str> = pending.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "src/snapshots/app__a.snap.new",
+                "src/snapshots/app__b.snap.new"
+            ]
+        );
+
+        let selection = |ids: &[&str]| SnapshotSelection {
+            snapshots: Some(ids.iter().map(|id| id.to_string()).collect()),
+            all: None,
+        };
+        let absolute = snapshots_dir.join("app__b.snap");
+        let selected = selection(&[absolute.to_str().unwrap()])
+            .select(&root, pending.clone())
+            .unwrap();
+        assert_eq!(selected[0].id, "src/snapshots/app__b.snap.new");
+        let err = selection(&["src/snapshots/app__d.snap"])
+            .select(&root, pending)
+            .unwrap_err();
+        assert!(err.message.contains("app__d.snap"), "{err:?}");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_validate_selection() {
+        let selection = |snapshots: Option<Vec<String>>, all| SnapshotSelection { snapshots, all };
+        assert!(selection(None, Some(true)).validate().is_ok());
+        assert!(
+            selection(Some(vec!["a.snap".into()]), None)
+                .validate()
+                .is_ok()
+        );
+        assert!(selection(None, None).validate().is_err());
+        assert!(
+            selection(Some(vec!["a.snap".into()]), Some(true))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_remove_inline_entries() {
+        let pending_file =
+            std::env::temp_dir().join(format!(".lib.rs.{}.pending-snap", std::process::id()));
+        fs::write(
+            &pending_file,
+            "{\"line\":3,\"new\":{\"snapshot\":\"a\"}}\n{\"line\":9,\"new\":{\"snapshot\":\"b\"}}\n",
+        )
+        .unwrap();
+        remove_inline_entries(&pending_file, &HashSet::from([3])).unwrap();
+        assert_eq!(
+            fs::read_to_string(&pending_file).unwrap(),
+            "{\"line\":9,\"new\":{\"snapshot\":\"b\"}}\n"
+        );
+        remove_inline_entries(&pending_file, &HashSet::from([9])).unwrap();
+        assert!(!pending_file.exists());
+    }
+}
@@ -0,0 +1,655 @@
+use std::{
+    fmt::Write,
+    path::Path,
+    process::Command,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response, Tool,
+    command::{Output, text_with_description},
+    diagnostics::filter_cargo_status,
+    execute_command,
+    serde_utils::{deserialize_string, deserialize_string_vec},
+    tools::{
+        cargo::{CargoTestRmcpTool, TestSelection},
+        common::{LOCKING_MODE_DESC, cargo_command, locking_mode_to_cli_flags},
+        workspace_root,
+    },
+};
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoLlvmCovRequest {
+    /// Rust toolchain to run cargo with, e.g. `nightly` or `1.90`. Must be installed, equivalent to
+    /// `cargo +<toolchain>`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    toolchain: Option<String>,
+
+    /// Only run tests whose name contains this string.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    testname: Option<String>,
+
+    /// Arguments passed to the test binaries after `--`, e.g. `--test-threads=1`.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string_vec"
+    )]
+    test_args: Option<Vec<String>>,
+
+    /// Also return the uncovered line ranges of this file, e.g. `src/parser.rs`, relative to the
+    /// workspace root.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    file: Option<String>,
+
+    #[serde(flatten)]
+    selection: TestSelection,
+
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+impl CargoLlvmCovRequest {
+    /// Builds the command that runs the tests and writes the JSON report to `report_path`. The
+    /// report only has the line numbers of regions when `file` is set.
+    pub fn build_cmd(&self, report_path: &Path) -> Result<Command, ErrorData> {
+        let mut cmd = cargo_command(self.toolchain.as_deref())?;
+        cmd.arg("llvm-cov");
+        if let Some(testname) = &self.testname {
+            cmd.arg(testname);
+        }
+        self.selection.add_package_and_target_args(&mut cmd);
+        self.selection.add_feature_args(&mut cmd);
+
+        cmd.arg("--json").arg("--output-path").arg(report_path);
+        if self.file.is_none() {
+            cmd.arg("--summary-only");
+        }
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        if let Some(test_args) = self.test_args.as_ref().filter(|args| !args.is_empty()) {
+            cmd.arg("--").args(test_args);
+        }
+        Ok(cmd)
+    }
+}
+
+/// Covered and total number of lines, regions or functions.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Summary {
+    count: u64,
+    covered: u64,
+    percent: f64,
+}
+
+impl Summary {
+    fn render(&self) -> String {
+        format!("{:.1}% ({}/{})", self.percent, self.covered, self.count)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct CoverageSummary {
+    lines: Summary,
+    regions: Summary,
+    functions: Summary,
+}
+
+impl CoverageSummary {
+    fn render(&self) -> String {
+        format!(
+            "lines {}, regions {}, functions {}",
+            self.lines.render(),
+            self.regions.render(),
+            self.functions.render()
+        )
+    }
+}
+
+/// A segment of llvm-cov's JSON export: `[line, column, count, has_count, is_region_entry,
+/// is_gap_region]`. A region starts or continues at each segment until the next one.
+type Segment = (u32, u32, u64, bool, bool, bool);
+
+/// A file of llvm-cov's JSON export.
+#[derive(Debug, serde::Deserialize)]
+struct ExportedFile {
+    filename: String,
+    summary: CoverageSummary,
+    /// Sorted by position, only exported without `--summary-only`
+    #[serde(default)]
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct ExportedData {
+    files: Vec<ExportedFile>,
+    totals: CoverageSummary,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Export {
+    data: Vec<ExportedData>,
+}
+
+/// Coverage of one file, relative to the workspace root.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct FileCoverage {
+    file: String,
+    #[serde(flatten)]
+    summary: CoverageSummary,
+}
+
+/// Lines from `start` to `end`, both included.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+struct LineRange {
+    start: u32,
+    end: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+struct UncoveredLines {
+    file: String,
+    ranges: Vec<LineRange>,
+}
+
+/// Coverage of a test run, returned as `structured_content`.
+#[derive(Debug, serde::Serialize)]
+struct CoverageReport {
+    files: Vec<FileCoverage>,
+    totals: CoverageSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uncovered: Option<UncoveredLines>,
+}
+
+impl CoverageReport {
+    fn render(&self) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            let _ = writeln!(text, "{}: {}", file.file, file.summary.render());
+        }
+        let _ = write!(text, "Total: {}", self.totals.render());
+        text
+    }
+}
+
+/// Path of a file of the report relative to `root`, or as it is if it is outside.
+fn relative_path(filename: &str, root: &Path) -> String {
+    Path::new(filename)
+        .strip_prefix(root)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| filename.to_owned())
+}
+
+/// Lines that have code that never ran, following how llvm-cov counts lines: a line counts if a
+/// region starts on it or a region from an earlier line continues over it, and it is uncovered
+/// if none of those regions ran.
+fn uncovered_lines(segments: &[Segment]) -> Vec<u32> {
+    let mut uncovered = Vec::new();
+    let (Some(first), Some(last)) = (segments.first(), segments.last()) else {
+        return uncovered;
+    };
+    let mut wrapped: Option<&Segment> = None;
+    let mut next = 0;
+    for line in first.0..=last.0 {
+        let start = next;
+        while next < segments.len() && segments[next].0 == line {
+            next += 1;
+        }
+        let line_segments = &segments[start..next];
+
+        let is_region_start =
+            |&&(_, _, _, has_count, is_entry, is_gap): &&Segment| has_count && is_entry && !is_gap;
+        let starts_skipped = line_segments
+            .first()
+            .is_some_and(|&(_, _, _, has_count, is_entry, _)| !has_count && is_entry);
+        let mut regions = line_segments.iter().filter(is_region_start).peekable();
+        let wrapped_count = wrapped.filter(|segment| segment.3).map(|segment| segment.2);
+        let mapped = !starts_skipped && (wrapped_count.is_some() || regions.peek().is_some());
+        if mapped {
+            let count = regions
+                .map(|segment| segment.2)
+                .chain(wrapped_count)
+                .max()
+                .unwrap_or(0);
+            if count == 0 {
+                uncovered.push(line);
+            }
+        }
+
+        if let Some(segment) = line_segments.last() {
+            wrapped = Some(segment);
+        }
+    }
+    uncovered
+}
+
+/// Joins consecutive lines into ranges.
+fn line_ranges(lines: &[u32]) -> Vec<LineRange> {
+    let mut ranges: Vec<LineRange> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some(range) if range.end + 1 == line => range.end = line,
+            _ => ranges.push(LineRange {
+                start: line,
+                end: line,
+            }),
+        }
+    }
+    ranges
+}
+
+fn render_ranges(ranges: &[LineRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| {
+            if range.start == range.end {
+                range.start.to_string()
+            } else {
+                format!("{}-{}", range.start, range.end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Why a requested file has no uncovered lines in the report.
+#[derive(Debug, PartialEq)]
+enum FileLookupError {
+    /// No file of the report has the path
+    Missing(String),
+    /// The path is not a file of the report, but the end of the paths of several
+    Ambiguous(String, Vec<String>),
+}
+
+/// Parses the JSON export. With `file`, the uncovered lines of that file, relative to `root`. A
+/// path that is no file of the report may also be the end of the path of exactly one file.
+fn parse_export(
+    json: &str,
+    root: &Path,
+    file: Option<&str>,
+) -> Result<(CoverageReport, Option<FileLookupError>), serde_json::Error> {
+    let export: Export = serde_json::from_str(json)?;
+    let Some(data) = export.data.into_iter().next() else {
+        return Err(serde::de::Error::custom("the report has no data"));
+    };
+
+    let mut uncovered = None;
+    let mut lookup_error = None;
+    if let Some(requested) = file {
+        let requested = Path::new(requested);
+        let resolved = root.join(requested);
+        let exact = data
+            .files
+            .iter()
+            .find(|exported| Path::new(&exported.filename) == resolved);
+        let found = match exact {
+            Some(exported) => Ok(exported),
+            None => {
+                let candidates = data
+                    .files
+                    .iter()
+                    .filter(|exported| Path::new(&exported.filename).ends_with(requested))
+                    .collect::<Vec<_>>();
+                match candidates.as_slice() {
+                    [exported] => Ok(*exported),
+                    [] => Err(FileLookupError::Missing(requested.display().to_string())),
+                    _ => Err(FileLookupError::Ambiguous(
+                        requested.display().to_string(),
+                        candidates
+                            .iter()
+                            .map(|exported| relative_path(&exported.filename, root))
+                            .collect(),
+                    )),
+                }
+            }
+        };
+        match found {
+            Ok(exported) => {
+                uncovered = Some(UncoveredLines {
+                    file: relative_path(&exported.filename, root),
+                    ranges: line_ranges(&uncovered_lines(&exported.segments)),
+                });
+            }
+            Err(error) => lookup_error = Some(error),
+        }
+    }
+
+    let files = data
+        .files
+        .into_iter()
+        .map(|exported| FileCoverage {
+            file: relative_path(&exported.filename, root),
+            summary: exported.summary,
+        })
+        .collect();
+    Ok((
+        CoverageReport {
+            files,
+            totals: data.totals,
+            uncovered,
+        },
+        lookup_error,
+    ))
+}
+
+pub struct CargoLlvmCovRmcpTool;
+
+impl Tool for CargoLlvmCovRmcpTool {
+    const NAME: &'static str = "cargo-llvm-cov";
+    const TITLE: &'static str = "Measure test coverage";
+    const DESCRIPTION: &'static str = "Runs the tests with cargo-llvm-cov and reports the line, region and function coverage of each file. Set `file` to also get the line ranges of that file that no test ran, e.g. to write the missing tests.";
+    type RequestArgs = CargoLlvmCovRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        // Unique per call, so concurrent calls don't read each other's report
+        static NEXT_REPORT: AtomicU32 = AtomicU32::new(1);
+        let report_path = std::env::temp_dir().join(format!(
+            "rust-mcp-server-llvm-cov-{}-{}.json",
+            std::process::id(),
+            NEXT_REPORT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // A report left by an earlier run must not be mistaken for the results of this one
+        let _ = std::fs::remove_file(&report_path);
+        let mut output = execute_command(request.build_cmd(&report_path)?, Self::NAME)?;
+        let json = std::fs::read_to_string(&report_path).ok();
+        let _ = std::fs::remove_file(&report_path);
+
+        let root = workspace_root()
+            .canonicalize()
+            .or_else(|_| std::env::current_dir())
+            .unwrap_or_default();
+        let parsed = json
+            .filter(|_| output.success())
+            .and_then(|json| parse_export(&json, &root, request.file.as_deref()).ok());
+        let Some((report, lookup_error)) = parsed else {
+            return Ok(unparsed_response(output));
+        };
+        let missing = match lookup_error {
+            Some(FileLookupError::Ambiguous(file, candidates)) => {
+                return Err(ErrorData::invalid_params(
+                    format!(
+                        "`{file}` matches several files of the report: {}. Pass the path relative to the workspace root",
+                        candidates.join(", ")
+                    ),
+                    None,
+                ));
+            }
+            Some(FileLookupError::Missing(file)) => Some(file),
+            None => None,
+        };
+
+        // The test results are in the output of #cargo-test, the coverage is what this tool adds
+        output.stdout = None;
+        output.stderr = output.stderr.take().and_then(filter_cargo_status);
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(report.render(), "coverage per file")
+                .annotate(Annotations::default()),
+        );
+        if let Some(uncovered) = &report.uncovered {
+            let text = if uncovered.ranges.is_empty() {
+                format!("{}: all lines are covered", uncovered.file)
+            } else {
+                format!(
+                    "{} uncovered lines: {}",
+                    uncovered.file,
+                    render_ranges(&uncovered.ranges)
+                )
+            };
+            response.add_content(
+                text_with_description(text, "uncovered lines").annotate(Annotations::default()),
+            );
+        }
+        if let Some(missing) = missing {
+            response.add_content(
+                text_with_description(
+                    format!(
+                        "No coverage data for `{missing}`. Only files compiled into the selected tests are in the report"
+                    ),
+                    "uncovered lines",
+                )
+                .annotate(Annotations::default()),
+            );
+        } else if request.file.is_none() {
+            response.add_recommendation(format!(
+                "Run #{} with `file` set to get the uncovered lines of a file",
+                Self::NAME
+            ));
+        }
+        response.set_structured_content(
+            serde_json::to_value(&report).expect("coverage report is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+/// Returns cargo-llvm-cov's own output when it produced no report, e.g. because a test failed
+/// or cargo-llvm-cov is not installed.
+fn unparsed_response(output: Output) -> Response {
+    let not_installed = output
+        .stderr
+        .as_ref()
+        .is_some_and(|stderr| stderr.0.contains("no such command: `llvm-cov`"));
+    let failed = !output.success();
+    let mut response: Response = output.into();
+    if not_installed {
+        response.add_recommendation(format!(
+            "Install cargo-llvm-cov with #{}",
+            CargoLlvmCovInstallRmcpTool::NAME
+        ));
+    } else if failed {
+        response.add_recommendation(format!(
+            "Coverage is only reported when the tests pass. Run #{} to get the failures with their details",
+            CargoTestRmcpTool::NAME
+        ));
+    }
+    response
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct CargoLlvmCovInstallRequest {}
+
+impl CargoLlvmCovInstallRequest {
+    /// Commands that install cargo-llvm-cov and the LLVM tools it runs.
+    pub fn build_cmds(&self) -> [Command; 2] {
+        let mut component = Command::new("rustup");
+        component
+            .arg("component")
+            .arg("add")
+            .arg("llvm-tools-preview");
+
+        let mut install = Command::new("cargo");
+        install.arg("install").arg("cargo-llvm-cov").arg("--locked");
+
+        [component, install]
+    }
+}
+
+pub struct CargoLlvmCovInstallRmcpTool;
+
+impl Tool for CargoLlvmCovInstallRmcpTool {
+    const NAME: &'static str = "cargo-llvm-cov-install";
+    const TITLE: &'static str = "Install cargo-llvm-cov";
+    const DESCRIPTION: &'static str =
+        "Installs cargo-llvm-cov and the llvm-tools-preview component it needs";
+    type RequestArgs = CargoLlvmCovInstallRequest;
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let mut outputs = Vec::new();
+        for cmd in request.build_cmds() {
+            let output = execute_command(cmd, Self::NAME)?;
+            let failed = !output.success();
+            outputs.push(output);
+            if failed {
+                break;
+            }
+        }
+        Ok(Output::combine(outputs)
+            .expect("the install runs at least one command")
+            .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{
+        "type": "llvm.coverage.json.export",
+        "version": "2.0.1",
+        "data": [{
+            "files": [
+                {
+                    "filename": "/ws/src/lib.rs",
+                    "segments": [
+                        [1, 28, 1, true, true, false],
+                        [3, 2, 0, false, false, false],
+                        [5, 32, 0, true, true, false],
+                        [6, 8, 0, true, true, false],
+                        [7, 6, 0, true, false, false],
+                        [8, 2, 0, false, false, false],
+                        [10, 1, 0, false, true, false],
+                        [11, 1, 0, false, false, false]
+                    ],
+                    "summary": {
+                        "lines": {"count": 6, "covered": 3, "percent": 50.0},
+                        "regions": {"count": 3, "covered": 1, "notcovered": 2, "percent": 33.33},
+                        "functions": {"count": 2, "covered": 1, "percent": 50.0}
+                    }
+                },
+                {
+                    "filename": "/ws/src/main.rs",
+                    "segments": [],
+                    "summary": {
+                        "lines": {"count": 0, "covered": 0, "percent": 0.0},
+                        "regions": {"count": 0, "covered": 0, "notcovered": 0, "percent": 0.0},
+                        "functions": {"count": 0, "covered": 0, "percent": 0.0}
+                    }
+                }
+            ],
+            "totals": {
+                "lines": {"count": 6, "covered": 3, "percent": 50.0},
+                "regions": {"count": 3, "covered": 1, "notcovered": 2, "percent": 33.33},
+                "functions": {"count": 2, "covered": 1, "percent": 50.0}
+            }
+        }]
+    }"#;
+
+    #[test]
+    fn test_parse_export() {
+        let (report, missing) = parse_export(EXPORT, Path::new("/ws"), None).unwrap();
+        assert_eq!(missing, None);
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].file, "src/lib.rs");
+        assert_eq!(
+            report.files[0].summary.regions,
+            Summary {
+                count: 3,
+                covered: 1,
+                percent: 33.33
+            }
+        );
+        assert!(report.uncovered.is_none());
+        assert_eq!(
+            report.render(),
+            "src/lib.rs: lines 50.0% (3/6), regions 33.3% (1/3), functions 50.0% (1/2)\n\
+             src/main.rs: lines 0.0% (0/0), regions 0.0% (0/0), functions 0.0% (0/0)\n\
+             Total: lines 50.0% (3/6), regions 33.3% (1/3), functions 50.0% (1/2)"
+        );
+
+        let (report, _) = parse_export(EXPORT, Path::new("/ws"), Some("./src/lib.rs")).unwrap();
+        assert_eq!(
+            report.uncovered,
+            Some(UncoveredLines {
+                file: "src/lib.rs".into(),
+                ranges: vec![LineRange { start: 5, end: 8 }],
+            })
+        );
+
+        let (_, missing) = parse_export(EXPORT, Path::new("/ws"), Some("src/other.rs")).unwrap();
+        assert_eq!(
+            missing,
+            Some(FileLookupError::Missing("src/other.rs".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_export_file_lookup() {
+        let export = EXPORT.replace("/ws/src/main.rs", "/ws/crates/a/src/lib.rs");
+        let uncovered_file = |file| {
+            parse_export(&export, Path::new("/ws"), Some(file))
+                .unwrap()
+                .0
+                .uncovered
+                .map(|uncovered| uncovered.file)
+        };
+
+        // The path relative to the root is preferred over the end of a longer path
+        assert_eq!(uncovered_file("src/lib.rs").as_deref(), Some("src/lib.rs"));
+        assert_eq!(
+            uncovered_file("/ws/crates/a/src/lib.rs").as_deref(),
+            Some("crates/a/src/lib.rs")
+        );
+        assert_eq!(
+            uncovered_file("a/src/lib.rs").as_deref(),
+            Some("crates/a/src/lib.rs")
+        );
+
+        let (report, lookup_error) =
+            parse_export(&export, Path::new("/ws"), Some("lib.rs")).unwrap();
+        assert!(report.uncovered.is_none());
+        assert_eq!(
+            lookup_error,
+            Some(FileLookupError::Ambiguous(
+                "lib.rs".into(),
+                vec!["src/lib.rs".into(), "crates/a/src/lib.rs".into()]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_uncovered_lines() {
+        // A function that ran on lines 1 to 3, one that did not on lines 5 to 8, and a region
+        // skipped by `#[cfg]` on line 10
+        let (report, _) = parse_export(EXPORT, Path::new("/ws"), Some("src/lib.rs")).unwrap();
+        let ranges = report.uncovered.unwrap().ranges;
+        assert_eq!(render_ranges(&ranges), "5-8");
+
+        // The block from the end of line 2 to line 4 never ran, the lines around it did. Line 2
+        // counts as covered as its code before the block ran
+        let segments = [
+            (1, 1, 4, true, true, false),
+            (2, 15, 0, true, true, false),
+            (4, 6, 4, true, false, false),
+            (6, 2, 0, false, false, false),
+        ];
+        assert_eq!(uncovered_lines(&segments), [3, 4]);
+        assert_eq!(render_ranges(&line_ranges(&[3, 7, 8, 9])), "3, 7-9");
+    }
+}
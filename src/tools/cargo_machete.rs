@@ -5,7 +5,8 @@ use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct CargoMacheteRequest {
-    /// Uses cargo-metadata to figure out the dependencies' names. May be useful if some dependencies are renamed.
+    /// Uses cargo-metadata to figure out the dependencies' names. May be useful if some
+    /// dependencies are renamed.
     #[serde(default)]
     with_metadata: Option<bool>,
 
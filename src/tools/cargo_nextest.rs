@@ -0,0 +1,552 @@
+use std::{fmt::Write, process::Command};
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response, Tool,
+    command::{Output, Stderr, text_with_description},
+    execute_command,
+    serde_utils::{deserialize_string, deserialize_string_vec},
+    testing::{ListedBinary, ListedTest, TestReport, parse_junit, render_test_list},
+    tools::{
+        common::{LOCKING_MODE_DESC, locking_mode_to_cli_flags, reject_conflict},
+        target_directory,
+    },
+};
+
+/// Name under which the server passes its nextest settings with `--tool-config-file`.
+const TOOL_NAME: &str = "rust-mcp-server";
+
+/// Package, feature and test selection shared by `cargo nextest run` and `cargo nextest list`.
+#[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
+pub struct NextestSelection {
+    /// Only include tests whose name contains one of these strings.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    filters: Option<Vec<String>>,
+    /// Filterset expression selecting the tests, e.g. `test(parse) & package(my-crate)` or
+    /// `kind(test)`.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    filterset: Option<String>,
+    /// Which tests to include with respect to `#[ignore]`: `default` (skip ignored tests), `only`
+    /// or `all`.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    run_ignored: Option<String>,
+    /// Packages to test. By default, the package in the current directory or the default
+    /// workspace members are selected.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    package: Option<Vec<String>>,
+    /// Test all packages in the workspace.
+    #[serde(default)]
+    workspace: Option<bool>,
+    /// Packages to exclude. Only used together with `workspace`.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    exclude: Option<Vec<String>>,
+    /// Features to activate, e.g. `serde` or `my-crate/serde`.
+    #[serde(default, deserialize_with = "deserialize_string_vec")]
+    features: Option<Vec<String>>,
+    /// Activate all available features of the selected packages.
+    #[serde(default)]
+    all_features: Option<bool>,
+    /// Do not activate the `default` feature of the selected packages.
+    #[serde(default)]
+    no_default_features: Option<bool>,
+    /// Build with the `release` profile. Same as `cargo_profile: "release"`, so don't set both.
+    #[serde(default)]
+    release: Option<bool>,
+    /// Cargo profile to build with, e.g. `dev`, `release` or a custom profile from `Cargo.toml`.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    cargo_profile: Option<String>,
+    #[schemars(description = LOCKING_MODE_DESC)]
+    #[serde(default, deserialize_with = "deserialize_string")]
+    locking_mode: Option<String>,
+}
+
+impl NextestSelection {
+    fn validate(&self) -> Result<(), ErrorData> {
+        reject_conflict(
+            ("release", self.release.unwrap_or(false)),
+            ("cargo_profile", self.cargo_profile.is_some()),
+            "`release` is the same as `cargo_profile: \"release\"`. Set only `cargo_profile`, or only `release: true`",
+        )?;
+        reject_conflict(
+            ("all_features", self.all_features.unwrap_or(false)),
+            (
+                "features",
+                self.features.as_ref().is_some_and(|f| !f.is_empty()),
+            ),
+            "`all_features` already activates every feature. Remove `features`, or drop `all_features` to activate only the listed features",
+        )?;
+        if let Some(run_ignored) = &self.run_ignored
+            && !["default", "only", "all"].contains(&run_ignored.as_str())
+        {
+            return Err(ErrorData::invalid_params(
+                format!("Invalid run_ignored '{run_ignored}'. Allowed values: default, only, all"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn add_args(&self, cmd: &mut Command) -> Result<(), ErrorData> {
+        if let Some(packages) = &self.package {
+            for package in packages {
+                cmd.arg("--package").arg(package);
+            }
+        }
+
+        if self.workspace.unwrap_or(false) {
+            cmd.arg("--workspace");
+        }
+
+        if let Some(excludes) = &self.exclude {
+            for exclude in excludes {
+                cmd.arg("--exclude").arg(exclude);
+            }
+        }
+
+        if let Some(features) = &self.features
+            && !features.is_empty()
+        {
+            cmd.arg("--features").arg(features.join(","));
+        }
+
+        if self.all_features.unwrap_or(false) {
+            cmd.arg("--all-features");
+        }
+
+        if self.no_default_features.unwrap_or(false) {
+            cmd.arg("--no-default-features");
+        }
+
+        if self.release.unwrap_or(false) {
+            cmd.arg("--release");
+        }
+
+        if let Some(profile) = &self.cargo_profile {
+            cmd.arg("--cargo-profile").arg(profile);
+        }
+
+        cmd.args(locking_mode_to_cli_flags(
+            self.locking_mode.as_deref(),
+            "unlocked",
+        )?);
+
+        if let Some(filterset) = &self.filterset {
+            cmd.arg("--filterset").arg(filterset);
+        }
+
+        if let Some(run_ignored) = &self.run_ignored {
+            cmd.arg("--run-ignored").arg(run_ignored);
+        }
+
+        if let Some(filters) = &self.filters {
+            cmd.args(filters);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
+pub struct CargoNextestRunRequest {
+    #[serde(flatten)]
+    selection: NextestSelection,
+    /// Nextest profile from `.config/nextest.toml`, e.g. `ci`. Defaults to `default`.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    profile: Option<String>,
+    /// Number of times to retry a failing test. Tests that pass on a retry are reported as flaky.
+    retries: Option<u32>,
+    /// Run only a part of the tests, e.g. `count:1/2` or `hash:1/3`. Useful to split a slow
+    /// suite into several calls.
+    #[serde(default, deserialize_with = "deserialize_string")]
+    partition: Option<String>,
+    /// Run all tests even if some of them fail.
+    #[serde(default)]
+    no_fail_fast: Option<bool>,
+    /// Number of tests to run at the same time. Defaults to the number of logical CPUs.
+    test_threads: Option<u32>,
+}
+
+impl CargoNextestRunRequest {
+    fn profile(&self) -> &str {
+        self.profile.as_deref().unwrap_or("default")
+    }
+
+    pub fn build_cmd(&self, tool_config: Option<&str>) -> Result<Command, ErrorData> {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("nextest").arg("run");
+
+        if let Some(tool_config) = tool_config {
+            cmd.arg("--tool-config-file")
+                .arg(format!("{TOOL_NAME}:{tool_config}"));
+        }
+
+        if let Some(profile) = &self.profile {
+            cmd.arg("--profile").arg(profile);
+        }
+
+        if let Some(retries) = self.retries {
+            cmd.arg("--retries").arg(retries.to_string());
+        }
+
+        if let Some(partition) = &self.partition {
+            cmd.arg("--partition").arg(partition);
+        }
+
+        if self.no_fail_fast.unwrap_or(false) {
+            cmd.arg("--no-fail-fast");
+        }
+
+        if let Some(threads) = self.test_threads {
+            cmd.arg("--test-threads").arg(threads.to_string());
+        }
+
+        self.selection.add_args(&mut cmd)?;
+        Ok(cmd)
+    }
+}
+
+pub struct CargoNextestRunRmcpTool;
+
+impl Tool for CargoNextestRunRmcpTool {
+    const NAME: &'static str = "cargo-nextest-run";
+    const TITLE: &'static str = "Run tests with cargo-nextest";
+    const DESCRIPTION: &'static str = "Run tests with cargo-nextest, each test in its own process. Supports retries, partitions and nextest profiles. Failures are returned structured from nextest's JUnit report.";
+    type RequestArgs = CargoNextestRunRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()?;
+        if let Some(profile) = &request.profile
+            && (profile.is_empty()
+                || !profile
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "Invalid profile '{profile}'. Nextest profile names consist of letters, digits, '-' and '_'"
+                ),
+                None,
+            ));
+        }
+        if let Some(partition) = &request.partition {
+            validate_partition(partition)?;
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        let junit_path = target_directory()
+            .join("nextest")
+            .join(request.profile())
+            .join("junit.xml");
+        // A report left by an earlier run must not be mistaken for the results of this one
+        let _ = std::fs::remove_file(&junit_path);
+        let tool_config = write_tool_config(request.profile());
+
+        let cmd = request.build_cmd(tool_config.as_deref())?;
+        let mut output = execute_command(cmd, Self::NAME)?;
+
+        let binaries = std::fs::read_to_string(&junit_path)
+            .ok()
+            .and_then(|xml| parse_junit(&xml).ok())
+            .filter(|binaries| !binaries.is_empty());
+        let Some(binaries) = binaries else {
+            return Ok(unparsed_response(output));
+        };
+
+        output.stderr = output.stderr.take().and_then(filter_nextest_stderr);
+        output.stdout = None;
+        Ok(TestReport::new(output.success(), binaries).into_response(output))
+    }
+}
+
+/// Accepts `count:M/N` and `hash:M/N` with `1 <= M <= N`.
+fn validate_partition(partition: &str) -> Result<(), ErrorData> {
+    let valid = partition
+        .strip_prefix("count:")
+        .or_else(|| partition.strip_prefix("hash:"))
+        .and_then(|shard| shard.split_once('/'))
+        .and_then(|(m, n)| Some((m.parse::<u32>().ok()?, n.parse::<u32>().ok()?)))
+        .is_some_and(|(m, n)| 1 <= m && m <= n);
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorData::invalid_params(
+            format!(
+                "Invalid partition '{partition}'. Use `count:M/N` or `hash:M/N` to run part M of N, e.g. `count:1/2`"
+            ),
+            None,
+        ))
+    }
+}
+
+/// Writes the tool config that enables the JUnit report for `profile`, returning its path.
+///
+/// Tool config has a lower priority than the repository's `.config/nextest.toml`, so a report
+/// path configured there wins and the results fall back to nextest's own output.
+fn write_tool_config(profile: &str) -> Option<String> {
+    let mut config = String::from("[profile.default.junit]\npath = \"junit.xml\"\n");
+    if profile != "default" {
+        let _ = write!(
+            config,
+            "\n[profile.{profile}.junit]\npath = \"junit.xml\"\n"
+        );
+    }
+    let path = std::env::temp_dir().join(format!("{TOOL_NAME}-nextest-{profile}.toml"));
+    std::fs::write(&path, config).ok()?;
+    path.to_str().map(str::to_owned)
+}
+
+/// Keeps errors, warnings and the summary from nextest's stderr. The status of each test and
+/// its captured output are in the report.
+fn filter_nextest_stderr(stderr: Stderr) -> Option<Stderr> {
+    let lines: Vec<&str> = stderr
+        .0
+        .lines()
+        .filter(|line| {
+            let line = line.trim_start();
+            line.starts_with("error") || line.starts_with("warning") || line.starts_with("Summary")
+        })
+        .collect();
+    (!lines.is_empty()).then(|| Stderr(lines.join("\n")))
+}
+
+/// Returns nextest's own output when it produced no results, e.g. because the tests did not
+/// compile or nextest is not installed.
+fn unparsed_response(output: Output) -> Response {
+    let not_installed = output
+        .stderr
+        .as_ref()
+        .is_some_and(|stderr| stderr.0.contains("no such command: `nextest`"));
+    let mut response: Response = output.into();
+    if not_installed {
+        response.add_recommendation("Install cargo-nextest with #cargo-nextest-install");
+    }
+    response
+}
+
+#[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
+pub struct CargoNextestListRequest {
+    #[serde(flatten)]
+    selection: NextestSelection,
+}
+
+impl CargoNextestListRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("nextest")
+            .arg("list")
+            .arg("--message-format")
+            .arg("json");
+        self.selection.add_args(&mut cmd)?;
+        Ok(cmd)
+    }
+}
+
+/// Parses the output of `cargo nextest list --message-format json`, keeping the tests that match
+/// the filters, including ignored tests that only `run_ignored` leaves out.
+fn parse_test_list(json: &str) -> Option<Vec<ListedBinary>> {
+    let list: serde_json::Value = serde_json::from_str(json).ok()?;
+    let suites = list["rust-suites"].as_object()?;
+    let binaries = suites
+        .iter()
+        .map(|(binary_id, suite)| {
+            let tests = suite["testcases"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(_, case)| {
+                    let filter_match = &case["filter-match"];
+                    filter_match["status"] == "matches" || filter_match["reason"] == "ignored"
+                })
+                .map(|(name, case)| ListedTest {
+                    name: name.clone(),
+                    ignored: case["ignored"].as_bool().unwrap_or(false),
+                })
+                .collect();
+            ListedBinary {
+                package: suite["package-name"].as_str().map(str::to_owned),
+                target: binary_id.clone(),
+                kind: suite["kind"].as_str().unwrap_or_default().to_owned(),
+                tests,
+            }
+        })
+        .filter(|binary| !binary.tests.is_empty())
+        .collect();
+    Some(binaries)
+}
+
+pub struct CargoNextestListRmcpTool;
+
+impl Tool for CargoNextestListRmcpTool {
+    const NAME: &'static str = "cargo-nextest-list";
+    const TITLE: &'static str = "List tests with cargo-nextest";
+    const DESCRIPTION: &'static str =
+        "List the tests cargo-nextest would run, grouped by test binary, without running them.";
+    type RequestArgs = CargoNextestListRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.selection.validate()
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        let mut output = execute_command(request.build_cmd()?, Self::NAME)?;
+        let binaries = output
+            .stdout
+            .as_ref()
+            .and_then(|stdout| parse_test_list(&stdout.0));
+        let Some(binaries) = binaries else {
+            return Ok(unparsed_response(output));
+        };
+
+        output.stdout = None;
+        output.stderr = output.stderr.take().and_then(filter_nextest_stderr);
+        let mut response: Response = output.into();
+        response.add_content(
+            text_with_description(
+                render_test_list(&binaries, "`filters`, `filterset` or `package`"),
+                "test list",
+            )
+            .annotate(Annotations::default()),
+        );
+        response.set_structured_content(serde_json::json!({ "binaries": binaries }));
+        Ok(response)
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
+pub struct CargoNextestInstallRequest {}
+
+impl CargoNextestInstallRequest {
+    pub fn build_cmd(&self) -> Result<Command, ErrorData> {
+        let mut cmd = Command::new("cargo");
+        cmd.arg("install").arg("cargo-nextest").arg("--locked");
+
+        Ok(cmd)
+    }
+}
+
+pub struct CargoNextestInstallRmcpTool;
+
+impl Tool for CargoNextestInstallRmcpTool {
+    const NAME: &'static str = "cargo-nextest-install";
+    const TITLE: &'static str = "Install cargo-nextest";
+    const DESCRIPTION: &'static str = "Installs cargo-nextest, a test runner for Rust projects";
+    type RequestArgs = CargoNextestInstallRequest;
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<crate::Response, ErrorData> {
+        execute_command(request.build_cmd()?, Self::NAME).map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_build_run_cmd() {
+        let request: CargoNextestRunRequest = serde_json::from_value(json!({
+            "filters": ["parse"],
+            "package": "app",
+            "profile": "ci",
+            "retries": 2,
+            "partition": "hash:1/3",
+            "run_ignored": "all",
+        }))
+        .unwrap();
+        CargoNextestRunRmcpTool.validate(&request).unwrap();
+
+        let cmd = request.build_cmd(Some("/tmp/config.toml")).unwrap();
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_string_lossy()).collect();
+        assert_eq!(
+            args,
+            [
+                "nextest",
+                "run",
+                "--tool-config-file",
+                "rust-mcp-server:/tmp/config.toml",
+                "--profile",
+                "ci",
+                "--retries",
+                "2",
+                "--partition",
+                "hash:1/3",
+                "--package",
+                "app",
+                "--run-ignored",
+                "all",
+                "parse"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_partition() {
+        assert!(validate_partition("count:1/2").is_ok());
+        assert!(validate_partition("hash:3/3").is_ok());
+        assert!(validate_partition("count:0/2").is_err());
+        assert!(validate_partition("count:3/2").is_err());
+        assert!(validate_partition("1/2").is_err());
+    }
+
+    #[test]
+    fn test_parse_test_list() {
+        let list = json!({
+            "test-count": 3,
+            "rust-suites": {
+                "app": {
+                    "package-name": "app",
+                    "binary-id": "app",
+                    "kind": "lib",
+                    "testcases": {
+                        "tests::fast": {"ignored": false, "filter-match": {"status": "matches"}},
+                        "tests::slow": {
+                            "ignored": true,
+                            "filter-match": {"status": "mismatch", "reason": "ignored"}
+                        },
+                        "tests::other": {
+                            "ignored": false,
+                            "filter-match": {"status": "mismatch", "reason": "string"}
+                        }
+                    }
+                },
+                "app::api": {
+                    "package-name": "app",
+                    "binary-id": "app::api",
+                    "kind": "test",
+                    "testcases": {}
+                }
+            }
+        });
+
+        let binaries = parse_test_list(&list.to_string()).unwrap();
+        assert_eq!(
+            binaries,
+            [ListedBinary {
+                package: Some("app".into()),
+                target: "app".into(),
+                kind: "lib".into(),
+                tests: vec![
+                    ListedTest {
+                        name: "tests::fast".into(),
+                        ignored: false
+                    },
+                    ListedTest {
+                        name: "tests::slow".into(),
+                        ignored: true
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            render_test_list(&binaries, "`package`"),
+            "2 test(s) in 1 test binary\n\napp (lib), 2 test(s)\n  tests::\n    fast\n    slow (ignored)"
+        );
+        assert!(parse_test_list("error").is_none());
+    }
+}
@@ -1,17 +1,18 @@
 /// Locking mode for dependency resolution.
 ///
 /// Valid options:
-/// - "locked" (default): Assert that `Cargo.lock` will remain unchanged
+/// - "locked": Assert that `Cargo.lock` will remain unchanged
 /// - "unlocked": Allow `Cargo.lock` to be updated
 /// - "offline": Run without accessing the network
 /// - "frozen": Equivalent to specifying both --locked and --offline
-pub const LOCKING_MODE_DESC: &str = r#"Locking mode for dependency resolution.
+///
+/// When omitted, the server's `--locking-mode` setting is used.
+pub const LOCKING_MODE_DESC: &str =
+    "One of: locked, unlocked, offline, frozen. Defaults to the server setting.";
 
-Valid options:
-- "locked" (default): Assert that `Cargo.lock` will remain unchanged
-- "unlocked": Allow `Cargo.lock` to be updated
-- "offline": Run without accessing the network
-- "frozen": Equivalent to specifying both --locked and --offline"#;
+/// Locking mode of the tools that change `Cargo.lock`, which ignore the server's
+/// `--locking-mode` setting.
+pub const LOCKFILE_LOCKING_MODE_DESC: &str = "One of: locked, unlocked, offline, frozen. Defaults to unlocked, the server setting does not apply.";
 
 /// Output verbosity level.
 ///
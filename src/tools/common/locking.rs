@@ -0,0 +1,94 @@
+use std::sync::OnceLock;
+
+use rmcp::ErrorData;
+
+pub(crate) const LOCKING_MODES: [&str; 4] = ["locked", "unlocked", "offline", "frozen"];
+
+static DEFAULT_LOCKING_MODE: OnceLock<String> = OnceLock::new();
+
+/// Sets the locking mode used by tools when a request doesn't specify one.
+pub fn set_default_locking_mode(mode: String) {
+    DEFAULT_LOCKING_MODE
+        .set(mode)
+        .expect("Default locking mode can only be set once");
+}
+
+/// Converts a locking mode to cargo CLI flags.
+///
+/// The mode requested by the agent takes precedence, then the server-level default
+/// (`--locking-mode`), then `fallback`, which is the tool's own default.
+pub(crate) fn locking_mode_to_cli_flags(
+    mode: Option<&str>,
+    fallback: &'static str,
+) -> Result<Vec<&'static str>, ErrorData> {
+    let mode = mode
+        .or(DEFAULT_LOCKING_MODE.get().map(String::as_str))
+        .unwrap_or(fallback);
+    mode_to_cli_flags(mode)
+}
+
+/// Converts the locking mode of a tool whose purpose is to change `Cargo.lock`, e.g. `cargo
+/// update`, to cargo CLI flags.
+///
+/// Only the mode requested by the agent applies. A server-level `locked` or `frozen` default
+/// would make every call fail, so these tools run unlocked unless asked otherwise.
+pub(crate) fn lockfile_locking_mode_to_cli_flags(
+    mode: Option<&str>,
+) -> Result<Vec<&'static str>, ErrorData> {
+    mode_to_cli_flags(mode.unwrap_or("unlocked"))
+}
+
+fn mode_to_cli_flags(mode: &str) -> Result<Vec<&'static str>, ErrorData> {
+    Ok(match mode {
+        "locked" => vec!["--locked"],
+        "unlocked" => vec![],
+        "offline" => vec!["--offline"],
+        "frozen" => vec!["--frozen"],
+        unknown => {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "Unknown locking mode: {unknown}. Valid options are: {}",
+                    LOCKING_MODES.join(", ")
+                ),
+                None,
+            ));
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locking_mode_cli_flags() {
+        assert!(
+            locking_mode_to_cli_flags(None, "unlocked")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            locking_mode_to_cli_flags(None, "locked").unwrap(),
+            ["--locked"]
+        );
+        assert_eq!(
+            locking_mode_to_cli_flags(Some("locked"), "unlocked").unwrap(),
+            ["--locked"]
+        );
+        assert!(
+            locking_mode_to_cli_flags(Some("unlocked"), "locked")
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            locking_mode_to_cli_flags(Some("offline"), "unlocked").unwrap(),
+            ["--offline"]
+        );
+        assert_eq!(
+            locking_mode_to_cli_flags(Some("frozen"), "unlocked").unwrap(),
+            ["--frozen"]
+        );
+
+        assert!(locking_mode_to_cli_flags(Some("invalid"), "unlocked").is_err());
+    }
+}
@@ -1,4 +1,16 @@
 mod docs;
+mod locking;
+mod toolchain;
+mod validation;
 
 #[allow(dead_code, unused_imports)]
-pub use docs::{LOCKING_MODE_DESC, OUTPUT_VERBOSITY_DESC};
+pub use docs::OUTPUT_VERBOSITY_DESC;
+pub use docs::{LOCKFILE_LOCKING_MODE_DESC, LOCKING_MODE_DESC};
+pub use locking::set_default_locking_mode;
+pub(crate) use locking::{
+    LOCKING_MODES, lockfile_locking_mode_to_cli_flags, locking_mode_to_cli_flags,
+};
+pub(crate) use toolchain::{
+    cargo_command, is_nightly, rustc_command, unchecked_cargo_command, validate_toolchain,
+};
+pub(crate) use validation::{reject_conflict, require, validate_features, validate_profile};
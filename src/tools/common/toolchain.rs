@@ -0,0 +1,165 @@
+use std::process::Command;
+
+use rmcp::ErrorData;
+
+/// Creates a `cargo` command, optionally pinned to a rustup toolchain (`cargo +<toolchain>`).
+///
+/// The toolchain is validated against `rustup toolchain list` before the command is built,
+/// so a typo results in a helpful error instead of rustup trying to install a new toolchain.
+pub(crate) fn cargo_command(toolchain: Option<&str>) -> Result<Command, ErrorData> {
+    if let Some(toolchain) = toolchain {
+        validate_toolchain(toolchain)?;
+    }
+    Ok(unchecked_cargo_command(toolchain))
+}
+
+/// Like [`cargo_command`], for a toolchain that was already checked with [`validate_toolchain`],
+/// e.g. by a tool that runs cargo many times.
+pub(crate) fn unchecked_cargo_command(toolchain: Option<&str>) -> Command {
+    let mut cmd = Command::new("cargo");
+    if let Some(toolchain) = toolchain {
+        cmd.arg(format!("+{toolchain}"));
+    }
+    cmd
+}
+
+/// Creates a `rustc` command, optionally pinned to a rustup toolchain (`rustc +<toolchain>`),
+/// validated like in [`cargo_command`]. It runs in the workspace root, so without a toolchain the
+/// one selected for the workspace is used.
+pub(crate) fn rustc_command(toolchain: Option<&str>) -> Result<Command, ErrorData> {
+    let mut cmd = Command::new("rustc");
+    if let Some(toolchain) = toolchain {
+        validate_toolchain(toolchain)?;
+        cmd.arg(format!("+{toolchain}"));
+    }
+    crate::tools::apply_workspace_root(&mut cmd);
+    Ok(cmd)
+}
+
+/// Returns `true` if `rustc` of the given toolchain, or of the toolchain selected for the
+/// workspace, is a nightly build. Unstable flags like `-Z unstable-options` need one.
+pub(crate) fn is_nightly(toolchain: Option<&str>) -> bool {
+    let mut cmd = Command::new("rustc");
+    if let Some(toolchain) = toolchain {
+        cmd.arg(format!("+{toolchain}"));
+    }
+    cmd.arg("--version");
+    crate::tools::apply_workspace_root(&mut cmd);
+    cmd.output().is_ok_and(|output| {
+        output.status.success() && String::from_utf8_lossy(&output.stdout).contains("-nightly")
+    })
+}
+
+/// Checks that `toolchain` is installed, so that rustup doesn't try to install it.
+pub(crate) fn validate_toolchain(toolchain: &str) -> Result<(), ErrorData> {
+    let output = Command::new("rustup")
+        .arg("toolchain")
+        .arg("list")
+        .output()
+        .map_err(|e| {
+            ErrorData::internal_error(
+                format!(
+                    "Failed to run `rustup toolchain list` to validate toolchain '{toolchain}': {e}. Toolchain selection requires rustup to be installed"
+                ),
+                None,
+            )
+        })?;
+
+    if !output.status.success() {
+        return Err(ErrorData::internal_error(
+            format!(
+                "`rustup toolchain list` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+            None,
+        ));
+    }
+
+    let installed = parse_toolchain_list(&String::from_utf8_lossy(&output.stdout));
+    if installed
+        .iter()
+        .any(|name| toolchain_matches(toolchain, name))
+    {
+        return Ok(());
+    }
+
+    let installed = if installed.is_empty() {
+        "none".to_owned()
+    } else {
+        installed.join(", ")
+    };
+    Err(ErrorData::invalid_params(
+        format!(
+            "Toolchain '{toolchain}' is not installed. Installed toolchains: {installed}. Use one of the installed toolchains (e.g. 'stable' or 'nightly') or install the missing one with `rustup toolchain install {toolchain}`"
+        ),
+        None,
+    ))
+}
+
+/// Extracts toolchain names from `rustup toolchain list` output,
+/// e.g. `stable-x86_64-unknown-linux-gnu (active, default)` -> `stable-x86_64-unknown-linux-gnu`.
+fn parse_toolchain_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| !name.is_empty() && *name != "no")
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Checks whether a requested toolchain (`nightly`, `1.90`, `stable-x86_64-pc-windows-msvc`)
+/// refers to the installed toolchain `installed`, which usually carries the host triple.
+fn toolchain_matches(requested: &str, installed: &str) -> bool {
+    if requested == installed {
+        return true;
+    }
+
+    // `nightly` matches `nightly-x86_64-unknown-linux-gnu`, but not `nightly-2025-01-01-x86_64-...`
+    installed
+        .strip_prefix(requested)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .and_then(|triple| triple.chars().next())
+        .is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toolchain_list() {
+        let output = "stable-x86_64-unknown-linux-gnu (active, default)\nnightly-x86_64-unknown-linux-gnu\n1.90-x86_64-unknown-linux-gnu\n";
+        assert_eq!(
+            parse_toolchain_list(output),
+            [
+                "stable-x86_64-unknown-linux-gnu",
+                "nightly-x86_64-unknown-linux-gnu",
+                "1.90-x86_64-unknown-linux-gnu"
+            ]
+        );
+        assert!(parse_toolchain_list("no installed toolchains\n").is_empty());
+    }
+
+    #[test]
+    fn test_toolchain_matches() {
+        let installed = "nightly-x86_64-unknown-linux-gnu";
+        assert!(toolchain_matches("nightly", installed));
+        assert!(toolchain_matches(installed, installed));
+        assert!(!toolchain_matches("stable", installed));
+        assert!(!toolchain_matches("night", installed));
+
+        assert!(toolchain_matches("1.90", "1.90-x86_64-unknown-linux-gnu"));
+        assert!(!toolchain_matches(
+            "1.90",
+            "1.90.0-x86_64-unknown-linux-gnu"
+        ));
+        assert!(!toolchain_matches(
+            "nightly",
+            "nightly-2025-01-01-x86_64-unknown-linux-gnu"
+        ));
+        assert!(toolchain_matches(
+            "nightly-2025-01-01",
+            "nightly-2025-01-01-x86_64-unknown-linux-gnu"
+        ));
+    }
+}
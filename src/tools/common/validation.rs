@@ -0,0 +1,102 @@
+use rmcp::ErrorData;
+
+/// Rejects a request that sets two mutually exclusive arguments.
+///
+/// Each argument is passed as `(name, is_set)`, and `fix` explains how to resolve the conflict.
+pub(crate) fn reject_conflict(
+    first: (&str, bool),
+    second: (&str, bool),
+    fix: &str,
+) -> Result<(), ErrorData> {
+    if first.1 && second.1 {
+        return Err(ErrorData::invalid_params(
+            format!(
+                "Conflicting arguments `{}` and `{}`: {fix}",
+                first.0, second.0
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects a request that sets an argument without the argument it depends on.
+pub(crate) fn require(
+    argument: (&str, bool),
+    required: (&str, bool),
+    fix: &str,
+) -> Result<(), ErrorData> {
+    if argument.1 && !required.1 {
+        return Err(ErrorData::invalid_params(
+            format!("Argument `{}` requires `{}`: {fix}", argument.0, required.0),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+/// `release` is a shorthand for `profile: "release"`, cargo refuses both at once.
+pub(crate) fn validate_profile(
+    release: Option<bool>,
+    profile: Option<&str>,
+) -> Result<(), ErrorData> {
+    reject_conflict(
+        ("release", release.unwrap_or(false)),
+        ("profile", profile.is_some()),
+        "`release` is the same as `profile: \"release\"`. Set only `profile`, or only `release: true`",
+    )
+}
+
+/// `all_features` already activates every feature, so an explicit list is either redundant or a
+/// mistake.
+pub(crate) fn validate_features(
+    all_features: Option<bool>,
+    features: Option<&[String]>,
+) -> Result<(), ErrorData> {
+    reject_conflict(
+        ("all_features", all_features.unwrap_or(false)),
+        ("features", features.is_some_and(|f| !f.is_empty())),
+        "`all_features` already activates every feature. Remove `features`, or drop `all_features` to activate only the listed features",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_profile() {
+        assert!(validate_profile(None, None).is_ok());
+        assert!(validate_profile(Some(true), None).is_ok());
+        assert!(validate_profile(Some(false), Some("dev")).is_ok());
+
+        let err = validate_profile(Some(true), Some("release")).unwrap_err();
+        assert!(err.message.contains("`release` and `profile`"), "{err:?}");
+    }
+
+    #[test]
+    fn test_validate_features() {
+        let features = ["serde".to_owned()];
+        assert!(validate_features(Some(true), None).is_ok());
+        assert!(validate_features(Some(true), Some(&[])).is_ok());
+        assert!(validate_features(None, Some(&features)).is_ok());
+
+        let err = validate_features(Some(true), Some(&features)).unwrap_err();
+        assert!(
+            err.message.contains("`all_features` and `features`"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_require() {
+        assert!(require(("allow_dirty", false), ("fix", false), "").is_ok());
+        assert!(require(("allow_dirty", true), ("fix", true), "").is_ok());
+
+        let err = require(("allow_dirty", true), ("fix", false), "set `fix: true`").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Argument `allow_dirty` requires `fix`: set `fix: true`"
+        );
+    }
+}
@@ -1,11 +1,19 @@
+pub mod apply_suggestions;
 pub mod cargo;
 #[cfg(feature = "cargo-deny")]
 pub mod cargo_deny;
 #[cfg(feature = "cargo-hack")]
 pub mod cargo_hack;
+#[cfg(feature = "cargo-insta")]
+pub mod cargo_insta;
+#[cfg(feature = "cargo-llvm-cov")]
+pub mod cargo_llvm_cov;
 #[cfg(feature = "cargo-machete")]
 pub mod cargo_machete;
+#[cfg(feature = "cargo-nextest")]
+pub mod cargo_nextest;
 pub mod common;
+pub mod process;
 pub mod rustc;
 #[cfg(feature = "rustup")]
 pub mod rustup;
@@ -18,8 +26,33 @@ pub fn set_workspace_root(root: String) {
         .expect("Workspace root can only be set once");
 }
 
+/// Directory the tools run in, or the current directory if no workspace root was set.
+pub(crate) fn workspace_root() -> std::path::PathBuf {
+    WORKSPACE_ROOT
+        .get()
+        .map(std::path::PathBuf::from)
+        .unwrap_or_default()
+}
+
 pub fn apply_workspace_root(cmd: &mut std::process::Command) {
     if let Some(root) = WORKSPACE_ROOT.get() {
         cmd.current_dir(root);
     }
 }
+
+/// Target directory of the workspace, where cargo and the tools it runs write their output.
+pub(crate) fn target_directory() -> std::path::PathBuf {
+    let mut cmd = std::process::Command::new("cargo");
+    cmd.args(["metadata", "--format-version", "1", "--no-deps"]);
+    apply_workspace_root(&mut cmd);
+    cmd.output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok())
+        .and_then(|metadata| {
+            metadata["target_directory"]
+                .as_str()
+                .map(std::path::PathBuf::from)
+        })
+        .unwrap_or_else(|| workspace_root().join("target"))
+}
@@ -0,0 +1,569 @@
+use std::{fmt::Write, path::PathBuf, time::Duration};
+
+use rmcp::{
+    ErrorData,
+    model::{AnnotateAble, Annotations},
+};
+
+use crate::{
+    Response, Tool,
+    command::{Stderr, text_with_description},
+    diagnostics::filter_cargo_status,
+    process::{self, Process},
+    serde_utils::deserialize_string,
+    tools::{cargo::RunTarget, workspace_root},
+};
+
+/// Longest wait for a background process to get ready.
+const MAX_READY_TIMEOUT_SECS: u64 = 600;
+
+/// Output returned per stream when a process starts, exits or is stopped.
+const TAIL_BYTES: usize = 4000;
+
+/// Output returned per stream by `process-output` by default.
+const DEFAULT_READ_BYTES: usize = 10_000;
+
+/// Largest amount of output `process-output` returns per stream.
+const MAX_READ_BYTES: usize = 100_000;
+
+/// State of a background process, returned as `structured_content`.
+#[derive(Debug, serde::Serialize)]
+struct ProcessInfo {
+    id: u32,
+    pid: u32,
+    command: String,
+    running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+    /// Seconds since the process was started
+    uptime_secs: u64,
+    /// Offset after the last byte of stdout, to read only newer output next time
+    stdout_end: usize,
+    stderr_end: usize,
+    /// Cargo's output while the program is built or when the build failed, for processes started
+    /// with `cargo run`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_output: Option<String>,
+}
+
+impl ProcessInfo {
+    fn of(process: &mut Process) -> Self {
+        let exit = process.exit_status();
+        Self {
+            id: process.id,
+            pid: process.pid(),
+            command: process.command.clone(),
+            running: exit.is_none(),
+            exit_code: exit.and_then(|status| status.code()),
+            uptime_secs: process.started.elapsed().as_secs(),
+            stdout_end: lock(&process.stdout).end(),
+            stderr_end: lock(&process.stderr).end(),
+            build_output: pending_build_output(process),
+        }
+    }
+
+    fn render(&self) -> String {
+        let state = match (self.running, self.exit_code) {
+            (true, _) if self.build_output.is_some() => "building".to_owned(),
+            (true, _) => "running".to_owned(),
+            (false, Some(code)) => format!("exited with code {code}"),
+            (false, None) => "terminated by a signal".to_owned(),
+        };
+        format!(
+            "#{} pid {} {state}, started {}s ago, stdout {} bytes, stderr {} bytes: `{}`",
+            self.id, self.pid, self.uptime_secs, self.stdout_end, self.stderr_end, self.command
+        )
+    }
+}
+
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Cargo's output until it started the program, without progress lines, while the program is
+/// built or when the build failed.
+fn pending_build_output(process: &Process) -> Option<String> {
+    let build_output = lock(process.build_output.as_ref()?).clone();
+    let started = build_output
+        .lines()
+        .any(|line| line.trim_start().starts_with("Running `"));
+    if started {
+        return None;
+    }
+    Some(filter_cargo_status(Stderr(build_output)).map_or_else(String::new, |stderr| stderr.0))
+}
+
+/// Adds the last output of both streams to the response.
+fn add_tail(response: &mut Response, process: &Process) {
+    for (name, log) in [("stdout", &process.stdout), ("stderr", &process.stderr)] {
+        let (_, _, text) = lock(log).read(None, TAIL_BYTES);
+        if !text.is_empty() {
+            response.add_content(
+                text_with_description(text, format!("last {name} of the process"))
+                    .annotate(Annotations::default()),
+            );
+        }
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ProcessStartRequest {
+    #[serde(flatten)]
+    target: RunTarget,
+
+    /// Path of an already built executable to run instead of `cargo run`, relative to the
+    /// workspace root, e.g. `target/debug/server`. Only `args` and `env` apply to it.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    executable: Option<String>,
+
+    /// Run with `RUST_BACKTRACE=1`, so panics print a backtrace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    backtrace: Option<bool>,
+
+    /// Wait until the program prints this text on stdout or stderr, e.g. `Listening on`, before
+    /// returning. By default, returns right after starting it.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_string"
+    )]
+    ready_pattern: Option<String>,
+
+    /// How long to wait for `ready_pattern`, including the build. Defaults to 120, at most 600.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ready_timeout_secs: Option<u64>,
+}
+
+impl ProcessStartRequest {
+    fn build_cmd(&self) -> Result<std::process::Command, ErrorData> {
+        let mut cmd = match &self.executable {
+            Some(executable) => self
+                .target
+                .build_executable_cmd(&resolve_executable(executable)?),
+            None => self.target.build_cmd()?,
+        };
+        if self.backtrace.unwrap_or(false) {
+            cmd.env("RUST_BACKTRACE", "1");
+        }
+        Ok(cmd)
+    }
+}
+
+/// Resolves an executable path, which must be inside the workspace.
+fn resolve_executable(executable: &str) -> Result<PathBuf, ErrorData> {
+    let root = workspace_root();
+    let root = root
+        .canonicalize()
+        .or_else(|_| std::env::current_dir())
+        .map_err(|e| ErrorData::internal_error(e.to_string(), None))?;
+    let path = root.join(executable).canonicalize().map_err(|e| {
+        ErrorData::invalid_params(
+            format!("Executable `{executable}` not found: {e}. Build it first with #cargo-build"),
+            None,
+        )
+    })?;
+    if !path.starts_with(&root) {
+        return Err(ErrorData::invalid_params(
+            format!("Executable `{executable}` is outside the workspace"),
+            None,
+        ));
+    }
+    Ok(path)
+}
+
+pub struct ProcessStartRmcpTool;
+
+impl Tool for ProcessStartRmcpTool {
+    const NAME: &'static str = "process-start";
+    const TITLE: &'static str = "Start a background process";
+    const DESCRIPTION: &'static str = "Starts a binary or example of the workspace in the background with `cargo run`, or an already built executable, e.g. a web service to test. Optionally waits until it prints a line that shows it is ready. The process runs until it is stopped or the session ends.";
+    type RequestArgs = ProcessStartRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        request.target.validate()?;
+        if request.executable.is_some()
+            && let Some(option) = request.target.cargo_option()
+        {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "Conflicting arguments `executable` and `{option}`: `{option}` only applies to `cargo run`. Remove it, or drop `executable` to build and run with cargo"
+                ),
+                None,
+            ));
+        }
+        if request
+            .ready_timeout_secs
+            .is_some_and(|secs| secs == 0 || secs > MAX_READY_TIMEOUT_SECS)
+        {
+            return Err(ErrorData::invalid_params(
+                format!("`ready_timeout_secs` must be between 1 and {MAX_READY_TIMEOUT_SECS}"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let id = process::start(request.build_cmd()?, request.executable.is_none())?;
+
+        let ready = match &request.ready_pattern {
+            Some(pattern) => {
+                let timeout = Duration::from_secs(request.ready_timeout_secs.unwrap_or(120));
+                Some(process::wait_until(id, timeout, |process| {
+                    lock(&process.stdout).contains(pattern)
+                        || lock(&process.stderr).contains(pattern)
+                })?)
+            }
+            None => None,
+        };
+
+        process::with_process(id, |process| {
+            let info = ProcessInfo::of(process);
+            let mut summary = format!("Started {}", info.render());
+            match ready {
+                Some(true) => summary.push_str("\nThe process is ready"),
+                Some(false) if info.running => {
+                    let _ = write!(
+                        summary,
+                        "\nThe process did not print `{}` in time, it is still running",
+                        request.ready_pattern.as_deref().unwrap_or_default()
+                    );
+                }
+                _ => {}
+            }
+
+            let mut response = Response::default();
+            response.add_content(
+                text_with_description(summary, "background process")
+                    .annotate(Annotations::default()),
+            );
+            if let Some(build_output) = info.build_output.as_ref().filter(|b| !b.is_empty()) {
+                response.add_content(
+                    text_with_description(build_output.clone(), "build output")
+                        .annotate(Annotations::default()),
+                );
+            }
+            if ready.is_some() || !info.running {
+                add_tail(&mut response, process);
+            }
+            if info.running {
+                response.add_recommendation(format!(
+                    "Read its output with #{} and stop it with #{} once done",
+                    ProcessOutputRmcpTool::NAME,
+                    ProcessStopRmcpTool::NAME
+                ));
+            } else {
+                response.add_recommendation(format!(
+                    "The process exited, remove it with #{}",
+                    ProcessStopRmcpTool::NAME
+                ));
+            }
+            response.set_structured_content(
+                serde_json::to_value(&info).expect("process info is always serializable"),
+            );
+            response
+        })
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ProcessListRequest {}
+
+pub struct ProcessListRmcpTool;
+
+impl Tool for ProcessListRmcpTool {
+    const NAME: &'static str = "process-list";
+    const TITLE: &'static str = "List background processes";
+    const DESCRIPTION: &'static str =
+        "Lists the background processes started with process-start, running or exited.";
+    type RequestArgs = ProcessListRequest;
+
+    fn call_rmcp_tool(&self, _request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let mut processes = Vec::new();
+        process::for_each_process(|process| processes.push(ProcessInfo::of(process)));
+
+        let text = if processes.is_empty() {
+            "No background processes".to_owned()
+        } else {
+            processes
+                .iter()
+                .map(ProcessInfo::render)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let mut response = Response::default();
+        response.add_content(
+            text_with_description(text, "background processes").annotate(Annotations::default()),
+        );
+        response.set_structured_content(serde_json::json!({ "processes": processes }));
+        Ok(response)
+    }
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ProcessOutputRequest {
+    /// Id of the process, as reported by `process-start`.
+    id: u32,
+
+    /// Read stdout from this offset, e.g. the `stdout_end` of the previous call to get only new
+    /// output. By default, the last `max_bytes` are returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stdout_offset: Option<usize>,
+
+    /// Read stderr from this offset, e.g. the `stderr_end` of the previous call to get only new
+    /// output. By default, the last `max_bytes` are returned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stderr_offset: Option<usize>,
+
+    /// Most bytes returned per stream. Defaults to 10000, at most 100000.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_bytes: Option<usize>,
+}
+
+/// Output read from one stream.
+#[derive(Debug, serde::Serialize)]
+struct StreamOutput {
+    /// Offset of the first byte returned, later than the requested offset if that output was
+    /// dropped because the process printed a lot since
+    offset: usize,
+    text: String,
+    /// Offset to continue reading from
+    next_offset: usize,
+}
+
+pub struct ProcessOutputRmcpTool;
+
+impl Tool for ProcessOutputRmcpTool {
+    const NAME: &'static str = "process-output";
+    const TITLE: &'static str = "Read background process output";
+    const DESCRIPTION: &'static str = "Reads the stdout and stderr of a background process, the latest output or from given offsets to follow it.";
+    type RequestArgs = ProcessOutputRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        if request
+            .max_bytes
+            .is_some_and(|bytes| bytes == 0 || bytes > MAX_READ_BYTES)
+        {
+            return Err(ErrorData::invalid_params(
+                format!("`max_bytes` must be between 1 and {MAX_READ_BYTES}"),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let max_bytes = request.max_bytes.unwrap_or(DEFAULT_READ_BYTES);
+        process::with_process(request.id, |process| {
+            let info = ProcessInfo::of(process);
+            let mut response = Response::default();
+            response.add_content(
+                text_with_description(info.render(), "background process")
+                    .annotate(Annotations::default()),
+            );
+            if let Some(build_output) = info.build_output.as_ref().filter(|b| !b.is_empty()) {
+                response.add_content(
+                    text_with_description(build_output.clone(), "build output")
+                        .annotate(Annotations::default()),
+                );
+            }
+
+            let mut streams = serde_json::Map::new();
+            for (name, log, offset) in [
+                ("stdout", &process.stdout, request.stdout_offset),
+                ("stderr", &process.stderr, request.stderr_offset),
+            ] {
+                let (offset, next_offset, text) = lock(log).read(offset, max_bytes);
+                let output = StreamOutput {
+                    offset,
+                    next_offset,
+                    text,
+                };
+                if !output.text.is_empty() {
+                    response.add_content(
+                        text_with_description(
+                            output.text.clone(),
+                            format!(
+                                "{name} from offset {} to {}",
+                                output.offset, output.next_offset
+                            ),
+                        )
+                        .annotate(Annotations::default()),
+                    );
+                }
+                streams.insert(
+                    name.to_owned(),
+                    serde_json::to_value(&output).expect("stream output is always serializable"),
+                );
+            }
+            streams.insert(
+                "process".to_owned(),
+                serde_json::to_value(&info).expect("process info is always serializable"),
+            );
+            response.set_structured_content(streams.into());
+            response
+        })
+    }
+}
+
+/// Signals that can be sent to a background process.
+const SIGNALS: [&str; 7] = ["TERM", "INT", "HUP", "QUIT", "KILL", "USR1", "USR2"];
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ProcessSignalRequest {
+    /// Id of the process, as reported by `process-start`.
+    id: u32,
+
+    /// Signal to send: `TERM`, `INT`, `HUP`, `QUIT`, `KILL`, `USR1` or `USR2`. Only `KILL` is
+    /// supported on Windows.
+    signal: String,
+}
+
+pub struct ProcessSignalRmcpTool;
+
+impl Tool for ProcessSignalRmcpTool {
+    const NAME: &'static str = "process-signal";
+    const TITLE: &'static str = "Signal a background process";
+    const DESCRIPTION: &'static str = "Sends a signal to a background process and the processes it started, e.g. `HUP` to reload its configuration.";
+    type RequestArgs = ProcessSignalRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        if !SIGNALS.contains(&signal_name(&request.signal)) {
+            return Err(ErrorData::invalid_params(
+                format!(
+                    "Unknown signal `{}`, expected one of {}",
+                    request.signal,
+                    SIGNALS.join(", ")
+                ),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let signal = signal_name(&request.signal);
+        process::with_process(request.id, |process| {
+            let sent = process.exit_status().is_none() && process.signal(signal);
+            let text = if sent {
+                format!("Sent {signal} to process #{}", process.id)
+            } else {
+                format!("Could not send {signal} to process #{}", process.id)
+            };
+            let mut response = Response::default();
+            response.add_content(
+                text_with_description(text, "signal").annotate(Annotations::default()),
+            );
+            let info = ProcessInfo::of(process);
+            response.add_content(
+                text_with_description(info.render(), "background process")
+                    .annotate(Annotations::default()),
+            );
+            response.set_structured_content(serde_json::json!({ "sent": sent, "process": info }));
+            response
+        })
+    }
+}
+
+/// `TERM` for `TERM`, `SIGTERM` or `sigterm`.
+fn signal_name(signal: &str) -> &str {
+    let signal = signal.trim();
+    let signal = signal
+        .strip_prefix("SIG")
+        .or_else(|| signal.strip_prefix("sig"))
+        .unwrap_or(signal);
+    SIGNALS
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(signal))
+        .copied()
+        .unwrap_or(signal)
+}
+
+#[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
+pub struct ProcessStopRequest {
+    /// Id of the process, as reported by `process-start`.
+    id: u32,
+
+    /// Seconds to wait after `TERM` before the process is killed. Defaults to 5, at most 60. `0`
+    /// kills it right away.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    grace_secs: Option<u64>,
+}
+
+pub struct ProcessStopRmcpTool;
+
+impl Tool for ProcessStopRmcpTool {
+    const NAME: &'static str = "process-stop";
+    const TITLE: &'static str = "Stop a background process";
+    const DESCRIPTION: &'static str = "Stops a background process, terminating it gracefully and killing it if it does not exit in time, and returns its last output. Also removes exited processes from the list.";
+    type RequestArgs = ProcessStopRequest;
+
+    fn validate(&self, request: &Self::RequestArgs) -> Result<(), ErrorData> {
+        if request.grace_secs.is_some_and(|secs| secs > 60) {
+            return Err(ErrorData::invalid_params(
+                "`grace_secs` must be at most 60",
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    fn call_rmcp_tool(&self, request: Self::RequestArgs) -> Result<Response, ErrorData> {
+        let grace = Duration::from_secs(request.grace_secs.unwrap_or(5));
+        let mut process = process::stop(request.id, grace)?;
+        let info = ProcessInfo::of(&mut process);
+
+        let mut response = Response::default();
+        response.add_content(
+            text_with_description(format!("Stopped {}", info.render()), "background process")
+                .annotate(Annotations::default()),
+        );
+        add_tail(&mut response, &process);
+        response.set_structured_content(
+            serde_json::to_value(&info).expect("process info is always serializable"),
+        );
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_name() {
+        assert_eq!(signal_name("TERM"), "TERM");
+        assert_eq!(signal_name("sigterm"), "TERM");
+        assert_eq!(signal_name(" SIGHUP "), "HUP");
+        assert_eq!(signal_name("STOP"), "STOP");
+    }
+
+    #[test]
+    fn test_validate_start() {
+        let request = |json| serde_json::from_value::<ProcessStartRequest>(json).unwrap();
+        let tool = ProcessStartRmcpTool;
+        assert!(
+            tool.validate(&request(serde_json::json!({
+                "executable": "target/debug/server",
+                "args": ["--port", "0"],
+            })))
+            .is_ok()
+        );
+        let err = tool
+            .validate(&request(serde_json::json!({
+                "executable": "target/debug/server",
+                "release": true,
+            })))
+            .unwrap_err();
+        assert!(
+            err.message.contains("`executable` and `release`"),
+            "{err:?}"
+        );
+    }
+}
@@ -5,6 +5,7 @@ use rmcp::ErrorData;
 
 #[derive(Debug, ::serde::Deserialize, ::schemars::JsonSchema)]
 pub struct RustcExplainRequest {
+    /// Error code to explain, e.g. `E0382`.
     pub error_code: String,
 }
 
@@ -129,7 +129,8 @@ impl Tool for RustupToolchainAddRmcpTool {
 
 #[derive(Debug, ::serde::Deserialize, schemars::JsonSchema)]
 pub struct RustupUpdateRequest {
-    /// Toolchain name to update, such as 'stable', 'nightly', or '1.8.0'. If not specified, updates all installed toolchains
+    /// Toolchain name to update, such as 'stable', 'nightly', or '1.8.0'. If not specified, updates
+    /// all installed toolchains
     #[serde(default, deserialize_with = "deserialize_string")]
     pub toolchain: Option<String>,
 